@@ -65,6 +65,7 @@ class AchievementManager {
                         <div class="achievement-info">
                             <div class="achievement-name">${achievement.name}</div>
                             <div class="achievement-description">${achievement.description}</div>
+                            ${this.formatRewards(achievement)}
                             ${!isUnlocked ? `
                                 <div class="achievement-progress">
                                     <div class="progress-bar">
@@ -89,6 +90,27 @@ class AchievementManager {
         this.checkNewUnlocks(achievements);
     }
 
+    // 成就奖励：永久加成与一次性奖励
+    formatRewards(achievement) {
+        const parts = (achievement.effects || [])
+            .map(effect => (typeof formatUpgradeEffect === 'function' ? formatUpgradeEffect(effect) : ''));
+        (achievement.rewards || []).forEach(reward => {
+            switch (reward.type) {
+                case 'grant':
+                    parts.push(`+${reward.amount} ${typeof resourceName === 'function' ? resourceName(reward.resource) : reward.resource}`);
+                    break;
+                case 'unlock_recipe':
+                    parts.push(`解锁配方 ${reward.recipe}`);
+                    break;
+                case 'unlock_feature':
+                    parts.push(`解锁 ${reward.feature}`);
+                    break;
+            }
+        });
+        const text = parts.filter(Boolean).join('，');
+        return text ? `<div class="achievement-reward">奖励：${text}</div>` : '';
+    }
+
     getCategoryName(category) {
         const categoryNames = {
             'clicks': '点击',
@@ -195,6 +217,15 @@ class AchievementManager {
         }, 5000);
     }
 
+    // Called for AchievementUnlocked events drained from the game
+    notifyUnlocked(event) {
+        if (this.notifiedAchievements.has(event.id)) {
+            return;
+        }
+        this.notifiedAchievements.add(event.id);
+        this.showNotification(event);
+    }
+
     notifyAchievement(achievementId) {
         const achievements = this.update();
         const achievement = achievements.find(a => a.id === achievementId);
@@ -212,9 +243,6 @@ window.updateAchievementsPanel = function() {
         if (achievementsTab && achievementsTab.classList.contains('active')) {
             window.achievementManager.update();
             window.achievementManager.renderAchievements('achievements-list');
-        } else {
-            window.achievementManager.update();
-            window.achievementManager.checkNewUnlocks(window.achievementManager.update());
         }
     }
 };
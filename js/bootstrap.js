@@ -4,9 +4,22 @@ async function initWasm() {
         // 动态导入生成的WASM绑定
         const init = await import('../pkg/idle_game.js');
         const wasm = await init.default();
+
+        // 大数格式化：value 为数字或存档中的 "1.5e300" 字符串，notation 为 short / scientific / engineering
+        window.formatNumber = (value, notation) =>
+            init.format_number(value, notation || window.numberNotation || 'short');
+        
+        // 初始化游戏；页面可通过 window.GAME_CONTENT_OVERRIDE 覆盖默认内容定义
+        const contentOverride = window.GAME_CONTENT_OVERRIDE;
+        const game = contentOverride
+            ? init.init_game_with_content(
+                typeof contentOverride === 'string' ? contentOverride : JSON.stringify(contentOverride))
+            : init.init_game();
         
-        // 初始化游戏
-        const game = init.init_game();
+        // 页面可通过 window.SAVE_STORAGE_BACKEND 提供同步的 getItem / setItem / removeItem 存储后端
+        if (window.SAVE_STORAGE_BACKEND) {
+            game.setStorageBackend(window.SAVE_STORAGE_BACKEND);
+        }
         
         // 尝试从 localStorage 加载存档
         let gameLoaded = false;
@@ -18,6 +31,11 @@ async function initWasm() {
                 console.log('   Coins:', game.get_coins());
                 console.log('   Wood:', game.get_wood());
                 console.log('   Stone:', game.get_stone());
+                const migration = game.get_migration_report();
+                if (migration && (migration.steps.length || migration.content_added.length)) {
+                    console.log(`   Migrated save v${migration.from_version} → v${migration.to_version}:`,
+                        migration.steps, migration.content_added);
+                }
             } else {
                 console.log('ℹ️ No saved game found, starting new game');
             }
@@ -52,12 +70,17 @@ async function initWasm() {
         if (game && typeof game.update_ui === 'function') {
             game.update_ui();
         }
+        window.processGameEvents();
         
         // 更新 i18n 翻译（如果存在）
         if (window.i18n) {
             window.i18n.updateAllTranslations();
         }
         
+        if (window.renderSaveSlots) {
+            window.renderSaveSlots();
+        }
+        
         console.log('Idle game initialized successfully!');
         
         // 启动游戏主循环
@@ -77,6 +100,8 @@ function startGameLoop(game) {
         if (game && typeof game.game_loop === 'function') {
             game.game_loop();
         }
+        window.processGameEvents();
+        checkOfflineReport(game);
         if (window.updateStatisticsPanel) {
             window.updateStatisticsPanel();
         }
@@ -92,6 +117,9 @@ function startGameLoop(game) {
         if (window.updateCoinButton) {
             window.updateCoinButton();
         }
+        if (window.updatePrestigePanel) {
+            window.updatePrestigePanel();
+        }
     }, 1000);
     
     // 自动保存 - 每 15 秒保存一次
@@ -105,6 +133,81 @@ function startGameLoop(game) {
             }
         }
     }, 15000); // 15 seconds
+
+    // 标签页恢复可见时立即结算离线收益
+    document.addEventListener('visibilitychange', () => {
+        if (document.visibilityState === 'visible' && game && typeof game.game_loop === 'function') {
+            game.game_loop();
+            window.processGameEvents();
+            checkOfflineReport(game);
+        }
+    });
+}
+
+// 领取离线收益报告并显示
+function checkOfflineReport(game) {
+    if (!game || typeof game.take_offline_report !== 'function') {
+        return;
+    }
+    try {
+        const report = game.take_offline_report();
+        if (report) {
+            showOfflineReport(report);
+        }
+    } catch (error) {
+        console.error('Failed to read offline report:', error);
+    }
+}
+
+function showOfflineReport(report) {
+    const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
+
+    const existing = document.getElementById('offline-notification');
+    if (existing) {
+        existing.remove();
+    }
+
+    const hours = Math.floor(report.duration_seconds / 3600);
+    const minutes = Math.floor((report.duration_seconds % 3600) / 60);
+    const resources = report.resources_gained || {};
+    const gained = Object.keys(resources)
+        .filter(resource => resources[resource] > 0)
+        .map(resource => `${t(resource) || resource} +${window.formatAmount(resources[resource])}`)
+        .join(', ');
+    const levels = (report.worker_levels_gained || [])
+        .map(gain => `${gain.worker} +${gain.levels}`)
+        .join(', ');
+    const crafts = (report.crafts_completed || []).length;
+
+    const notification = document.createElement('div');
+    notification.id = 'offline-notification';
+    notification.className = 'achievement-notification';
+    notification.innerHTML = `
+        <div class="notification-content">
+            <div class="notification-icon">💤</div>
+            <div class="notification-text">
+                <div class="notification-title">${t('offlineProgressTitle') || '离线收益'}</div>
+                <div class="notification-name">${hours}h ${minutes}m (${Math.round(report.efficiency * 100)}%)</div>
+                <div class="notification-description">${gained || '-'}</div>
+                ${levels ? `<div class="notification-description">${t('workerLevelsGained') || '工人升级'}: ${levels}</div>` : ''}
+                ${crafts ? `<div class="notification-description">${t('craftsCompleted') || '完成合成'}: ${crafts}</div>` : ''}
+            </div>
+        </div>
+    `;
+
+    document.body.appendChild(notification);
+
+    requestAnimationFrame(() => {
+        notification.classList.add('show');
+    });
+
+    setTimeout(() => {
+        notification.classList.remove('show');
+        notification.classList.add('hide');
+        setTimeout(() => {
+            notification.remove();
+        }, 300);
+    }, 8000);
 }
 
 // 页面加载完成后初始化游戏
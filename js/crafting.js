@@ -28,12 +28,21 @@ class CraftingManager {
     /**
      * Craft a recipe by ID
      * @param {string} recipeId - The recipe ID to craft
+     * @param {number|string} times - Crafts in the batch, or 'max' for as many as affordable
      * @returns {boolean} Success status
      */
-    craft(recipeId) {
+    craft(recipeId, times = 1) {
         if (this.rustGame && typeof this.rustGame.craft_resource === 'function') {
             try {
-                const success = this.rustGame.craft_resource(recipeId);
+                let success;
+                if (times === 'max') {
+                    success = this.rustGame.craft_max(recipeId) > 0;
+                } else if (times > 1) {
+                    success = this.rustGame.craft_resource_times(recipeId, times) > 0;
+                } else {
+                    success = this.rustGame.craft_resource(recipeId);
+                }
+                window.processGameEvents();
                 if (!success) {
                     console.warn(`Crafting failed for recipe: ${recipeId}`);
                 }
@@ -46,19 +55,74 @@ class CraftingManager {
         return false;
     }
 
+    /**
+     * Cancel a queued craft, refunding its inputs
+     * @param {number} index - Position of the craft in the queue
+     * @returns {boolean} Success status
+     */
+    cancel(index) {
+        if (this.rustGame && typeof this.rustGame.cancel_craft === 'function') {
+            const success = this.rustGame.cancel_craft(index);
+            window.processGameEvents();
+            this.renderRecipes();
+            return success;
+        }
+        return false;
+    }
+
+    /**
+     * Render the crafts in progress or waiting for a slot
+     * @returns {string} Queue HTML, empty when nothing is queued
+     */
+    renderQueue() {
+        if (!this.rustGame || typeof this.rustGame.get_crafting_queue !== 'function') {
+            return '';
+        }
+        const queue = this.rustGame.get_crafting_queue() || [];
+        if (queue.length === 0) {
+            return '';
+        }
+        const slots = this.rustGame.get_crafting_slots();
+        const jobs = queue.map((job, index) => {
+            const recipe = this.currentRecipes.find(r => r.id === job.recipe_id);
+            const percentage = job.duration > 0 ? Math.min(100, (job.progress / job.duration) * 100) : 100;
+            const remaining = Math.ceil(Math.max(0, job.duration - job.progress));
+            const status = index < slots ? `${remaining}s` : '等待中';
+            return `
+                <div class="crafting-job" id="crafting-job-${index}">
+                    <span class="recipe-name">${recipe ? recipe.name : job.recipe_id}${job.count > 1 ? ` ×${job.count}` : ''}</span>
+                    <div class="progress-bar">
+                        <div class="progress-bar-fill" style="width: ${percentage}%"></div>
+                    </div>
+                    <span class="progress-text">${status}</span>
+                    <button type="button" onclick="window.craftingManager.cancel(${index})">取消</button>
+                </div>
+            `;
+        }).join('');
+        return `
+            <div class="crafting-queue">
+                <h4>合成队列（${queue.length}，同时进行 ${slots}）</h4>
+                ${jobs}
+            </div>
+        `;
+    }
+
     /**
      * Show confirmation dialog before crafting
      * @param {Object} recipe - The recipe to confirm
+     * @param {number|string} times - Crafts in the batch, or 'max'
      * @returns {Promise<boolean>} User confirmation result
      */
-    showConfirmationDialog(recipe) {
+    showConfirmationDialog(recipe, times = 1) {
         return new Promise((resolve) => {
             const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
             
-            const inputText = this.getResourceAmountText(recipe.input_resource, recipe.input_amount);
-            const outputText = this.getResourceAmountText(recipe.output_resource, recipe.output_amount);
+            const inputText = this.getItemsText(recipe.inputs);
+            const outputText = this.getOutputsText(recipe);
             
-            const message = `确认合成？\n消耗：${inputText}\n获得：${outputText}`;
+            const durationText = recipe.duration > 0 ? `\n耗时：${recipe.duration}s` : '';
+            const timesText = times === 'max' ? '（最大数量）' : (times > 1 ? ` ×${times}` : '');
+            const message = `确认合成${timesText}？\n每次消耗：${inputText}\n每次获得：${outputText}${durationText}`;
             
             if (confirm(message)) {
                 resolve(true);
@@ -75,18 +139,44 @@ class CraftingManager {
      * @returns {string} Formatted resource text
      */
     getResourceAmountText(resource, amount) {
-        const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
-        
-        let resourceName = resource;
-        if (resource === 'coins') {
-            resourceName = t('coins') || '金币';
-        } else if (resource === 'wood') {
-            resourceName = t('wood') || '木头';
-        } else if (resource === 'stone') {
-            resourceName = t('stone') || '石头';
+        const name = typeof resourceName === 'function' ? resourceName(resource) : resource;
+        return `${Math.floor(amount)} ${name}`;
+    }
+
+    /**
+     * Get formatted text for a list of recipe items, e.g. "10 木头 + 2 石头"
+     * @param {Array} items - Items with resource and amount
+     * @returns {string} Formatted items text
+     */
+    getItemsText(items) {
+        return (items || []).map(item => this.getResourceAmountText(item.resource, item.amount)).join(' + ');
+    }
+
+    /**
+     * Get formatted outputs of a recipe, bonus outputs with their chance
+     * @param {Object} recipe - The recipe
+     * @returns {string} Formatted outputs text
+     */
+    getOutputsText(recipe) {
+        const bonuses = (recipe.bonus_outputs || []).map(bonus =>
+            `${Math.round(bonus.chance * 100)}% +${this.getResourceAmountText(bonus.resource, bonus.amount)}`);
+        return [this.getItemsText(recipe.outputs)].concat(bonuses).join('，');
+    }
+
+    /**
+     * Name of the building the recipe needs but is not owned; null otherwise
+     * @param {Object} recipe - The recipe to check
+     * @returns {string|null} Missing building name
+     */
+    missingBuildingName(recipe) {
+        if (!recipe.requires_building || !this.rustGame || typeof this.rustGame.get_buildings !== 'function') {
+            return null;
         }
-        
-        return `${Math.floor(amount)} ${resourceName}`;
+        const building = (this.rustGame.get_buildings() || []).find(b => b.id === recipe.requires_building);
+        if (building && building.count > 0) {
+            return null;
+        }
+        return building ? building.name : recipe.requires_building;
     }
 
     /**
@@ -98,16 +188,8 @@ class CraftingManager {
         if (!this.rustGame) return false;
         
         try {
-            let currentAmount = 0;
-            if (recipe.input_resource === 'coins') {
-                currentAmount = this.rustGame.get_coins();
-            } else if (recipe.input_resource === 'wood') {
-                currentAmount = this.rustGame.get_wood();
-            } else if (recipe.input_resource === 'stone') {
-                currentAmount = this.rustGame.get_stone();
-            }
-            
-            return currentAmount >= recipe.input_amount;
+            return (recipe.inputs || []).every(input =>
+                this.rustGame.get_resource(input.resource) >= input.amount);
         } catch (error) {
             console.error('Error checking resources:', error);
             return false;
@@ -117,8 +199,9 @@ class CraftingManager {
     /**
      * Handle craft button click
      * @param {string} recipeId - Recipe ID to craft
+     * @param {number|string} times - Crafts in the batch, or 'max'
      */
-    async handleCraftClick(recipeId) {
+    async handleCraftClick(recipeId, times = 1) {
         const recipe = this.currentRecipes.find(r => r.id === recipeId);
         if (!recipe) {
             console.warn(`Recipe ${recipeId} not found`);
@@ -126,27 +209,32 @@ class CraftingManager {
         }
 
         // Check if recipe is unlocked
-        if (!recipe.unlocked) {
+        if (!recipe.unlocked || (window.lockedFeatureName && window.lockedFeatureName(recipe.requires))) {
             console.warn(`Recipe ${recipeId} is not unlocked`);
             return;
         }
 
+        const missingBuilding = this.missingBuildingName(recipe);
+        if (missingBuilding) {
+            alert(`需要建筑：${missingBuilding}`);
+            return;
+        }
+
         // Check resources first
         if (!this.hasEnoughResources(recipe)) {
-            const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
-            const inputText = this.getResourceAmountText(recipe.input_resource, recipe.input_amount);
+            const inputText = this.getItemsText(recipe.inputs);
             alert(`资源不足！需要：${inputText}`);
             return;
         }
 
         // Show confirmation dialog
-        const confirmed = await this.showConfirmationDialog(recipe);
+        const confirmed = await this.showConfirmationDialog(recipe, times);
         if (!confirmed) {
             return;
         }
 
         // Perform crafting
-        const success = this.craft(recipeId);
+        const success = this.craft(recipeId, times);
         
         if (success) {
             // Visual feedback for success
@@ -190,12 +278,14 @@ class CraftingManager {
 
         // Build recipe list HTML
         const recipeElements = recipes.map((recipe, index) => {
-            const isUnlocked = recipe.unlocked !== undefined ? recipe.unlocked : true;
+            const isUnlocked = (recipe.unlocked !== undefined ? recipe.unlocked : true)
+                && !(window.lockedFeatureName && window.lockedFeatureName(recipe.requires));
+            const missingBuilding = this.missingBuildingName(recipe);
             const hasResources = this.hasEnoughResources(recipe);
-            const canCraft = isUnlocked && hasResources;
+            const canCraft = isUnlocked && !missingBuilding && hasResources;
             
-            const inputText = this.getResourceAmountText(recipe.input_resource, recipe.input_amount);
-            const outputText = this.getResourceAmountText(recipe.output_resource, recipe.output_amount);
+            const inputText = this.getItemsText(recipe.inputs);
+            const outputText = this.getOutputsText(recipe);
             
             const statusClass = !isUnlocked ? 'recipe-locked' : (!hasResources ? 'recipe-insufficient' : 'recipe-available');
             const buttonText = isUnlocked ? (t('craft') || '合成') : (t('locked') || '已锁定');
@@ -216,6 +306,8 @@ class CraftingManager {
                             <span class="recipe-label">${t('output') || '获得'}:</span>
                             <span class="recipe-resource">${outputText}</span>
                         </div>
+                        ${recipe.duration > 0 ? `<div class="recipe-duration">耗时：${recipe.duration}s</div>` : ''}
+                        ${missingBuilding ? `<div class="recipe-requirement">需要建筑：${missingBuilding}</div>` : ''}
                     </div>
                     <div class="recipe-actions">
                         <button 
@@ -225,12 +317,16 @@ class CraftingManager {
                             onclick="window.craftingManager.handleCraftClick('${recipe.id}')">
                             ${buttonText}
                         </button>
+                        <button class="craft-button" ${disabledAttr}
+                            onclick="window.craftingManager.handleCraftClick('${recipe.id}', 10)">×10</button>
+                        <button class="craft-button" ${disabledAttr}
+                            onclick="window.craftingManager.handleCraftClick('${recipe.id}', 'max')">${t('craftMax') || '最大'}</button>
                     </div>
                 </div>
             `;
         }).join('');
 
-        container.innerHTML = recipeElements;
+        container.innerHTML = this.renderQueue() + recipeElements;
     }
 }
 
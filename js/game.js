@@ -75,6 +75,81 @@ window.updateResourceDisplay = function(coins, wood, stone, coinsPerSecond, wood
     }
 };
 
+// Display text for an amount: numbers are rounded down, and big values
+// (numbers or "1.5e300" strings from the game) get suffixes or exponents
+window.formatAmount = function(value) {
+    if (typeof value === 'number') {
+        value = isFinite(value) ? Math.floor(value) : 0;
+    }
+    return window.formatNumber ? window.formatNumber(value) : String(value);
+};
+
+// Resources with a fixed slot in the banner; anything else defined in content
+// gets an item created on demand
+const BUILTIN_RESOURCES = ['coins', 'wood', 'stone'];
+
+window.updateExtraResources = function(resources) {
+    const container = document.getElementById('resources');
+    if (!container || !Array.isArray(resources)) {
+        return;
+    }
+
+    resources
+        .filter(resource => !BUILTIN_RESOURCES.includes(resource.id))
+        .forEach(resource => {
+            let item = document.getElementById(`resource-${resource.id}`);
+            if (!item) {
+                item = document.createElement('div');
+                item.className = 'resource-item';
+                item.id = `resource-${resource.id}`;
+                item.innerHTML = '<span class="resource-icon"></span><span class="resource-value"></span>';
+                container.appendChild(item);
+            }
+
+            const perSecond = isFinite(resource.per_second) ? resource.per_second : 0;
+            item.querySelector('.resource-icon').textContent = resource.icon || '';
+            item.querySelector('.resource-value').textContent =
+                `${resource.name}：${window.formatAmount(resource.amount)} (+${perSecond.toFixed(1)}/s)`;
+        });
+};
+
+// Short description of one upgrade effect, e.g. "+1 金币/点击"
+function formatUpgradeEffect(effect) {
+    const t = (key) => (window.i18n ? window.i18n.t(key) : key);
+    const percent = (fraction) => `${Math.round(fraction * 100)}%`;
+    switch (effect.type) {
+        case 'click_bonus':
+            return `+${effect.amount}${t('perClick')}`;
+        case 'click_multiplier':
+            return `+${percent(effect.bonus)}${t('clickPower')}`;
+        case 'autoclicker':
+            return `+${effect.count}${t('autoclickers')}`;
+        case 'resource_rate':
+            return `+${effect.rate} ${resourceName(effect.resource)}${t('perSecond')}`;
+        case 'building_multiplier': {
+            const buildings = window.rustGame ? window.rustGame.get_buildings() || [] : [];
+            const building = buildings.find(b => b.id === effect.building);
+            return `+${percent(effect.bonus)} ${building ? building.name : effect.building}`;
+        }
+        case 'global_multiplier':
+            return `+${percent(effect.bonus)}${t('allProduction')}`;
+        case 'cost_reduction':
+            return `-${percent(effect.fraction)}${t('buildingCost')}`;
+        case 'worker_xp_multiplier':
+            return `+${percent(effect.bonus)}${t('workerXp')}`;
+        case 'starting_resource':
+            return `${t('startingWith')}${effect.amount} ${resourceName(effect.resource)}`;
+        case 'keep_autoclickers':
+            return t('keepAutoclickers');
+        case 'worker_slots':
+            return `+${effect.count}${t('workerSlots')}`;
+        case 'crafting_slots':
+            return `+${effect.count}${t('craftingSlots')}`;
+        default:
+            return '';
+    }
+}
+
 // Function that will be called from Rust/WASM to update upgrades
 window.updateUpgradeButtons = function(upgrades) {
     const upgradeList = document.getElementById('upgrade-list');
@@ -92,35 +167,16 @@ window.updateUpgradeButtons = function(upgrades) {
                 const costText = window.i18n ? window.i18n.t('cost') : 'Cost';
                 const buyText = window.i18n ? window.i18n.t('buy') : 'Buy';
                 
-                // Fix: use production_increase instead of productionIncrease (serde uses snake_case)
-                const productionIncrease = upgrade.production_increase || upgrade.productionIncrease || 0;
-                
-                // Determine the correct unit based on upgrade type
-                let unitText = '';
-                if (upgrade.name === 'Better Click' || upgrade.name === '更好的点击') {
-                    // Better Click increases coins per click
-                    unitText = window.i18n ? window.i18n.t('perClick') : ' coins/click';
-                } else if (upgrade.name.startsWith('Autoclicker') || upgrade.name.startsWith('自动点击器')) {
-                    // Autoclicker now performs real clicks equivalent to coins per click
-                    unitText = window.i18n ? window.i18n.t('perClick') : ' per tick';
-                } else if (upgrade.name === 'Lumberjack Efficiency' || upgrade.name === '伐木工效率') {
-                    // Lumberjack Efficiency increases wood per second
-                    unitText = window.i18n ? window.i18n.t('woodPerSecondShort') : ' wood/sec';
-                } else if (upgrade.name === 'Stone Mason Skill' || upgrade.name === '石匠技能') {
-                    // Stone Mason Skill increases stone per second
-                    unitText = window.i18n ? window.i18n.t('stonePerSecondShort') : ' stone/sec';
-                } else {
-                    // Default fallback
-                    unitText = window.i18n ? window.i18n.t('perSecond') : '/sec';
-                }
+                // Each upgrade describes its own effects
+                const effectText = (upgrade.effects || []).map(formatUpgradeEffect).join(', ');
                 
                 upgradeDiv.innerHTML = `
                     <div>
                         <strong>${upgrade.name}</strong><br>
-                        <small>+${productionIncrease}${unitText}</small>
+                        <small>${effectText}</small>
                     </div>
                     <div>
-                        <span>${costText}: ${Math.floor(upgrade.cost)}</span>
+                        <span>${costText}: ${window.formatAmount(upgrade.cost)}</span>
                         <button id="buy-upgrade-${index}" 
                                 onclick="window.buyUpgrade(${index})"
                                 ${!window.gameInitialized ? 'disabled' : ''}>
@@ -147,7 +203,7 @@ window.updateUpgradeButtons = function(upgrades) {
                     const buyText = window.i18n ? window.i18n.t('buy') : 'Buy';
                     
                     if (costSpan) {
-                        costSpan.textContent = `${costText}: ${Math.floor(upgrade.cost)}`;
+                        costSpan.textContent = `${costText}: ${window.formatAmount(upgrade.cost)}`;
                     }
                     
                     if (buyButton) {
@@ -167,6 +223,65 @@ window.updateUpgradeButtons = function(upgrades) {
     }
 };
 
+// Display name of a resource id, falling back to the id itself
+function resourceName(resourceId) {
+    const resources = window.rustGame && typeof window.rustGame.get_resources === 'function'
+        ? window.rustGame.get_resources()
+        : null;
+    const resource = Array.isArray(resources) ? resources.find(r => r.id === resourceId) : null;
+    return resource ? resource.name : resourceId;
+}
+
+// e.g. "+0.2 木头/sec, -1 石头/sec"
+function formatBuildingFlows(building, perSecondText) {
+    const outputs = (building.outputs || []).map(o => `+${o.rate} ${resourceName(o.resource)}${perSecondText}`);
+    const inputs = (building.inputs || []).map(i => `-${i.rate} ${resourceName(i.resource)}${perSecondText}`);
+    return outputs.concat(inputs).join(', ');
+}
+
+// 转换建筑运行的配方与状态，例如 "自动合成 金币换木材 0.2/s · 运行中"
+function formatConverter(building, perSecondText) {
+    const converter = building.converter;
+    if (!converter) {
+        return '';
+    }
+    const recipes = window.rustGame && typeof window.rustGame.get_crafting_recipes === 'function'
+        ? window.rustGame.get_crafting_recipes() || []
+        : [];
+    const recipe = recipes.find(r => r.id === converter.recipe);
+    let status = '运行中';
+    if (converter.locked) {
+        status = `🔒 停工：${recipe ? recipe.name : converter.recipe} 未解锁`;
+    } else if (converter.stalled_on) {
+        status = `⚠️ 停工：缺少${resourceName(converter.stalled_on)}`;
+    }
+    return `自动合成 ${recipe ? recipe.name : converter.recipe} ${converter.rate}${perSecondText} · ${status}`;
+}
+
+// 尚未解锁的所需功能名；无需解锁或已解锁时为 null
+window.lockedFeatureName = function(requires) {
+    const game = window.rustGame;
+    if (!requires || !game || typeof game.get_unlocks !== 'function') {
+        return null;
+    }
+    const feature = (game.get_unlocks() || []).find(f => f.id === requires);
+    if (feature && feature.unlocked) {
+        return null;
+    }
+    return feature ? feature.name : requires;
+};
+
+// 建筑的购买按钮；被锁定时显示所需功能
+function buildingBuyButton(building, index) {
+    const locked = window.lockedFeatureName(building.requires);
+    const buyText = window.i18n ? window.i18n.t('buy') : 'Buy';
+    return `<button id="buy-building-${index}" 
+                    onclick="window.buyBuilding(${index})"
+                    ${!window.gameInitialized || locked ? 'disabled' : ''}>
+                ${locked ? `🔒 ${locked}` : buyText}
+            </button>`;
+}
+
 // Function that will be called from Rust/WASM to update buildings
 window.updateBuildingDisplay = function(buildings) {
     const buildingList = document.getElementById('building-list');
@@ -186,22 +301,19 @@ window.updateBuildingDisplay = function(buildings) {
                 const buyText = window.i18n ? window.i18n.t('buy') : 'Buy';
                 const perSecondText = window.i18n ? window.i18n.t('perSecond') : '/sec';
                 
-                // Fix: use production_rate instead of productionRate (serde uses snake_case)
-                const productionRate = building.production_rate || building.productionRate || 0;
+                // Buildings list their own outputs and inputs per second
+                const flows = formatBuildingFlows(building, perSecondText);
                 
                 buildingDiv.innerHTML = `
                     <div>
                         <strong>${building.name}</strong><br>
-                        <small>${productionRate}${perSecondText}</small>
+                        <small>${flows}</small>
+                        <small class="converter-status" id="converter-status-${index}">${formatConverter(building, perSecondText)}</small>
                     </div>
                     <div>
                         ${ownedText}: ${building.count}<br>
-                        ${costText}: ${Math.floor(building.cost)}
-                        <button id="buy-building-${index}" 
-                                onclick="window.buyBuilding(${index})"
-                                ${!window.gameInitialized ? 'disabled' : ''}>
-                            ${buyText}
-                        </button>
+                        ${costText}: ${window.formatAmount(building.cost)}
+                        ${buildingBuyButton(building, index)}
                     </div>
                 `;
                 buildingList.appendChild(buildingDiv);
@@ -220,14 +332,17 @@ window.updateBuildingDisplay = function(buildings) {
                         
                         secondDiv.innerHTML = `
                             ${ownedText}: ${building.count}<br>
-                            ${costText}: ${Math.floor(building.cost)}
-                            <button id="buy-building-${index}" 
-                                    onclick="window.buyBuilding(${index})"
-                                    ${!window.gameInitialized ? 'disabled' : ''}>
-                                ${window.i18n ? window.i18n.t('buy') : 'Buy'}
-                            </button>
+                            ${costText}: ${window.formatAmount(building.cost)}
+                            ${buildingBuyButton(building, index)}
                         `;
                     }
+                    const converterStatus = document.getElementById(`converter-status-${index}`);
+                    if (converterStatus) {
+                        const perSecondText = window.i18n ? window.i18n.t('perSecond') : '/sec';
+                        converterStatus.textContent = formatConverter(building, perSecondText);
+                        converterStatus.classList.toggle('stalled', Boolean(building.converter
+                            && (building.converter.stalled_on || building.converter.locked)));
+                    }
                 }
             }
             for (let index = 0; index < buildings.length; index++) {
@@ -246,36 +361,144 @@ window.updateBuildingDisplay = function(buildings) {
     }
 };
 
+// Visual feedback for a failed purchase, driven by PurchaseFailed events
+function flashPurchaseFailed(target) {
+    let buttonId = null;
+    if (target.kind === 'upgrade') {
+        buttonId = `buy-upgrade-${target.index}`;
+    } else if (target.kind === 'building') {
+        buttonId = `buy-building-${target.index}`;
+    }
+    const button = buttonId ? document.getElementById(buttonId) : null;
+    if (button) {
+        button.classList.add('purchase-failed');
+        setTimeout(() => {
+            button.classList.remove('purchase-failed');
+        }, 300);
+    }
+}
+
+// Drain the events raised by the Rust game and refresh the affected UI
+window.processGameEvents = function() {
+    const game = window.rustGame;
+    if (!game || typeof game.drain_events !== 'function') {
+        return;
+    }
+
+    let events = [];
+    try {
+        events = game.drain_events() || [];
+    } catch (error) {
+        console.error('Failed to drain game events:', error);
+        return;
+    }
+
+    let resourcesChanged = false;
+    let upgradesChanged = false;
+    let buildingsChanged = false;
+
+    events.forEach(event => {
+        switch (event.type) {
+            case 'ResourceChanged':
+                resourcesChanged = true;
+                break;
+            case 'UpgradeBought':
+                upgradesChanged = true;
+                break;
+            case 'BuildingBought':
+                buildingsChanged = true;
+                break;
+            case 'CraftQueued':
+            case 'CraftCancelled':
+            case 'Crafted':
+                break;
+            case 'ConverterStalled':
+            case 'ConverterLocked':
+            case 'ConverterResumed':
+                buildingsChanged = true;
+                break;
+            case 'AchievementUnlocked':
+                if (window.achievementManager) {
+                    window.achievementManager.notifyUnlocked(event);
+                }
+                break;
+            case 'FeatureUnlocked':
+                // Unlocks may open up buildings, recipes and workers
+                buildingsChanged = true;
+                if (window.unlockManager) {
+                    window.unlockManager.update();
+                }
+                if (window.workerManager && typeof window.workerManager.renderWorkers === 'function') {
+                    window.workerManager.renderWorkers();
+                }
+                if (window.craftingManager && typeof window.craftingManager.renderRecipes === 'function') {
+                    window.craftingManager.renderRecipes();
+                }
+                break;
+            case 'WorkerLeveledUp':
+                if (window.workerManager && typeof window.workerManager.renderWorkers === 'function') {
+                    window.workerManager.renderWorkers();
+                }
+                break;
+            case 'PurchaseFailed':
+                flashPurchaseFailed(event.target);
+                break;
+            case 'Prestiged':
+                upgradesChanged = true;
+                buildingsChanged = true;
+                if (window.workerManager && typeof window.workerManager.renderWorkers === 'function') {
+                    window.workerManager.renderWorkers();
+                }
+                window.updatePrestigePanel();
+                break;
+            case 'PrestigeUpgradeBought':
+                if (window.workerManager && typeof window.workerManager.renderWorkers === 'function') {
+                    window.workerManager.renderWorkers();
+                }
+                window.updatePrestigePanel();
+                break;
+        }
+    });
+
+    if (resourcesChanged) {
+        // Amounts come from the registry so values past the f64 range keep
+        // their big-number form
+        const resources = game.get_resources() || [];
+        const amountOf = (id) => {
+            const resource = resources.find(r => r.id === id);
+            return resource ? resource.amount : 0;
+        };
+        window.updateResourceDisplay(
+            amountOf('coins'),
+            amountOf('wood'),
+            amountOf('stone'),
+            game.get_coins_per_second(),
+            game.get_wood_per_second(),
+            game.get_stone_per_second(),
+            game.get_coins_per_click()
+        );
+        window.updateExtraResources(resources);
+    }
+    if (upgradesChanged) {
+        window.updateUpgradeButtons(game.get_upgrades());
+    }
+    if (buildingsChanged) {
+        window.updateBuildingDisplay(game.get_buildings());
+    }
+};
+
 // Functions called from UI to communicate with Rust/WASM
 window.buyUpgrade = function(index) {
     if (window.rustGame && typeof window.rustGame.buy_upgrade === 'function') {
-        const success = window.rustGame.buy_upgrade(index);
-        if (!success) {
-            // Provide visual feedback for failed purchase
-            const button = document.getElementById(`buy-upgrade-${index}`);
-            if (button) {
-                button.classList.add('purchase-failed');
-                setTimeout(() => {
-                    button.classList.remove('purchase-failed');
-                }, 300);
-            }
-        }
+        window.rustGame.buy_upgrade(index);
+        window.processGameEvents();
     }
 };
 
 window.buyBuilding = function(index) {
     if (window.rustGame && typeof window.rustGame.buy_building === 'function') {
-        const success = window.rustGame.buy_building(index);
-        if (!success) {
-            // Provide visual feedback for failed purchase
-            const button = document.getElementById(`buy-building-${index}`);
-            if (button) {
-                button.classList.add('purchase-failed');
-                setTimeout(() => {
-                    button.classList.remove('purchase-failed');
-                }, 300);
-            }
-        }
+        window.rustGame.buy_building(index);
+        window.processGameEvents();
     }
 };
 
@@ -287,6 +510,7 @@ document.addEventListener('DOMContentLoaded', function() {
         clickArea.addEventListener('click', function() {
             if (window.rustGame && typeof window.rustGame.click_action === 'function') {
                 window.rustGame.click_action();
+                window.processGameEvents();
             }
         });
     }
@@ -297,6 +521,7 @@ document.addEventListener('DOMContentLoaded', function() {
         headerCoinClickArea.addEventListener('click', function(e) {
             if (window.rustGame && typeof window.rustGame.click_action === 'function') {
                 window.rustGame.click_action();
+                window.processGameEvents();
                 createCoinParticles(e.clientX, e.clientY);
             }
         });
@@ -307,6 +532,7 @@ document.addEventListener('DOMContentLoaded', function() {
         coinButton.addEventListener('click', function(e) {
             if (window.rustGame && typeof window.rustGame.click_action === 'function') {
                 window.rustGame.click_action();
+                window.processGameEvents();
                 createCoinParticles(e.clientX, e.clientY);
             }
         });
@@ -337,13 +563,54 @@ document.addEventListener('DOMContentLoaded', function() {
         });
     }
     
+    // 转生：重置本轮进度，换取永久生产加成
+    const prestigeBtn = document.getElementById('prestige-button');
+    if (prestigeBtn) {
+        prestigeBtn.addEventListener('click', function() {
+            const game = window.rustGame;
+            if (!game || typeof game.prestige !== 'function') {
+                return;
+            }
+            const summary = game.get_prestige();
+            if (!confirm(`转生将重置资源、建筑、升级和工人，获得 ${window.formatAmount(summary.pending_points)} 转生点。确定继续吗？`)) {
+                return;
+            }
+            game.prestige();
+            window.processGameEvents();
+        });
+    }
+    
+    // Named save slots and automatic backups
+    const saveToSlotBtn = document.getElementById('save-to-slot');
+    if (saveToSlotBtn) {
+        saveToSlotBtn.addEventListener('click', function() {
+            const nameInput = document.getElementById('slot-name');
+            const name = nameInput ? nameInput.value.trim() : '';
+            if (!name) {
+                alert('请先输入存档名称。');
+                return;
+            }
+            if (window.rustGame && typeof window.rustGame.saveToSlot === 'function') {
+                try {
+                    window.rustGame.saveToSlot(name);
+                    renderSaveSlots();
+                } catch (slotError) {
+                    console.error('Save to slot failed:', slotError);
+                    alert('保存失败：' + slotError.message);
+                }
+            }
+        });
+    }
+    renderSaveSlots();
+    
     // Export to BASE64 button
     const exportBtn = document.getElementById('export-base64');
     if (exportBtn) {
         exportBtn.addEventListener('click', function() {
             if (window.rustGame && typeof window.rustGame.exportToBase64 === 'function') {
                 try {
-                    const base64Str = window.rustGame.exportToBase64();
+                    // 紧凑格式：仅包含可变状态，二进制打包并压缩，便于在聊天中分享
+                    const base64Str = window.rustGame.exportToBase64('compact');
                     const textArea = document.getElementById('import-export-text');
                     if (textArea) {
                         textArea.value = base64Str;
@@ -376,14 +643,28 @@ document.addEventListener('DOMContentLoaded', function() {
                 }
                 
                 try {
-                    window.rustGame.importFromBase64(textArea.value.trim());
-                    alert('导入成功！游戏已加载。');
+                    const report = window.rustGame.importFromBase64(textArea.value.trim());
+                    const load = report ? report.load : null;
+                    const repaired = load && load.repaired ? load.repaired.length : 0;
+                    let message = '导入成功！游戏已加载。';
+                    // 校验只能发现手动修改，不能证明存档未被篡改
+                    if (report && report.integrity === 'modified') {
+                        message += '\n⚠️ 校验失败：存档在导出后被修改过。';
+                    } else if (report && report.integrity === 'unsigned') {
+                        message += '\nℹ️ 旧版存档，未经校验。';
+                    }
+                    if (repaired > 0) {
+                        console.warn('Repaired save problems:', load.repaired);
+                        message += `\n已自动修复 ${repaired} 处存档问题。`;
+                    }
+                    alert(message);
                     console.log('Game imported from BASE64 at', new Date().toLocaleTimeString());
                     
                     // Refresh UI
                     if (window.rustGame.update_ui) {
                         window.rustGame.update_ui();
                     }
+                    window.processGameEvents();
                 } catch (importError) {
                     console.error('Import failed:', importError);
                     alert('导入失败：' + (importError.message || '无效的 BASE64 字符串'));
@@ -451,4 +732,113 @@ window.updateCoinButton = function() {
     if (coinCount) {
         coinCount.textContent = Math.floor(coins).toLocaleString();
     }
-};
\ No newline at end of file
+};
+
+// 存档位列表：手动存档与自动备份，附带游玩时间、金币和存档版本
+function renderSaveSlots() {
+    const list = document.getElementById('save-slots');
+    const game = window.rustGame;
+    if (!list || !game || typeof game.listSaveSlots !== 'function') {
+        return;
+    }
+    let slots = [];
+    try {
+        slots = game.listSaveSlots() || [];
+    } catch (listError) {
+        console.error('Listing save slots failed:', listError);
+    }
+    list.innerHTML = '';
+    slots.slice().reverse().forEach(slot => {
+        const item = document.createElement('li');
+        const label = slot.kind === 'backup' ? '🕒 自动备份' : '💾 ' + slot.name;
+        const minutes = Math.floor(slot.play_time_seconds / 60);
+        item.textContent = `${label} · ${new Date(slot.saved_at).toLocaleString()} · ` +
+            `${minutes} 分钟 · ${window.formatAmount(slot.coins)} 金币 · v${slot.save_version} `;
+
+        const loadBtn = document.createElement('button');
+        loadBtn.textContent = '恢复';
+        loadBtn.addEventListener('click', () => {
+            if (!confirm('恢复存档将覆盖当前游戏进度。确定继续吗？')) {
+                return;
+            }
+            try {
+                game.restoreSlot(slot.name);
+                if (game.update_ui) {
+                    game.update_ui();
+                }
+                window.processGameEvents();
+                renderSaveSlots();
+            } catch (restoreError) {
+                console.error('Restore failed:', restoreError);
+                alert('恢复失败：' + restoreError.message);
+            }
+        });
+
+        const deleteBtn = document.createElement('button');
+        deleteBtn.textContent = '删除';
+        deleteBtn.addEventListener('click', () => {
+            if (confirm('确定删除该存档吗？')) {
+                game.deleteSlot(slot.name);
+                renderSaveSlots();
+            }
+        });
+
+        item.appendChild(loadBtn);
+        item.appendChild(deleteBtn);
+        list.appendChild(item);
+    });
+}
+window.renderSaveSlots = renderSaveSlots;
+
+// 转生面板：转生点、永久加成以及下一点所需的累计金币
+window.updatePrestigePanel = function() {
+    const info = document.getElementById('prestige-info');
+    const button = document.getElementById('prestige-button');
+    const game = window.rustGame;
+    if (!info || !game || typeof game.get_prestige !== 'function') {
+        return;
+    }
+    const summary = game.get_prestige();
+    if (!summary) {
+        return;
+    }
+    info.innerHTML = `
+        <p>转生点：${window.formatAmount(summary.points)}（已转生 ${summary.prestige_count} 次）</p>
+        <p>永久加成：×${summary.multiplier.toFixed(2)}</p>
+        <p>累计金币：${window.formatAmount(summary.lifetime_coins)} / 下一点需 ${window.formatAmount(summary.coins_for_next_point)}</p>
+        <p>现在转生可获得：${window.formatAmount(summary.pending_points)} 点</p>`;
+    if (button) {
+        button.disabled = !(Number(summary.pending_points) >= 1);
+    }
+    renderPrestigeShop(Number(summary.points));
+};
+
+// 转生商店：用转生点购买永久加成
+function renderPrestigeShop(points) {
+    const shop = document.getElementById('prestige-shop');
+    const game = window.rustGame;
+    if (!shop || !game || typeof game.get_prestige_upgrades !== 'function') {
+        return;
+    }
+    const perks = game.get_prestige_upgrades() || [];
+    shop.innerHTML = '';
+    perks.forEach((perk, index) => {
+        const maxed = perk.max_level != null && perk.owned >= perk.max_level;
+        const level = perk.max_level != null ? `${perk.owned}/${perk.max_level}` : `${perk.owned}`;
+        const effects = (perk.effects || []).map(formatUpgradeEffect).filter(Boolean).join('，');
+        const item = document.createElement('div');
+        item.className = 'prestige-perk';
+        item.innerHTML = `
+            <span class="perk-name">${perk.name}（${level}）</span>
+            <span class="perk-effect">${effects}</span>
+            <button class="perk-buy" data-index="${index}">
+                ${maxed ? '已满级' : `${window.formatAmount(perk.cost)} 转生点`}
+            </button>`;
+        const buy = item.querySelector('.perk-buy');
+        buy.disabled = maxed || points < Number(perk.cost);
+        buy.addEventListener('click', function() {
+            game.buy_prestige_upgrade(index);
+        });
+        shop.appendChild(item);
+    });
+}
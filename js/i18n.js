@@ -48,6 +48,8 @@ class I18n {
                 'cancel': 'Cancel',
                 'confirm': 'Confirm',
                 'assignFailed': 'Assignment Failed',
+                'workerSlotsUsed': 'Worker slots: {used} / {slots}',
+                'workerSlotsFull': 'All {slots} worker slots are taken; unassign a worker or buy more slots first',
                 'totalWorkers': 'Total Workers',
                 'assignedWorkers': 'Assigned',
                 'noWorkers': 'No workers available',
@@ -61,6 +63,16 @@ class I18n {
                 'perClick': ' coins/click',
                 'woodPerSecondShort': ' wood/sec',
                 'stonePerSecondShort': ' stone/sec',
+                'autoclickers': ' autoclicker(s)',
+                'clickPower': ' click power',
+                'allProduction': ' all production',
+                'buildingCost': ' building cost',
+                'workerXp': ' worker XP',
+                'startingWith': 'Start each run with ',
+                'keepAutoclickers': 'Keep autoclickers through prestige',
+                'workerSlots': ' worker slot(s)',
+                'craftingSlots': ' crafting slot(s)',
+                'craftMax': 'Max',
                 
                 // Settings
                 'theme': 'Theme',
@@ -103,6 +115,9 @@ class I18n {
                 'upgradesPurchased': 'Upgrades Purchased',
                 'achievementsUnlocked': 'Achievements Unlocked',
                 'achievementUnlockedTitle': 'Achievement Unlocked!',
+                'offlineProgressTitle': 'Welcome Back!',
+                'workerLevelsGained': 'Worker levels',
+                'craftsCompleted': 'Crafts completed',
                 'achievementUnlocked': 'Achievement Unlocked',
                 'justNow': 'Just now',
                 'minutesAgo': '{count} minutes ago',
@@ -159,6 +174,8 @@ class I18n {
                 'cancel': '取消',
                 'confirm': '确认',
                 'assignFailed': '分配失败',
+                'workerSlotsUsed': '工位：{used} / {slots}',
+                'workerSlotsFull': '{slots} 个工位已满，请先取消分配其他工人或获得更多工位',
                 'totalWorkers': '总工人',
                 'assignedWorkers': '已分配',
                 'noWorkers': '没有工人',
@@ -172,6 +189,16 @@ class I18n {
                 'perClick': ' 金币/点击',
                 'woodPerSecondShort': ' 木头/秒',
                 'stonePerSecondShort': ' 石头/秒',
+                'autoclickers': ' 个自动点击器',
+                'clickPower': ' 点击收益',
+                'allProduction': ' 全部产出',
+                'buildingCost': ' 建筑花费',
+                'workerXp': ' 工人经验',
+                'startingWith': '每次开局获得 ',
+                'keepAutoclickers': '转生后保留自动点击器',
+                'workerSlots': ' 个工位',
+                'craftingSlots': ' 个合成位',
+                'craftMax': '最大',
                 
                 // Settings
                 'theme': '主题',
@@ -214,6 +241,9 @@ class I18n {
                 'upgradesPurchased': '购买升级',
                 'achievementsUnlocked': '解锁成就',
                 'achievementUnlockedTitle': '成就解锁!',
+                'offlineProgressTitle': '离线收益',
+                'workerLevelsGained': '工人升级',
+                'craftsCompleted': '完成合成',
                 'achievementUnlocked': '成就解锁',
                 'justNow': '刚刚',
                 'minutesAgo': '{count}分钟前',
@@ -319,7 +349,9 @@ class I18n {
          if (element) {
              // Ensure amount is a valid finite number before processing
              const safeAmount = (typeof amount === 'number' && isFinite(amount)) ? amount : 0;
-             element.textContent = Math.floor(safeAmount).toString();
+             element.textContent = window.formatAmount
+                 ? window.formatAmount(amount)
+                 : Math.floor(safeAmount).toString();
          }
      }
     
@@ -331,7 +363,7 @@ class I18n {
              const safeAmount = (typeof amount === 'number' && isFinite(amount)) ? amount : 0;
              element.textContent = this.t('resourceFormat', { 
                  resource: resourceName, 
-                 amount: Math.floor(safeAmount) 
+                 amount: window.formatAmount ? window.formatAmount(amount) : Math.floor(safeAmount)
              });
          }
      }
@@ -47,9 +47,13 @@ class StatisticsManager {
 
         const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
 
-        // Format number with commas for readability
+        // Format number with commas for readability; big totals use the
+        // game's number notation
         const formatNumber = (num) => {
-            return Math.floor(num).toLocaleString();
+            if (typeof num === 'number' && num < 1e6) {
+                return Math.floor(num).toLocaleString();
+            }
+            return window.formatAmount ? window.formatAmount(num) : String(num);
         };
 
         panel.innerHTML = `
@@ -53,31 +53,12 @@ class UnlockManager {
                 percentage: 0,
                 unlocked: false,
                 name: featureId,
-                requirementType: 'unknown'
+                prerequisites: []
             };
         }
 
-        let currentValue = 0;
-        if (this.rustGame) {
-            switch (feature.requirement_type) {
-                case 'total_clicks':
-                    currentValue = this.rustGame.get_total_clicks ? this.rustGame.get_total_clicks() : 0;
-                    break;
-                case 'total_coins':
-                    currentValue = this.rustGame.get_coins ? this.rustGame.get_coins() : 0;
-                    break;
-                case 'buildings_owned':
-                    if (this.rustGame.get_statistics) {
-                        const stats = this.rustGame.get_statistics();
-                        currentValue = stats.buildings_purchased || 0;
-                    }
-                    break;
-                default:
-                    currentValue = 0;
-            }
-        }
-
-        const required = feature.requirement_value || 0;
+        const currentValue = feature.progress || 0;
+        const required = feature.requirement || 0;
         const percentage = required > 0 ? Math.min(100, (currentValue / required) * 100) : 0;
 
         return {
@@ -86,7 +67,7 @@ class UnlockManager {
             percentage: percentage,
             unlocked: feature.unlocked || false,
             name: feature.name || featureId,
-            requirementType: feature.requirement_type || 'unknown'
+            prerequisites: feature.prerequisites || []
         };
     }
 
@@ -121,15 +102,20 @@ class UnlockManager {
             const progress = this.checkProgress(unlock.id);
             const progressBarWidth = `${progress.percentage}%`;
             
-            const canUnlock = !progress.unlocked && progress.current >= progress.required;
+            const canUnlock = !progress.unlocked && progress.current >= progress.required
+                && this.prerequisitesMet(unlock);
 
             unlockDiv.innerHTML = `
                 <div class="unlock-icon">${progress.unlocked ? '🔓' : '🔒'}</div>
                 <div class="unlock-title">${unlock.name}</div>
                 <div class="unlock-description">${this.getDescription(unlock.id)}</div>
                 <div class="unlock-requirement">
-                    需求：${this.formatRequirement(unlock.requirement_type, unlock.requirement_value)}
+                    需求：${this.formatCondition(unlock.condition)}
                 </div>
+                ${progress.prerequisites.length > 0 ? `
+                <div class="unlock-prerequisites">
+                    前置：${progress.prerequisites.map(id => this.featureName(id)).join('、')}
+                </div>` : ''}
                 <div class="unlock-progress">
                     <div class="progress-bar">
                         <div class="progress-bar-fill" style="width: ${progressBarWidth}"></div>
@@ -152,20 +138,54 @@ class UnlockManager {
         });
     }
 
-    formatRequirement(type, value) {
-        const clickText = window.i18n ? window.i18n.t('clicks') : 'clicks';
-        const coinsText = window.i18n ? window.i18n.t('coins') : 'coins';
-        const buildingsText = window.i18n ? window.i18n.t('buildings') : 'buildings';
+    prerequisitesMet(feature) {
+        return (feature.prerequisites || []).every(id => {
+            const prerequisite = this.unlocks.find(u => u.id === id);
+            return prerequisite && prerequisite.unlocked;
+        });
+    }
+
+    featureName(featureId) {
+        const feature = this.unlocks.find(u => u.id === featureId);
+        return feature ? feature.name : featureId;
+    }
 
-        switch (type) {
+    formatCondition(condition) {
+        if (!condition) {
+            return '';
+        }
+        switch (condition.type) {
+            case 'stat': {
+                const comparator = condition.comparator && condition.comparator !== '>='
+                    ? `${condition.comparator} ` : '';
+                return `${comparator}${condition.value.toFixed(0)} ${this.formatStat(condition.stat)}`;
+            }
+            case 'all':
+                return condition.conditions.map(c => this.formatCondition(c)).join(' 且 ');
+            case 'any':
+                return condition.conditions.map(c => this.formatCondition(c)).join(' 或 ');
+            default:
+                return '';
+        }
+    }
+
+    formatStat(stat) {
+        const t = key => (window.i18n ? window.i18n.t(key) : key);
+        const [kind, id] = stat.split(':');
+        switch (kind) {
+            case 'clicks':
             case 'total_clicks':
-                return `${value} ${clickText}`;
-            case 'total_coins':
-                return `${value.toFixed(0)} ${coinsText}`;
-            case 'buildings_owned':
-                return `${value} ${buildingsText}`;
+                return t('clicks');
+            case 'buildings_purchased':
+                return t('buildings');
+            case 'resource':
+            case 'earned':
+                return typeof resourceName === 'function' ? resourceName(id) : id;
+            case 'building':
+            case 'upgrade':
+                return id;
             default:
-                return `${value}`;
+                return stat;
         }
     }
 
@@ -174,6 +194,7 @@ class UnlockManager {
             'statistics_panel': '解锁统计数据面板，查看您的游戏统计信息',
             'achievements_panel': '解锁成就面板，追踪您的游戏成就',
             'crafting_panel': '解锁资源合成系统，转换不同资源',
+            'unlock_crafting': '解锁资源合成，转换不同资源',
             'workers_tab': '解锁工人标签页，管理工人分配',
             'advanced_buildings': '解锁高级建筑，提升生产效率'
         };
@@ -186,8 +207,9 @@ class UnlockManager {
             if (!button) return;
 
             const progress = this.checkProgress(unlock.id);
-            const canUnlock = !progress.unlocked && progress.current >= progress.required;
-            
+            const canUnlock = !progress.unlocked && progress.current >= progress.required
+                && this.prerequisitesMet(unlock);
+
             button.disabled = !canUnlock;
             if (canUnlock) {
                 button.classList.remove('disabled');
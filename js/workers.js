@@ -23,7 +23,7 @@ class WorkerManager {
     /**
      * Assign a worker to a building
      * @param {number} workerIndex - Index of the worker
-     * @param {string} buildingId - ID/name of the building
+     * @param {string} buildingId - Building id (a name is also accepted)
      * @returns {boolean} Success status
      */
     assignWorker(workerIndex, buildingId) {
@@ -38,6 +38,17 @@ class WorkerManager {
         return false;
     }
 
+    /**
+     * Workers that may be assigned at once
+     * @returns {number} Worker slots, or Infinity when unknown
+     */
+    getWorkerSlots() {
+        if (this.rustGame && typeof this.rustGame.get_worker_slots === 'function') {
+            return this.rustGame.get_worker_slots();
+        }
+        return Infinity;
+    }
+
     /**
      * Get buildings from Rust game state for selection
      * @returns {Array} Array of building objects
@@ -55,6 +66,16 @@ class WorkerManager {
         return [];
     }
 
+    /**
+     * Display name of a building id
+     * @param {string} buildingId - Stable building id
+     * @returns {string} Building name, or the id if it is unknown
+     */
+    buildingName(buildingId) {
+        const building = this.getBuildings().find(b => b.id === buildingId);
+        return building ? building.name : buildingId;
+    }
+
     /**
      * Format efficiency multiplier as percentage
      * @param {number} multiplier - Efficiency multiplier (e.g., 1.0, 1.2)
@@ -90,14 +111,21 @@ class WorkerManager {
             return;
         }
 
-        let html = '<div class="workers-grid">';
+        const slots = this.getWorkerSlots();
+        const used = workers.filter(w => w.assignedBuilding).length;
+        let html = '';
+        if (Number.isFinite(slots)) {
+            html += `<p class="worker-slots ${used >= slots ? 'full' : ''}">${t('workerSlotsUsed', { used, slots })}</p>`;
+        }
+        html += '<div class="workers-grid">';
         
         workers.forEach((worker, index) => {
             const isAssigned = worker.assignedBuilding !== null && worker.assignedBuilding !== undefined;
-            const assignedBuildingName = isAssigned ? worker.assignedBuilding : (t('unassigned') || '未分配');
+            const assignedBuildingName = isAssigned ? this.buildingName(worker.assignedBuilding) : (t('unassigned') || '未分配');
             const efficiencyBonus = this.formatEfficiency(worker.efficiencyMultiplier);
             const xpProgress = this.formatXP(worker.xp, worker.xpToNextLevel);
             const progressPercent = Math.min(100, (worker.xp / worker.xpToNextLevel) * 100);
+            const locked = window.lockedFeatureName ? window.lockedFeatureName(worker.requires) : null;
 
             html += `
                 <div class="worker-card" id="worker-card-${index}">
@@ -120,7 +148,7 @@ class WorkerManager {
                         </div>
                         <div class="worker-info-row">
                             <span class="worker-label">${t('preferences') || '偏好'}:</span>
-                            <span class="worker-value">${worker.preferences}</span>
+                            <span class="worker-value">${this.buildingName(worker.preferences)}</span>
                         </div>
                         <div class="worker-xp-section">
                             <div class="worker-info-row">
@@ -137,8 +165,9 @@ class WorkerManager {
                             class="worker-assign-btn" 
                             onclick="window.workerManager.showAssignmentModal(${index})"
                             title="${isAssigned ? t('reassignWorker') : t('assignWorker')}"
+                            ${locked ? 'disabled' : ''}
                         >
-                            ${isAssigned ? t('reassign') : t('assign')}
+                            ${locked ? `🔒 ${locked}` : (isAssigned ? t('reassign') : t('assign'))}
                         </button>
                     </div>
                 </div>
@@ -165,12 +194,13 @@ class WorkerManager {
 
         const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
         
-        let html = `<option value="">${t('selectBuilding') || '选择建筑'}</option>`;
+        // 空的建筑 id 表示取消分配；提示项不可选，避免误取消
+        let html = `<option value="" disabled ${worker.assignedBuilding ? '' : 'selected'}>${t('selectBuilding') || '选择建筑'}</option>`;
         html += `<option value="">${t('unassign') || '取消分配'}</option>`;
         
         buildings.forEach((building, index) => {
-            const isSelected = worker.assignedBuilding === building.name;
-            html += `<option value="${building.name}" ${isSelected ? 'selected' : ''}>${building.name} (${building.count})</option>`;
+            const isSelected = worker.assignedBuilding === building.id;
+            html += `<option value="${building.id}" ${isSelected ? 'selected' : ''}>${building.name} (${building.count})</option>`;
         });
 
         return html;
@@ -224,7 +254,7 @@ class WorkerManager {
                         </div>
                         <div class="preview-row">
                             <span class="preview-label">${t('preference') || '偏好'}:</span>
-                            <span class="preview-value">${worker.preferences}</span>
+                            <span class="preview-value">${this.buildingName(worker.preferences)}</span>
                         </div>
                     </div>
                 </div>
@@ -269,8 +299,16 @@ class WorkerManager {
 
         const buildingId = select.value;
         
-        if (buildingId) {
+        // 第 0 项是提示；选中“取消分配”时 buildingId 为空
+        if (select.selectedIndex > 0) {
+            const worker = this.update()[workerIndex];
+            const slots = this.getWorkerSlots();
+            const slotsFull = worker && !worker.assignedBuilding
+                && this.update().filter(w => w.assignedBuilding).length >= slots;
             const success = this.assignWorker(workerIndex, buildingId);
+            if (window.processGameEvents) {
+                window.processGameEvents();
+            }
             if (success) {
                 this.renderWorkers();
                 this.closeAssignmentModal();
@@ -280,7 +318,8 @@ class WorkerManager {
                 }
             } else {
                 console.error('Failed to assign worker');
-                alert(window.i18n ? window.i18n.t('assignFailed') || '分配失败' : '分配失败');
+                const t = window.i18n ? window.i18n.t.bind(window.i18n) : (key) => key;
+                alert(slotsFull ? t('workerSlotsFull', { slots }) : (t('assignFailed') || '分配失败'));
             }
         } else {
             this.closeAssignmentModal();
@@ -343,7 +382,7 @@ class WorkerManager {
         
         workers.forEach((worker, index) => {
             const isAssigned = worker.assignedBuilding !== null && worker.assignedBuilding !== undefined;
-            const assignedBuildingName = isAssigned ? worker.assignedBuilding : (t('unassigned') || '未分配');
+            const assignedBuildingName = isAssigned ? this.buildingName(worker.assignedBuilding) : (t('unassigned') || '未分配');
             const efficiencyBonus = this.formatEfficiency(worker.efficiencyMultiplier);
 
             html += `
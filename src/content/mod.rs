@@ -0,0 +1,789 @@
+use crate::entities::{Building, Converter, Upgrade, Worker};
+use crate::state::{BigNum, Resource, ResourceId, ResourceRate, ResourceRegistry};
+use crate::systems::{
+    Achievement, AchievementReward, BonusOutput, Condition, CraftingRecipe, RecipeItem, StatKey,
+    UnlockedFeature, UpgradeEffect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Content shipped with the game; an override from the page is merged on top
+const DEFAULT_CONTENT: &str = include_str!("default.json");
+
+fn default_building_cost_multiplier() -> f64 {
+    1.15
+}
+
+fn default_upgrade_cost_multiplier() -> f64 {
+    1.5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResourceDef {
+    pub id: ResourceId,
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BuildingDef {
+    pub id: String,
+    pub name: String,
+    pub base_cost: f64,
+    /// Resources each owned building produces per second
+    #[serde(default)]
+    pub outputs: Vec<ResourceRate>,
+    /// Resources each owned building consumes per second; the building is
+    /// idle while any of them is out of stock
+    #[serde(default)]
+    pub inputs: Vec<ResourceRate>,
+    /// Factor applied to the cost after each purchase
+    #[serde(default = "default_building_cost_multiplier")]
+    pub cost_multiplier: f64,
+    /// Feature that must be unlocked first
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// Recipe each owned building crafts on its own, and how often
+    #[serde(default)]
+    pub converter: Option<ConverterDef>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConverterDef {
+    pub recipe: String,
+    /// Crafts per second for each owned building
+    pub rate: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UpgradeDef {
+    pub id: String,
+    pub name: String,
+    pub base_cost: f64,
+    /// What each owned level does
+    #[serde(default)]
+    pub effects: Vec<UpgradeEffect>,
+    /// Factor applied to the cost after each purchase
+    #[serde(default = "default_upgrade_cost_multiplier")]
+    pub cost_multiplier: f64,
+    /// Most levels that can be bought; unlimited when absent
+    #[serde(default)]
+    pub max_level: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AchievementDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub condition: Condition,
+    /// Permanent bonuses while unlocked
+    #[serde(default)]
+    pub effects: Vec<UpgradeEffect>,
+    /// Granted once, on unlock
+    #[serde(default)]
+    pub rewards: Vec<AchievementReward>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecipeDef {
+    pub id: String,
+    pub name: String,
+    pub inputs: Vec<RecipeItem>,
+    pub outputs: Vec<RecipeItem>,
+    /// Extra outputs that each craft rolls for
+    #[serde(default)]
+    pub bonus_outputs: Vec<BonusOutput>,
+    /// Seconds each craft takes; 0 completes at once
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default = "default_true")]
+    pub unlocked: bool,
+    /// Feature that must be unlocked first
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// Building that must be owned first
+    #[serde(default)]
+    pub requires_building: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkerDef {
+    pub id: String,
+    pub name: String,
+    pub skills: String,
+    pub background: String,
+    /// Id of the building this worker is most efficient in
+    pub preferences: String,
+    /// Feature that must be unlocked first
+    #[serde(default)]
+    pub requires: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnlockDef {
+    pub id: String,
+    pub name: String,
+    pub feature_type: String,
+    pub condition: Condition,
+    /// Features that must be unlocked first
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+}
+
+/// Why a content definition could not be used
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContentError {
+    Parse(String),
+    MissingId {
+        kind: &'static str,
+    },
+    DuplicateId {
+        kind: &'static str,
+        id: String,
+    },
+    UnknownResource {
+        kind: &'static str,
+        id: String,
+        resource: String,
+    },
+    UnknownBuilding {
+        kind: &'static str,
+        id: String,
+        building: String,
+    },
+    UnknownUpgrade {
+        kind: &'static str,
+        id: String,
+        upgrade: String,
+    },
+    UnknownRecipe {
+        kind: &'static str,
+        id: String,
+        recipe: String,
+    },
+    UnknownFeature {
+        kind: &'static str,
+        id: String,
+        feature: String,
+    },
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentError::Parse(message) => write!(f, "Content parse error: {}", message),
+            ContentError::MissingId { kind } => write!(f, "A {} definition has no id", kind),
+            ContentError::DuplicateId { kind, id } => {
+                write!(f, "Duplicate {} id: {}", kind, id)
+            }
+            ContentError::UnknownResource { kind, id, resource } => {
+                write!(f, "The {} {} uses unknown resource {}", kind, id, resource)
+            }
+            ContentError::UnknownBuilding { kind, id, building } => {
+                write!(
+                    f,
+                    "The {} {} refers to unknown building {}",
+                    kind, id, building
+                )
+            }
+            ContentError::UnknownUpgrade { kind, id, upgrade } => {
+                write!(
+                    f,
+                    "The {} {} refers to unknown upgrade {}",
+                    kind, id, upgrade
+                )
+            }
+            ContentError::UnknownRecipe { kind, id, recipe } => {
+                write!(f, "The {} {} refers to unknown recipe {}", kind, id, recipe)
+            }
+            ContentError::UnknownFeature { kind, id, feature } => {
+                write!(
+                    f,
+                    "The {} {} refers to unknown feature {}",
+                    kind, id, feature
+                )
+            }
+        }
+    }
+}
+
+/// Declarative definition of everything the player can build, buy, earn,
+/// craft or hire; the running game is instantiated from it
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GameContent {
+    #[serde(default)]
+    pub resources: Vec<ResourceDef>,
+    #[serde(default)]
+    pub buildings: Vec<BuildingDef>,
+    #[serde(default)]
+    pub upgrades: Vec<UpgradeDef>,
+    #[serde(default)]
+    pub achievements: Vec<AchievementDef>,
+    #[serde(default)]
+    pub recipes: Vec<RecipeDef>,
+    #[serde(default)]
+    pub workers: Vec<WorkerDef>,
+    #[serde(default)]
+    pub unlocks: Vec<UnlockDef>,
+    /// Permanent upgrades bought with prestige points, kept across prestiges
+    #[serde(default)]
+    pub prestige_upgrades: Vec<UpgradeDef>,
+}
+
+/// Replace entries of `base` that share an id with one in `overrides`, and
+/// append the rest in the order they were given
+fn merge_by_id<T>(base: &mut Vec<T>, overrides: Vec<T>, id: impl Fn(&T) -> &str) {
+    for entry in overrides {
+        match base.iter().position(|existing| id(existing) == id(&entry)) {
+            Some(i) => base[i] = entry,
+            None => base.push(entry),
+        }
+    }
+}
+
+fn check_ids<'a>(
+    kind: &'static str,
+    ids: impl Iterator<Item = &'a str>,
+) -> Result<(), ContentError> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if id.is_empty() {
+            return Err(ContentError::MissingId { kind });
+        }
+        if !seen.insert(id) {
+            return Err(ContentError::DuplicateId {
+                kind,
+                id: id.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn new_upgrade(def: &UpgradeDef) -> Upgrade {
+    Upgrade {
+        id: def.id.clone(),
+        name: def.name.clone(),
+        cost: BigNum::from(def.base_cost),
+        effects: def.effects.clone(),
+        owned: 0,
+        unlocked: true,
+        max_level: def.max_level,
+    }
+}
+
+impl GameContent {
+    /// The content embedded in the binary
+    pub fn embedded() -> GameContent {
+        let content = GameContent::from_json(DEFAULT_CONTENT).expect("embedded content parses");
+        content
+            .check_references()
+            .expect("embedded content references known resources");
+        content
+    }
+
+    /// Parse and validate a complete or partial content definition
+    pub fn from_json(json: &str) -> Result<GameContent, ContentError> {
+        let content: GameContent =
+            serde_json::from_str(json).map_err(|e| ContentError::Parse(e.to_string()))?;
+        content.validate()?;
+        Ok(content)
+    }
+
+    /// Embedded content with `json` merged on top; entries are matched by id
+    pub fn embedded_with_override(json: &str) -> Result<GameContent, ContentError> {
+        let mut content = GameContent::embedded();
+        content.merge(GameContent::from_json(json)?);
+        content.check_references()?;
+        Ok(content)
+    }
+
+    /// Merge `overrides` into this content, entry by entry
+    pub fn merge(&mut self, overrides: GameContent) {
+        merge_by_id(&mut self.resources, overrides.resources, |d| &d.id);
+        merge_by_id(&mut self.buildings, overrides.buildings, |d| &d.id);
+        merge_by_id(&mut self.upgrades, overrides.upgrades, |d| &d.id);
+        merge_by_id(&mut self.achievements, overrides.achievements, |d| &d.id);
+        merge_by_id(&mut self.recipes, overrides.recipes, |d| &d.id);
+        merge_by_id(&mut self.workers, overrides.workers, |d| &d.id);
+        merge_by_id(&mut self.unlocks, overrides.unlocks, |d| &d.id);
+        merge_by_id(
+            &mut self.prestige_upgrades,
+            overrides.prestige_upgrades,
+            |d| &d.id,
+        );
+    }
+
+    /// Every entry needs an id that is unique within its kind
+    pub fn validate(&self) -> Result<(), ContentError> {
+        check_ids("resource", self.resources.iter().map(|d| d.id.as_str()))?;
+        check_ids("building", self.buildings.iter().map(|d| d.id.as_str()))?;
+        check_ids("upgrade", self.upgrades.iter().map(|d| d.id.as_str()))?;
+        check_ids(
+            "achievement",
+            self.achievements.iter().map(|d| d.id.as_str()),
+        )?;
+        check_ids("recipe", self.recipes.iter().map(|d| d.id.as_str()))?;
+        check_ids("worker", self.workers.iter().map(|d| d.id.as_str()))?;
+        check_ids("unlock", self.unlocks.iter().map(|d| d.id.as_str()))?;
+        check_ids(
+            "prestige upgrade",
+            self.prestige_upgrades.iter().map(|d| d.id.as_str()),
+        )
+    }
+
+    /// Every resource and building that content mentions must be defined; only
+    /// meaningful on complete content, not on a partial override
+    pub fn check_references(&self) -> Result<(), ContentError> {
+        let known = |resource: &str| self.resources.iter().any(|d| d.id == resource);
+        for def in &self.buildings {
+            for flow in def.outputs.iter().chain(&def.inputs) {
+                if !known(&flow.resource) {
+                    return Err(ContentError::UnknownResource {
+                        kind: "building",
+                        id: def.id.clone(),
+                        resource: flow.resource.clone(),
+                    });
+                }
+            }
+        }
+        for def in &self.buildings {
+            if let Some(converter) = &def.converter {
+                if !self.recipes.iter().any(|r| r.id == converter.recipe) {
+                    return Err(ContentError::UnknownRecipe {
+                        kind: "building",
+                        id: def.id.clone(),
+                        recipe: converter.recipe.clone(),
+                    });
+                }
+            }
+        }
+        let requirements = self
+            .buildings
+            .iter()
+            .map(|d| ("building", &d.id, &d.requires))
+            .chain(self.recipes.iter().map(|d| ("recipe", &d.id, &d.requires)))
+            .chain(self.workers.iter().map(|d| ("worker", &d.id, &d.requires)));
+        for (kind, id, requires) in requirements {
+            if let Some(feature) = requires {
+                if !self.unlocks.iter().any(|d| &d.id == feature) {
+                    return Err(ContentError::UnknownFeature {
+                        kind,
+                        id: id.clone(),
+                        feature: feature.clone(),
+                    });
+                }
+            }
+        }
+        for def in &self.unlocks {
+            self.check_condition("unlock", &def.id, &def.condition)?;
+            for feature in &def.prerequisites {
+                if !self.unlocks.iter().any(|d| &d.id == feature) {
+                    return Err(ContentError::UnknownFeature {
+                        kind: "unlock",
+                        id: def.id.clone(),
+                        feature: feature.clone(),
+                    });
+                }
+            }
+        }
+        for def in &self.upgrades {
+            self.check_effects("upgrade", &def.id, &def.effects)?;
+        }
+        for def in &self.prestige_upgrades {
+            self.check_effects("prestige upgrade", &def.id, &def.effects)?;
+        }
+        for def in &self.achievements {
+            self.check_condition("achievement", &def.id, &def.condition)?;
+            self.check_effects("achievement", &def.id, &def.effects)?;
+            self.check_rewards(def)?;
+        }
+        for def in &self.recipes {
+            let resources = def
+                .inputs
+                .iter()
+                .chain(&def.outputs)
+                .map(|item| &item.resource)
+                .chain(def.bonus_outputs.iter().map(|bonus| &bonus.resource));
+            for resource in resources {
+                if !known(resource) {
+                    return Err(ContentError::UnknownResource {
+                        kind: "recipe",
+                        id: def.id.clone(),
+                        resource: resource.clone(),
+                    });
+                }
+            }
+            if let Some(building) = &def.requires_building {
+                if !self.buildings.iter().any(|d| &d.id == building) {
+                    return Err(ContentError::UnknownBuilding {
+                        kind: "recipe",
+                        id: def.id.clone(),
+                        building: building.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_effects(
+        &self,
+        kind: &'static str,
+        id: &str,
+        effects: &[UpgradeEffect],
+    ) -> Result<(), ContentError> {
+        for effect in effects {
+            match effect {
+                UpgradeEffect::ResourceRate { resource, .. }
+                | UpgradeEffect::StartingResource { resource, .. }
+                    if !self.has_resource(resource) =>
+                {
+                    return Err(ContentError::UnknownResource {
+                        kind,
+                        id: id.to_string(),
+                        resource: resource.clone(),
+                    });
+                }
+                UpgradeEffect::BuildingMultiplier { building, .. }
+                    if self.building(building).is_none() =>
+                {
+                    return Err(ContentError::UnknownBuilding {
+                        kind,
+                        id: id.to_string(),
+                        building: building.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rewards(&self, def: &AchievementDef) -> Result<(), ContentError> {
+        let kind = "achievement";
+        for reward in &def.rewards {
+            match reward {
+                AchievementReward::Grant { resource, .. } if !self.has_resource(resource) => {
+                    return Err(ContentError::UnknownResource {
+                        kind,
+                        id: def.id.clone(),
+                        resource: resource.clone(),
+                    });
+                }
+                AchievementReward::UnlockRecipe { recipe }
+                    if !self.recipes.iter().any(|d| &d.id == recipe) =>
+                {
+                    return Err(ContentError::UnknownRecipe {
+                        kind,
+                        id: def.id.clone(),
+                        recipe: recipe.clone(),
+                    });
+                }
+                AchievementReward::UnlockFeature { feature }
+                    if !self.unlocks.iter().any(|d| &d.id == feature) =>
+                {
+                    return Err(ContentError::UnknownFeature {
+                        kind,
+                        id: def.id.clone(),
+                        feature: feature.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Every resource, building and upgrade a condition reads must exist
+    fn check_condition(
+        &self,
+        kind: &'static str,
+        id: &str,
+        condition: &Condition,
+    ) -> Result<(), ContentError> {
+        for stat in condition.stats() {
+            match stat {
+                StatKey::Resource(resource) | StatKey::Earned(resource)
+                    if !self.has_resource(resource) =>
+                {
+                    return Err(ContentError::UnknownResource {
+                        kind,
+                        id: id.to_string(),
+                        resource: resource.clone(),
+                    });
+                }
+                StatKey::Building(building) if self.building(building).is_none() => {
+                    return Err(ContentError::UnknownBuilding {
+                        kind,
+                        id: id.to_string(),
+                        building: building.clone(),
+                    });
+                }
+                StatKey::Upgrade(upgrade) if self.upgrade(upgrade).is_none() => {
+                    return Err(ContentError::UnknownUpgrade {
+                        kind,
+                        id: id.to_string(),
+                        upgrade: upgrade.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn has_resource(&self, id: &str) -> bool {
+        self.resources.iter().any(|d| d.id == id)
+    }
+
+    pub fn building(&self, id: &str) -> Option<&BuildingDef> {
+        self.buildings.iter().find(|d| d.id == id)
+    }
+
+    pub fn upgrade(&self, id: &str) -> Option<&UpgradeDef> {
+        self.upgrades.iter().find(|d| d.id == id)
+    }
+
+    pub fn prestige_upgrade(&self, id: &str) -> Option<&UpgradeDef> {
+        self.prestige_upgrades.iter().find(|d| d.id == id)
+    }
+
+    pub fn worker(&self, id: &str) -> Option<&WorkerDef> {
+        self.workers.iter().find(|d| d.id == id)
+    }
+
+    pub fn new_resources(&self) -> ResourceRegistry {
+        ResourceRegistry::new(
+            self.resources
+                .iter()
+                .map(|d| Resource::new(&d.id, &d.name, &d.icon))
+                .collect(),
+        )
+    }
+
+    /// Add resources defined after `resources` was saved and refresh the
+    /// display metadata of the others
+    pub fn register_resources(&self, resources: &mut ResourceRegistry) {
+        for d in &self.resources {
+            resources.register(Resource::new(&d.id, &d.name, &d.icon));
+        }
+    }
+
+    pub fn new_buildings(&self) -> Vec<Building> {
+        self.buildings
+            .iter()
+            .map(|d| Building {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                cost: BigNum::from(d.base_cost),
+                outputs: d.outputs.clone(),
+                inputs: d.inputs.clone(),
+                requires: d.requires.clone(),
+                converter: d
+                    .converter
+                    .as_ref()
+                    .map(|c| Converter::new(&c.recipe, c.rate)),
+                count: 0,
+            })
+            .collect()
+    }
+
+    pub fn new_upgrades(&self) -> Vec<Upgrade> {
+        self.upgrades.iter().map(new_upgrade).collect()
+    }
+
+    pub fn new_prestige_upgrades(&self) -> Vec<Upgrade> {
+        self.prestige_upgrades.iter().map(new_upgrade).collect()
+    }
+
+    pub fn new_achievements(&self) -> Vec<Achievement> {
+        self.achievements
+            .iter()
+            .map(|d| Achievement {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                description: d.description.clone(),
+                unlocked: false,
+                unlock_timestamp: None,
+                progress: 0.0,
+                requirement: d.condition.requirement(),
+                category: d.category.clone(),
+                condition: d.condition.clone(),
+                effects: d.effects.clone(),
+                rewards: d.rewards.clone(),
+            })
+            .collect()
+    }
+
+    pub fn new_recipes(&self) -> Vec<CraftingRecipe> {
+        self.recipes
+            .iter()
+            .map(|d| CraftingRecipe {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                inputs: d.inputs.clone(),
+                outputs: d.outputs.clone(),
+                bonus_outputs: d.bonus_outputs.clone(),
+                duration: d.duration,
+                unlocked: d.unlocked,
+                requires: d.requires.clone(),
+                requires_building: d.requires_building.clone(),
+            })
+            .collect()
+    }
+
+    pub fn new_workers(&self) -> Vec<Worker> {
+        self.workers
+            .iter()
+            .map(|d| Worker {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                skills: d.skills.clone(),
+                background: d.background.clone(),
+                preferences: d.preferences.clone(),
+                requires: d.requires.clone(),
+                assigned_building: None,
+                level: 1,
+                efficiency_multiplier: 1.0,
+                xp: 0.0,
+                xp_to_next_level: 100.0,
+            })
+            .collect()
+    }
+
+    pub fn new_unlocks(&self) -> Vec<UnlockedFeature> {
+        self.unlocks
+            .iter()
+            .map(|d| UnlockedFeature {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                feature_type: d.feature_type.clone(),
+                unlocked: false,
+                unlock_timestamp: None,
+                progress: 0.0,
+                requirement: d.condition.requirement(),
+                condition: d.condition.clone(),
+                prerequisites: d.prerequisites.clone(),
+            })
+            .collect()
+    }
+
+    /// Bring entities restored from a save in line with these definitions:
+    /// saves that predate content ids get the id of the definition with the
+    /// same name, production and effects come from the definitions, and
+    /// worker building references stored as names are turned into ids
+    pub fn sync_saved_entities(
+        &self,
+        buildings: &mut [Building],
+        upgrades: &mut [Upgrade],
+        workers: &mut [Worker],
+    ) {
+        for building in buildings.iter_mut() {
+            if building.id.is_empty() {
+                if let Some(def) = self.buildings.iter().find(|d| d.name == building.name) {
+                    building.id = def.id.clone();
+                }
+            }
+            if let Some(def) = self.building(&building.id) {
+                building.outputs = def.outputs.clone();
+                building.inputs = def.inputs.clone();
+                building.requires = def.requires.clone();
+                // Keep the progress and stall of a converter that stays one
+                building.converter = match (def.converter.as_ref(), building.converter.take()) {
+                    (Some(c), Some(saved)) if saved.recipe == c.recipe => Some(Converter {
+                        rate: c.rate,
+                        ..saved
+                    }),
+                    (Some(c), _) => Some(Converter::new(&c.recipe, c.rate)),
+                    (None, _) => None,
+                };
+            }
+        }
+        for upgrade in upgrades.iter_mut() {
+            if upgrade.id.is_empty() {
+                if let Some(def) = self.upgrades.iter().find(|d| d.name == upgrade.name) {
+                    upgrade.id = def.id.clone();
+                }
+            }
+            if let Some(def) = self.upgrade(&upgrade.id) {
+                upgrade.effects = def.effects.clone();
+                upgrade.max_level = def.max_level;
+            }
+        }
+        for worker in workers.iter_mut() {
+            if worker.id.is_empty() {
+                if let Some(def) = self.workers.iter().find(|d| d.name == worker.name) {
+                    worker.id = def.id.clone();
+                }
+            }
+            if let Some(def) = self.worker(&worker.id) {
+                worker.requires = def.requires.clone();
+            }
+            if let Some(def) = self.buildings.iter().find(|d| d.name == worker.preferences) {
+                worker.preferences = def.id.clone();
+            }
+            if let Some(assigned) = worker.assigned_building.as_mut() {
+                if let Some(def) = self.buildings.iter().find(|d| &d.name == assigned) {
+                    *assigned = def.id.clone();
+                }
+            }
+        }
+    }
+
+    /// Conditions and prerequisites of saved features come from the definitions
+    pub fn sync_unlocks(&self, features: &mut [UnlockedFeature]) {
+        for feature in features.iter_mut() {
+            if let Some(def) = self.unlocks.iter().find(|d| d.id == feature.id) {
+                feature.condition = def.condition.clone();
+                feature.requirement = def.condition.requirement();
+                feature.prerequisites = def.prerequisites.clone();
+            }
+        }
+    }
+
+    /// Ingredients and requirements of saved recipes come from the definitions
+    pub fn sync_recipes(&self, recipes: &mut [CraftingRecipe]) {
+        for recipe in recipes.iter_mut() {
+            if let Some(def) = self.recipes.iter().find(|d| d.id == recipe.id) {
+                recipe.inputs = def.inputs.clone();
+                recipe.outputs = def.outputs.clone();
+                recipe.bonus_outputs = def.bonus_outputs.clone();
+                recipe.duration = def.duration;
+                recipe.requires = def.requires.clone();
+                recipe.requires_building = def.requires_building.clone();
+            }
+        }
+    }
+
+    /// Conditions and rewards of saved achievements come from the definitions, so
+    /// content changes apply to existing saves
+    pub fn sync_achievements(&self, achievements: &mut [Achievement]) {
+        for achievement in achievements.iter_mut() {
+            if let Some(def) = self.achievements.iter().find(|d| d.id == achievement.id) {
+                achievement.condition = def.condition.clone();
+                achievement.requirement = def.condition.requirement();
+                achievement.effects = def.effects.clone();
+                achievement.rewards = def.rewards.clone();
+            }
+        }
+    }
+
+    /// Effects and level caps of saved prestige upgrades come from the definitions
+    pub fn sync_prestige_upgrades(&self, upgrades: &mut [Upgrade]) {
+        for upgrade in upgrades.iter_mut() {
+            if let Some(def) = self.prestige_upgrade(&upgrade.id) {
+                upgrade.effects = def.effects.clone();
+                upgrade.max_level = def.max_level;
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Source of timestamps for the game, in milliseconds since the Unix epoch
+pub trait Clock {
+    fn now(&self) -> f64;
+}
+
+/// Wall clock of the browser, backed by `Date.now()`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now(&self) -> f64 {
+        js_sys::Date::now()
+    }
+}
+
+/// Clock that only moves when told to; clones share the same time so a test
+/// can keep a handle while the game owns another
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<f64>>,
+}
+
+impl ManualClock {
+    pub fn new(start: f64) -> Self {
+        ManualClock {
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    pub fn set(&self, now: f64) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, millis: f64) {
+        self.now.set(self.now.get() + millis);
+    }
+
+    pub fn advance_secs(&self, secs: f64) {
+        self.advance(secs * 1000.0);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+}
+
+/// Wraps another clock and never reports a time earlier than one it already
+/// returned, so a system clock adjustment cannot produce negative elapsed time
+#[derive(Debug)]
+pub struct MonotonicClock<C: Clock> {
+    inner: C,
+    last: Cell<f64>,
+}
+
+impl<C: Clock> MonotonicClock<C> {
+    pub fn new(inner: C) -> Self {
+        MonotonicClock {
+            inner,
+            last: Cell::new(f64::NEG_INFINITY),
+        }
+    }
+}
+
+impl<C: Clock> Clock for MonotonicClock<C> {
+    fn now(&self) -> f64 {
+        let now = self.inner.now().max(self.last.get());
+        self.last.set(now);
+        now
+    }
+}
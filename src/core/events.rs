@@ -0,0 +1,185 @@
+use crate::state::BigNum;
+use crate::systems::RecipeItem;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Oldest events are dropped past this size if the frontend stops draining
+const MAX_QUEUED_EVENTS: usize = 512;
+
+/// What a failed purchase or craft was aimed at
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PurchaseTarget {
+    Building {
+        index: usize,
+    },
+    Upgrade {
+        index: usize,
+    },
+    Recipe {
+        id: String,
+    },
+    Prestige,
+    PrestigeUpgrade {
+        index: usize,
+    },
+    /// Assigning a worker
+    Worker {
+        index: usize,
+    },
+}
+
+/// Why a purchase or craft did not go through
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailureReason {
+    NotFound,
+    /// Every level of the upgrade is already owned
+    MaxLevel,
+    /// Not available yet; unlocking `feature` makes it so, when there is one
+    Locked {
+        feature: Option<String>,
+    },
+    /// The crafting queue has no room for another craft
+    QueueFull,
+    /// Needs at least one `building` owned
+    MissingBuilding {
+        building: String,
+    },
+    /// No building with this id or name exists
+    UnknownBuilding {
+        building: String,
+    },
+    /// All `slots` worker slots are already taken
+    NoWorkerSlots {
+        slots: u32,
+    },
+    InsufficientResources {
+        resource: String,
+        required: BigNum,
+        available: BigNum,
+    },
+}
+
+/// Something that happened in the simulation that the frontend may react to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    ResourceChanged {
+        resource: String,
+        amount: BigNum,
+        per_second: f64,
+    },
+    BuildingBought {
+        index: usize,
+        name: String,
+        count: u32,
+        next_cost: BigNum,
+    },
+    UpgradeBought {
+        index: usize,
+        name: String,
+        owned: u32,
+        next_cost: BigNum,
+    },
+    /// A craft was queued; its inputs are spent and it completes with `Crafted`
+    CraftQueued {
+        recipe_id: String,
+        /// Crafts in the batch
+        count: u32,
+        duration: f64,
+    },
+    /// A queued craft was cancelled and its inputs refunded
+    CraftCancelled {
+        recipe_id: String,
+    },
+    Crafted {
+        recipe_id: String,
+        /// Crafts in the batch
+        count: u32,
+        /// Bonus outputs the craft rolled, on top of the regular ones
+        bonus: Vec<RecipeItem>,
+    },
+    /// The converter building at `index` ran out of `resource` and stopped
+    ConverterStalled {
+        index: usize,
+        name: String,
+        resource: String,
+    },
+    /// The converter building at `index` stopped because its recipe is
+    /// locked; unlocking `feature` starts it again, when there is one
+    ConverterLocked {
+        index: usize,
+        name: String,
+        feature: Option<String>,
+    },
+    /// The converter building at `index` has its inputs and recipe again
+    ConverterResumed {
+        index: usize,
+        name: String,
+    },
+    AchievementUnlocked {
+        id: String,
+        name: String,
+        description: String,
+    },
+    FeatureUnlocked {
+        id: String,
+        name: String,
+    },
+    WorkerLeveledUp {
+        worker_index: usize,
+        name: String,
+        level: u32,
+    },
+    PurchaseFailed {
+        target: PurchaseTarget,
+        reason: FailureReason,
+    },
+    PrestigeUpgradeBought {
+        index: usize,
+        name: String,
+        owned: u32,
+        next_cost: BigNum,
+    },
+    Prestiged {
+        points_gained: BigNum,
+        points: BigNum,
+        prestige_count: u32,
+    },
+}
+
+/// Events accumulated between two drains by the frontend
+#[derive(Default, Debug)]
+pub struct EventQueue {
+    events: VecDeque<GameEvent>,
+}
+
+impl EventQueue {
+    /// Queue an event; a newer `ResourceChanged` replaces a pending one for
+    /// the same resource since only the latest value matters
+    pub fn push(&mut self, event: GameEvent) {
+        if let GameEvent::ResourceChanged { resource, .. } = &event {
+            self.events.retain(|queued| {
+                !matches!(queued, GameEvent::ResourceChanged { resource: r, .. } if r == resource)
+            });
+        }
+
+        self.events.push_back(event);
+        while self.events.len() > MAX_QUEUED_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
@@ -0,0 +1,1503 @@
+use crate::content::GameContent;
+use crate::core::clock::Clock;
+use crate::core::events::{EventQueue, FailureReason, GameEvent, PurchaseTarget};
+use crate::core::rng::Rng;
+use crate::entities::{Building, Upgrade, Worker};
+use crate::persistence::export::{
+    self, ExportSizeReport, ImportReport, Integrity, SaveFormat, DEFAULT_EXPORT_KEY,
+};
+use crate::persistence::{compact, slots};
+use crate::persistence::{
+    validation, LoadMode, MigrationReport, SaveError, SaveStorage, SavedGame, SlotMetadata,
+    SAVE_VERSION,
+};
+use crate::state::resources::{COINS, STONE, WOOD};
+use crate::state::{BigNum, GameState, ResourceId, Statistics};
+use crate::systems::crafting::{MAX_CRAFT_BATCH, MAX_QUEUED_CRAFTS};
+use crate::systems::offline::WorkerLevelGain;
+use crate::systems::prestige::{PrestigeSummary, PRESTIGE_POINTS};
+use crate::systems::production;
+use crate::systems::{
+    Achievement, AchievementReward, CraftingJob, CraftingRecipe, Modifiers, OfflineConfig,
+    OfflineReport, PrestigeState, RecipeItem, StatKey, UnlockedFeature, UpgradeEffect,
+};
+use std::rc::Rc;
+
+/// Platform-independent game simulation: owns all state and rules, and knows
+/// nothing about the DOM, so it runs natively as well as behind `IdleGame`
+pub struct GameCore {
+    pub state: GameState,
+    pub statistics: Statistics,
+    pub upgrades: Vec<Upgrade>,
+    pub buildings: Vec<Building>,
+    pub workers: Vec<Worker>,
+    pub achievements: Vec<Achievement>,
+    pub crafting_recipes: Vec<CraftingRecipe>,
+    /// Crafts in progress or waiting for a slot, oldest first
+    pub crafting_queue: Vec<CraftingJob>,
+    pub unlocked_features: Vec<UnlockedFeature>,
+    /// Progress kept across prestiges
+    pub prestige: PrestigeState,
+    /// Permanent upgrades bought with prestige points
+    pub prestige_upgrades: Vec<Upgrade>,
+    pub offline_config: OfflineConfig,
+    content: GameContent,
+    modifiers: Modifiers,
+    pending_offline_report: Option<OfflineReport>,
+    events: EventQueue,
+    /// Resource values last announced through `ResourceChanged`
+    reported_resources: Vec<(ResourceId, BigNum, f64)>,
+    /// What the last `load_json` had to migrate
+    last_migration: Option<MigrationReport>,
+    /// Secret that signs export codes
+    export_key: Vec<u8>,
+    /// Rolls for chance-based outputs
+    rng: Rng,
+    clock: Rc<dyn Clock>,
+}
+
+impl GameCore {
+    /// Create a new game with the embedded content whose timestamps and
+    /// elapsed time come from `clock`
+    pub fn new(clock: Rc<dyn Clock>) -> GameCore {
+        GameCore::with_content(clock, GameContent::embedded())
+    }
+
+    /// Create a new game instantiated from `content`
+    pub fn with_content(clock: Rc<dyn Clock>, content: GameContent) -> GameCore {
+        let now = clock.now();
+
+        GameCore {
+            state: GameState::new(content.new_resources(), now),
+            statistics: Statistics::default(),
+            upgrades: content.new_upgrades(),
+            buildings: content.new_buildings(),
+            workers: content.new_workers(),
+            achievements: content.new_achievements(),
+            crafting_recipes: content.new_recipes(),
+            crafting_queue: Vec::new(),
+            unlocked_features: content.new_unlocks(),
+            prestige: PrestigeState::default(),
+            prestige_upgrades: content.new_prestige_upgrades(),
+            content,
+            modifiers: Modifiers::default(),
+            offline_config: OfflineConfig::default(),
+            pending_offline_report: None,
+            events: EventQueue::default(),
+            reported_resources: Vec::new(),
+            last_migration: None,
+            export_key: DEFAULT_EXPORT_KEY.to_vec(),
+            rng: Rng::new(now.to_bits()),
+            clock,
+        }
+    }
+
+    /// Definitions this game was instantiated from
+    pub fn content(&self) -> &GameContent {
+        &self.content
+    }
+
+    /// Restart chance-based rolls from `seed`, making them reproducible
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Current time according to the injected clock
+    pub fn now(&self) -> f64 {
+        self.clock.now()
+    }
+
+    /// Take every event raised since the previous call, oldest first
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain()
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Announce resources whose amount or rate moved since the last announcement
+    fn emit_resource_changes(&mut self) {
+        let snapshot = self.resource_snapshot();
+        for entry in &snapshot {
+            if self.reported_resources.contains(entry) {
+                continue;
+            }
+            let (resource, amount, per_second) = entry.clone();
+            self.events.push(GameEvent::ResourceChanged {
+                resource,
+                amount,
+                per_second,
+            });
+        }
+        self.reported_resources = snapshot;
+    }
+
+    fn purchase_failed(&mut self, target: PurchaseTarget, reason: FailureReason) -> bool {
+        self.emit(GameEvent::PurchaseFailed { target, reason });
+        false
+    }
+
+    pub fn click_action(&mut self) {
+        let earned = self.state.coins_per_click;
+        self.statistics.total_clicks += 1;
+        self.earn(COINS, earned);
+        self.state.total_clicks += 1;
+
+        self.check_milestones();
+        self.emit_resource_changes();
+    }
+
+    pub fn buy_upgrade(&mut self, index: usize) -> bool {
+        let target = PurchaseTarget::Upgrade { index };
+        if index >= self.upgrades.len() {
+            return self.purchase_failed(target, FailureReason::NotFound);
+        }
+
+        if self.upgrades[index].is_maxed() {
+            return self.purchase_failed(target, FailureReason::MaxLevel);
+        }
+        let upgrade_cost = self.upgrades[index].cost;
+        if !self.state.resources.spend(COINS, upgrade_cost) {
+            let reason = FailureReason::InsufficientResources {
+                resource: COINS.to_string(),
+                required: upgrade_cost,
+                available: self.state.resources.amount(COINS),
+            };
+            return self.purchase_failed(target, reason);
+        }
+        let cost_multiplier = self
+            .content
+            .upgrade(&self.upgrades[index].id)
+            .map_or(1.5, |def| def.cost_multiplier);
+
+        let upgrade = &mut self.upgrades[index];
+        upgrade.owned += 1;
+        upgrade.cost *= cost_multiplier;
+        let event = GameEvent::UpgradeBought {
+            index,
+            name: upgrade.name.clone(),
+            owned: upgrade.owned,
+            next_cost: upgrade.cost,
+        };
+        self.statistics.upgrades_purchased += 1;
+
+        self.update_production();
+        self.emit(event);
+        self.check_milestones();
+        self.emit_resource_changes();
+        true
+    }
+
+    pub fn buy_building(&mut self, index: usize) -> bool {
+        let target = PurchaseTarget::Building { index };
+        if index >= self.buildings.len() {
+            return self.purchase_failed(target, FailureReason::NotFound);
+        }
+        if let Some(reason) = self.lock_reason(self.buildings[index].requires.as_deref()) {
+            return self.purchase_failed(target, reason);
+        }
+
+        let building_cost = self.building_price(index);
+        if !self.state.resources.spend(COINS, building_cost) {
+            let reason = FailureReason::InsufficientResources {
+                resource: COINS.to_string(),
+                required: building_cost,
+                available: self.state.resources.amount(COINS),
+            };
+            return self.purchase_failed(target, reason);
+        }
+        let cost_multiplier = self
+            .content
+            .building(&self.buildings[index].id)
+            .map_or(1.15, |def| def.cost_multiplier);
+        let building = &mut self.buildings[index];
+        building.count += 1;
+        building.cost *= cost_multiplier;
+        let event = GameEvent::BuildingBought {
+            index,
+            name: building.name.clone(),
+            count: building.count,
+            next_cost: building.cost * self.modifiers.cost_multiplier,
+        };
+        self.statistics.buildings_purchased += 1;
+        self.emit(event);
+
+        self.check_milestones();
+
+        self.update_production();
+        self.emit_resource_changes();
+        true
+    }
+
+    /// What the next building at `index` costs after cost reductions
+    pub fn building_price(&self, index: usize) -> BigNum {
+        self.buildings[index].cost * self.modifiers.cost_multiplier
+    }
+
+    /// Buildings as the player sees them, with cost reductions applied to `cost`
+    pub fn priced_buildings(&self) -> Vec<Building> {
+        self.buildings
+            .iter()
+            .map(|building| Building {
+                cost: building.cost * self.modifiers.cost_multiplier,
+                ..building.clone()
+            })
+            .collect()
+    }
+
+    /// Buy a level of the permanent upgrade at `index` with prestige points
+    pub fn buy_prestige_upgrade(&mut self, index: usize) -> bool {
+        let target = PurchaseTarget::PrestigeUpgrade { index };
+        if index >= self.prestige_upgrades.len() {
+            return self.purchase_failed(target, FailureReason::NotFound);
+        }
+        if self.prestige_upgrades[index].is_maxed() {
+            return self.purchase_failed(target, FailureReason::MaxLevel);
+        }
+        let cost = self.prestige_upgrades[index].cost;
+        if self.prestige.points < cost {
+            let reason = FailureReason::InsufficientResources {
+                resource: PRESTIGE_POINTS.to_string(),
+                required: cost,
+                available: self.prestige.points,
+            };
+            return self.purchase_failed(target, reason);
+        }
+        self.prestige.points -= cost;
+        let cost_multiplier = self
+            .content
+            .prestige_upgrade(&self.prestige_upgrades[index].id)
+            .map_or(1.5, |def| def.cost_multiplier);
+
+        let upgrade = &mut self.prestige_upgrades[index];
+        upgrade.owned += 1;
+        upgrade.cost *= cost_multiplier;
+        let event = GameEvent::PrestigeUpgradeBought {
+            index,
+            name: upgrade.name.clone(),
+            owned: upgrade.owned,
+            next_cost: upgrade.cost,
+        };
+
+        self.update_production();
+        self.emit(event);
+        self.emit_resource_changes();
+        true
+    }
+
+    /// Combined effects of everything the player owns, as of the last
+    /// `update_production`
+    pub fn modifiers(&self) -> &Modifiers {
+        &self.modifiers
+    }
+
+    pub fn craft_resource(&mut self, recipe_id: &str) -> bool {
+        self.craft_resource_times(recipe_id, 1) == 1
+    }
+
+    /// Craft `recipe_id` up to `times` times in one batch, as many as the
+    /// inputs allow. The batch is spent, queued or produced at once and
+    /// checked for milestones once; returns how many crafts happened
+    pub fn craft_resource_times(&mut self, recipe_id: &str, times: u32) -> u32 {
+        let target = PurchaseTarget::Recipe {
+            id: recipe_id.to_string(),
+        };
+        let recipe = match self.crafting_recipes.iter().find(|r| r.id == recipe_id) {
+            Some(r) => r.clone(),
+            None => return self.craft_failed(target, FailureReason::NotFound),
+        };
+        if !recipe.unlocked {
+            return self.craft_failed(target, FailureReason::Locked { feature: None });
+        }
+        if let Some(reason) = self.lock_reason(recipe.requires.as_deref()) {
+            return self.craft_failed(target, reason);
+        }
+
+        if let Some(building) = &recipe.requires_building {
+            if !self
+                .buildings
+                .iter()
+                .any(|b| &b.id == building && b.count > 0)
+            {
+                let reason = FailureReason::MissingBuilding {
+                    building: building.clone(),
+                };
+                return self.craft_failed(target, reason);
+            }
+        }
+
+        if !recipe.resources().all(|r| self.state.resources.contains(r)) {
+            return self.craft_failed(target, FailureReason::NotFound);
+        }
+        if times == 0 {
+            return 0;
+        }
+        if let Some((input, available)) = recipe.missing_input(&self.state.resources) {
+            let reason = FailureReason::InsufficientResources {
+                resource: input.resource.clone(),
+                required: input.amount.into(),
+                available,
+            };
+            return self.craft_failed(target, reason);
+        }
+        let queued = recipe.duration > 0.0;
+        if queued && self.crafting_queue.len() >= MAX_QUEUED_CRAFTS {
+            return self.craft_failed(target, FailureReason::QueueFull);
+        }
+
+        let count = recipe.affordable(&self.state.resources).min(times);
+        for input in &recipe.inputs {
+            self.state
+                .resources
+                .spend(&input.resource, input.amount * count as f64);
+        }
+        if queued {
+            let job = CraftingJob::new(&recipe, count);
+            self.emit(GameEvent::CraftQueued {
+                recipe_id: recipe.id.clone(),
+                count,
+                duration: job.duration,
+            });
+            self.crafting_queue.push(job);
+        } else {
+            self.complete_craft(&recipe, count);
+        }
+
+        self.check_milestones();
+        self.emit_resource_changes();
+        count
+    }
+
+    /// Craft `recipe_id` as many times as the inputs allow, up to
+    /// `MAX_CRAFT_BATCH`; returns how many crafts happened
+    pub fn craft_max(&mut self, recipe_id: &str) -> u32 {
+        self.craft_resource_times(recipe_id, MAX_CRAFT_BATCH)
+    }
+
+    /// `purchase_failed` for crafts, which count rather than succeed
+    fn craft_failed(&mut self, target: PurchaseTarget, reason: FailureReason) -> u32 {
+        self.purchase_failed(target, reason);
+        0
+    }
+
+    /// Produce the outputs of `count` crafts of `recipe`, rolling for the
+    /// bonuses of each
+    fn complete_craft(&mut self, recipe: &CraftingRecipe, count: u32) {
+        let bonus = self.produce_outputs(recipe, count);
+        self.statistics.total_resources_crafted += count;
+        self.emit(GameEvent::Crafted {
+            recipe_id: recipe.id.clone(),
+            count,
+            bonus,
+        });
+    }
+
+    /// Add the outputs of `count` crafts of `recipe` to the stockpile and
+    /// return the bonus outputs rolled
+    fn produce_outputs(&mut self, recipe: &CraftingRecipe, count: u32) -> Vec<RecipeItem> {
+        for output in &recipe.outputs {
+            self.earn(&output.resource, output.amount * count as f64);
+        }
+        let mut bonus = Vec::new();
+        for output in &recipe.bonus_outputs {
+            let hits = (0..count).filter(|_| self.rng.roll(output.chance)).count();
+            if hits > 0 {
+                let amount = output.amount * hits as f64;
+                self.earn(&output.resource, amount);
+                bonus.push(RecipeItem::new(&output.resource, amount));
+            }
+        }
+        bonus
+    }
+
+    /// Add a gain of `resource`, counting it towards the run's and the
+    /// lifetime totals; every source of new resources goes through here
+    fn earn(&mut self, resource: &str, amount: impl Into<BigNum>) {
+        let amount = amount.into();
+        if self.state.resources.earn(resource, amount) && amount > BigNum::ZERO {
+            self.statistics.record_earned(resource, amount);
+        }
+    }
+
+    /// Let converter buildings run their recipes for `seconds`: each crafts
+    /// as often as time allows and the stockpile can pay for, and stalls on
+    /// an input that runs short until there is enough of it again
+    fn run_converters(&mut self, seconds: f64) {
+        for index in 0..self.buildings.len() {
+            let building = &self.buildings[index];
+            let Some(converter) = &building.converter else {
+                continue;
+            };
+            let recipe = self
+                .crafting_recipes
+                .iter()
+                .find(|r| r.id == converter.recipe)
+                .cloned();
+            let Some(recipe) = recipe.filter(|_| building.count > 0) else {
+                continue;
+            };
+            // A converter never crafts what the player could not craft by hand
+            let lock = match self.lock_reason(recipe.requires.as_deref()) {
+                Some(FailureReason::Locked { feature }) => Some(feature),
+                _ if !recipe.unlocked => Some(None),
+                _ => None,
+            };
+            if let Some(feature) = lock {
+                let building = &mut self.buildings[index];
+                let Some(converter) = building.converter.as_mut() else {
+                    continue;
+                };
+                if !converter.locked {
+                    converter.locked = true;
+                    let name = building.name.clone();
+                    self.emit(GameEvent::ConverterLocked {
+                        index,
+                        name,
+                        feature,
+                    });
+                }
+                continue;
+            }
+            let speed = converter.rate
+                * building.count as f64
+                * production::get_worker_bonus_for_building(&self.workers, &building.id)
+                * self.modifiers.building_multiplier(&building.id);
+            let progress = converter.progress + seconds * speed;
+            if !progress.is_finite() {
+                continue;
+            }
+            let due = progress.floor();
+            let crafts = (recipe.affordable(&self.state.resources) as f64).min(due) as u32;
+
+            for input in &recipe.inputs {
+                self.state
+                    .resources
+                    .spend(&input.resource, input.amount * crafts as f64);
+            }
+            self.produce_outputs(&recipe, crafts);
+
+            let building = &mut self.buildings[index];
+            let Some(converter) = building.converter.as_mut() else {
+                continue;
+            };
+            // Time spent stalled is lost rather than banked
+            converter.progress = progress - due;
+            let stalled_on = if (crafts as f64) < due {
+                recipe
+                    .missing_input(&self.state.resources)
+                    .map(|(input, _)| input.resource.clone())
+            } else if due >= 1.0 {
+                None
+            } else {
+                converter.stalled_on.clone()
+            };
+            let was_locked = std::mem::take(&mut converter.locked);
+            if stalled_on == converter.stalled_on && !was_locked {
+                continue;
+            }
+            converter.stalled_on = stalled_on.clone();
+            let name = building.name.clone();
+            let event = match stalled_on {
+                Some(resource) => GameEvent::ConverterStalled {
+                    index,
+                    name,
+                    resource,
+                },
+                None => GameEvent::ConverterResumed { index, name },
+            };
+            self.emit(event);
+        }
+    }
+
+    /// Run the crafting queue for `seconds`: the first `crafting_slots` jobs
+    /// progress together, and a slot freed by a finished job passes straight
+    /// to the next one. Returns the recipes completed, in order
+    fn advance_crafting(&mut self, mut seconds: f64) -> Vec<String> {
+        let mut completed = Vec::new();
+        while seconds > 0.0 && !self.crafting_queue.is_empty() {
+            let slots = (self.modifiers.crafting_slots() as usize).min(self.crafting_queue.len());
+            let step = self.crafting_queue[..slots]
+                .iter()
+                .map(CraftingJob::remaining)
+                .fold(seconds, f64::min);
+            seconds -= step;
+
+            let mut finished = Vec::new();
+            let mut index = 0;
+            for _ in 0..slots {
+                let job = &mut self.crafting_queue[index];
+                if job.remaining() <= step {
+                    finished.push(self.crafting_queue.remove(index));
+                } else {
+                    job.progress += step;
+                    index += 1;
+                }
+            }
+            for job in finished {
+                // Recipes removed from content complete with nothing
+                let recipe = self
+                    .crafting_recipes
+                    .iter()
+                    .find(|r| r.id == job.recipe_id)
+                    .cloned();
+                if let Some(recipe) = recipe {
+                    self.complete_craft(&recipe, job.count);
+                }
+                completed.push(job.recipe_id);
+            }
+        }
+        completed
+    }
+
+    /// Drop the queued craft at `index` and refund its inputs
+    pub fn cancel_craft(&mut self, index: usize) -> bool {
+        if index >= self.crafting_queue.len() {
+            return false;
+        }
+        let job = self.crafting_queue.remove(index);
+        if let Some(recipe) = self.crafting_recipes.iter().find(|r| r.id == job.recipe_id) {
+            for input in &recipe.inputs {
+                self.state
+                    .resources
+                    .refund(&input.resource, input.amount * job.count as f64);
+            }
+        }
+        self.emit(GameEvent::CraftCancelled {
+            recipe_id: job.recipe_id,
+        });
+        self.emit_resource_changes();
+        true
+    }
+
+    /// Queued crafts that progress at once
+    pub fn crafting_slots(&self) -> u32 {
+        self.modifiers.crafting_slots()
+    }
+
+    /// Workers that may be assigned to buildings at once
+    pub fn worker_slots(&self) -> u32 {
+        self.modifiers.worker_slots()
+    }
+
+    /// Amount of any resource; 0 if it does not exist
+    pub fn resource_amount(&self, id: &str) -> BigNum {
+        self.state.resources.amount(id)
+    }
+
+    /// `resource_amount` as an `f64`, saturating for amounts past its range
+    pub fn get_resource(&self, id: &str) -> f64 {
+        self.resource_amount(id).to_f64()
+    }
+
+    /// Production per second of any resource; 0 if it does not exist
+    pub fn get_resource_rate(&self, id: &str) -> f64 {
+        self.state.resources.rate(id)
+    }
+
+    pub fn get_coins(&self) -> f64 {
+        self.get_resource(COINS)
+    }
+
+    pub fn get_wood(&self) -> f64 {
+        self.get_resource(WOOD)
+    }
+
+    pub fn get_stone(&self) -> f64 {
+        self.get_resource(STONE)
+    }
+
+    pub fn get_coins_per_second(&self) -> f64 {
+        self.get_resource_rate(COINS)
+    }
+
+    pub fn get_wood_per_second(&self) -> f64 {
+        self.get_resource_rate(WOOD)
+    }
+
+    pub fn get_stone_per_second(&self) -> f64 {
+        self.get_resource_rate(STONE)
+    }
+
+    pub fn get_coins_per_click(&self) -> f64 {
+        self.state.coins_per_click
+    }
+
+    /// Put a worker in a building, given by id or, for older callers, by name;
+    /// a worker who is not assigned yet needs a free worker slot, and an empty
+    /// id unassigns them
+    pub fn assign_worker(&mut self, worker_index: usize, building_id: &str) -> bool {
+        if building_id.is_empty() {
+            return self.unassign_worker(worker_index);
+        }
+        let target = PurchaseTarget::Worker {
+            index: worker_index,
+        };
+        if worker_index >= self.workers.len() {
+            return self.purchase_failed(target, FailureReason::NotFound);
+        }
+        if let Some(reason) = self.lock_reason(self.workers[worker_index].requires.as_deref()) {
+            return self.purchase_failed(target, reason);
+        }
+
+        let building_id = match self
+            .buildings
+            .iter()
+            .find(|b| b.id == building_id)
+            .or_else(|| self.buildings.iter().find(|b| b.name == building_id))
+        {
+            Some(building) => building.id.clone(),
+            None => {
+                let reason = FailureReason::UnknownBuilding {
+                    building: building_id.to_string(),
+                };
+                return self.purchase_failed(target, reason);
+            }
+        };
+
+        // Moving an assigned worker frees their old slot
+        let assigned = self
+            .workers
+            .iter()
+            .filter(|w| w.assigned_building.is_some())
+            .count() as u32;
+        let slots = self.worker_slots();
+        if self.workers[worker_index].assigned_building.is_none() && assigned >= slots {
+            return self.purchase_failed(target, FailureReason::NoWorkerSlots { slots });
+        }
+
+        let worker = &mut self.workers[worker_index];
+
+        let mut efficiency = 1.0;
+
+        if worker.preferences == building_id {
+            efficiency += 0.2;
+        }
+
+        efficiency += (worker.level as f64) * 0.05;
+
+        worker.efficiency_multiplier = efficiency;
+        worker.assigned_building = Some(building_id);
+
+        self.update_production();
+        self.check_milestones();
+        self.emit_resource_changes();
+
+        true
+    }
+
+    /// Take a worker out of their building, freeing their worker slot
+    pub fn unassign_worker(&mut self, worker_index: usize) -> bool {
+        let Some(worker) = self.workers.get_mut(worker_index) else {
+            let target = PurchaseTarget::Worker {
+                index: worker_index,
+            };
+            return self.purchase_failed(target, FailureReason::NotFound);
+        };
+        if worker.assigned_building.take().is_none() {
+            return true;
+        }
+
+        self.update_production();
+        self.check_milestones();
+        self.emit_resource_changes();
+
+        true
+    }
+
+    pub fn get_worker_production_bonus(&self, worker_index: usize) -> f64 {
+        if worker_index >= self.workers.len() {
+            return 0.0;
+        }
+
+        let worker = &self.workers[worker_index];
+        if worker.assigned_building.is_none() {
+            return 0.0;
+        }
+
+        worker.efficiency_multiplier - 1.0
+    }
+
+    /// Recompute modifiers, click power and per-second rates from buildings,
+    /// upgrades and workers
+    pub fn update_production(&mut self) {
+        self.modifiers =
+            production::collect_modifiers(self.upgrades.iter().chain(&self.prestige_upgrades));
+        for achievement in &self.achievements {
+            achievement.apply_to(&mut self.modifiers);
+        }
+        self.prestige.apply_to(&mut self.modifiers);
+        self.state.coins_per_click = self.modifiers.coins_per_click();
+        self.state.autoclick_count = self.modifiers.autoclickers;
+
+        let rates = production::update_production(
+            &self.buildings,
+            &self.workers,
+            &self.state.resources,
+            &self.modifiers,
+        );
+        for resource in self.state.resources.iter_mut() {
+            resource.per_second = rates.get(&resource.id).copied().unwrap_or(0.0);
+        }
+    }
+
+    /// Grant XP to assigned workers; caller is responsible for `update_production`
+    pub fn grant_worker_xp(&mut self, elapsed: f64) {
+        let levels_before: Vec<u32> = self.workers.iter().map(|w| w.level).collect();
+        production::grant_worker_xp(
+            &mut self.workers,
+            elapsed,
+            self.modifiers.worker_xp_multiplier,
+        );
+
+        for (worker_index, before) in levels_before.into_iter().enumerate() {
+            let worker = &self.workers[worker_index];
+            if worker.level > before {
+                let event = GameEvent::WorkerLeveledUp {
+                    worker_index,
+                    name: worker.name.clone(),
+                    level: worker.level,
+                };
+                self.emit(event);
+            }
+        }
+    }
+
+    /// Advance the simulation to the clock's current time; a gap longer than
+    /// the offline threshold is credited as offline progress instead
+    pub fn game_loop(&mut self) {
+        let now = self.clock.now();
+        let elapsed = (now - self.state.last_update_time) / 1000.0;
+
+        if elapsed > self.offline_config.threshold_seconds {
+            let report = self.apply_offline_progress(elapsed);
+            match self.pending_offline_report.as_mut() {
+                Some(pending) => pending.merge(report),
+                None => self.pending_offline_report = Some(report),
+            }
+            self.state.last_update_time = now;
+        } else if elapsed > 0.0 {
+            self.accrue_production(elapsed);
+            self.run_converters(elapsed);
+            self.advance_crafting(elapsed);
+            self.state.last_update_time = now;
+
+            self.statistics.play_time_seconds += elapsed;
+
+            // Grant XP to assigned workers, then refresh rates for any level-ups
+            self.grant_worker_xp(elapsed);
+            self.update_production();
+        }
+
+        self.check_milestones();
+        self.emit_resource_changes();
+    }
+
+    /// Add `seconds` worth of building and autoclicker output; consumers stop
+    /// once their inputs run out
+    fn accrue_production(&mut self, seconds: f64) {
+        let mut gains = production::step_production(
+            &self.buildings,
+            &self.workers,
+            &self.state.resources,
+            &self.modifiers,
+            seconds,
+        );
+
+        if self.state.autoclick_count > 0 {
+            let autoclicks_per_second = 10.0;
+            let autoclick_effect = self.state.coins_per_click
+                * self.state.autoclick_count as f64
+                * autoclicks_per_second
+                * seconds;
+            if autoclick_effect.is_finite() {
+                *gains.entry(COINS.to_string()).or_insert(0.0) += autoclick_effect;
+            }
+        }
+
+        for (resource, gained) in gains {
+            if gained > 0.0 {
+                self.earn(&resource, gained);
+            } else if gained < 0.0 {
+                let left = (self.state.resources.amount(&resource) + gained).max(BigNum::ZERO);
+                self.state.resources.set_amount(&resource, left);
+            }
+        }
+    }
+
+    fn resource_snapshot(&self) -> Vec<(ResourceId, BigNum, f64)> {
+        self.state
+            .resources
+            .iter()
+            .map(|r| (r.id.clone(), r.amount, r.per_second))
+            .collect()
+    }
+
+    fn resource_amounts(&self) -> Vec<(ResourceId, BigNum)> {
+        self.state
+            .resources
+            .iter()
+            .map(|r| (r.id.clone(), r.amount))
+            .collect()
+    }
+
+    /// Credit production, worker XP and achievements for `elapsed_seconds`
+    /// spent away, subject to the offline cap and efficiency; the credited
+    /// time also counts as play time
+    pub fn apply_offline_progress(&mut self, elapsed_seconds: f64) -> OfflineReport {
+        let credited = self.offline_config.credited_seconds(elapsed_seconds);
+        let effective = self.offline_config.effective_seconds(elapsed_seconds);
+        self.statistics.play_time_seconds += credited;
+
+        let resources_before = self.resource_amounts();
+        let levels_before: Vec<u32> = self.workers.iter().map(|w| w.level).collect();
+        let unlocked_before: Vec<bool> = self.achievements.iter().map(|a| a.unlocked).collect();
+
+        self.accrue_production(effective);
+        self.run_converters(effective);
+        let crafts_completed = self.advance_crafting(credited);
+        self.grant_worker_xp(effective);
+        self.update_production();
+        self.check_milestones();
+
+        let resources_gained = self
+            .resource_amounts()
+            .iter()
+            .zip(resources_before.iter())
+            .map(|((resource, after), (_, before))| (resource.clone(), *after - *before))
+            .collect();
+
+        let worker_levels_gained = self
+            .workers
+            .iter()
+            .zip(levels_before)
+            .filter(|(worker, before)| worker.level > *before)
+            .map(|(worker, before)| WorkerLevelGain {
+                worker: worker.name.clone(),
+                levels: worker.level - before,
+            })
+            .collect();
+
+        let achievements_unlocked = self
+            .achievements
+            .iter()
+            .zip(unlocked_before)
+            .filter(|(achievement, before)| achievement.unlocked && !before)
+            .map(|(achievement, _)| achievement.id.clone())
+            .collect();
+
+        OfflineReport {
+            duration_seconds: elapsed_seconds,
+            credited_seconds: credited,
+            efficiency: self.offline_config.efficiency,
+            resources_gained,
+            worker_levels_gained,
+            achievements_unlocked,
+            crafts_completed,
+        }
+    }
+
+    /// Hand the most recent offline catch-up to the caller, if any
+    pub fn take_offline_report(&mut self) -> Option<OfflineReport> {
+        self.pending_offline_report.take()
+    }
+
+    pub fn unlock_feature(&mut self, feature_id: &str) -> bool {
+        if !self.check_unlock(feature_id) {
+            return false;
+        }
+
+        match self
+            .unlocked_features
+            .iter()
+            .position(|f| f.id == feature_id)
+        {
+            Some(index) => {
+                self.mark_feature_unlocked(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unlock the feature at `index` whatever its requirement says
+    fn mark_feature_unlocked(&mut self, index: usize) {
+        let now = self.clock.now();
+        let feature = &mut self.unlocked_features[index];
+        if feature.unlocked {
+            return;
+        }
+
+        feature.unlocked = true;
+        feature.unlock_timestamp = Some(now);
+        let event = GameEvent::FeatureUnlocked {
+            id: feature.id.clone(),
+            name: feature.name.clone(),
+        };
+        self.emit(event);
+    }
+
+    /// Start over from the content definitions, keeping the same content
+    pub fn reset_game(&mut self) {
+        let content = &self.content;
+        self.statistics = Statistics::default();
+        self.achievements = content.new_achievements();
+        self.crafting_recipes = content.new_recipes();
+        self.crafting_queue.clear();
+        self.unlocked_features = content.new_unlocks();
+        self.workers = content.new_workers();
+        self.upgrades = content.new_upgrades();
+        self.buildings = content.new_buildings();
+        self.state = GameState::new(content.new_resources(), self.clock.now());
+        self.prestige = PrestigeState::default();
+        self.prestige_upgrades = content.new_prestige_upgrades();
+        self.modifiers = Modifiers::default();
+
+        self.emit_resource_changes();
+    }
+
+    /// Prestige points that prestiging now would add
+    pub fn pending_prestige_points(&self) -> BigNum {
+        self.prestige
+            .pending_points(self.statistics.total_coins_earned)
+    }
+
+    pub fn prestige_summary(&self) -> PrestigeSummary {
+        self.prestige.summary(self.statistics.total_coins_earned)
+    }
+
+    /// Trade the current run for prestige points: resources, buildings,
+    /// upgrades, workers and queued crafts start over, while prestige
+    /// progress, statistics, achievements, recipes and unlocks are kept
+    pub fn prestige(&mut self) -> bool {
+        let points = self.pending_prestige_points();
+        if points < 1.0 {
+            let reason = FailureReason::InsufficientResources {
+                resource: COINS.to_string(),
+                required: self.prestige.coins_for_next_point(),
+                available: self.statistics.total_coins_earned,
+            };
+            return self.purchase_failed(PurchaseTarget::Prestige, reason);
+        }
+
+        self.prestige.claim(points);
+        let content = &self.content;
+        let mut upgrades = content.new_upgrades();
+        if self.modifiers.keep_autoclickers {
+            for (upgrade, kept) in upgrades.iter_mut().zip(&self.upgrades) {
+                let autoclicker = kept
+                    .effects
+                    .iter()
+                    .any(|effect| matches!(effect, UpgradeEffect::Autoclicker { .. }));
+                if autoclicker && upgrade.id == kept.id {
+                    *upgrade = kept.clone();
+                }
+            }
+        }
+        self.workers = content.new_workers();
+        self.upgrades = upgrades;
+        self.crafting_queue.clear();
+        self.buildings = content.new_buildings();
+        self.state = GameState::new(content.new_resources(), self.clock.now());
+        self.update_production();
+        for (resource, amount) in self.modifiers.starting_resources.clone() {
+            self.earn(&resource, amount);
+        }
+
+        self.check_milestones();
+        self.emit(GameEvent::Prestiged {
+            points_gained: points,
+            points: self.prestige.points,
+            prestige_count: self.prestige.prestige_count,
+        });
+        self.emit_resource_changes();
+        true
+    }
+
+    /// Current value of `stat`, as achievement conditions see it
+    pub fn stat(&self, stat: &StatKey) -> f64 {
+        match stat {
+            StatKey::Clicks => self.state.total_clicks as f64,
+            StatKey::TotalClicks => self.statistics.total_clicks as f64,
+            StatKey::BuildingsPurchased => self.statistics.buildings_purchased as f64,
+            StatKey::UpgradesPurchased => self.statistics.upgrades_purchased as f64,
+            StatKey::ResourcesCrafted => self.statistics.total_resources_crafted as f64,
+            StatKey::AchievementsUnlocked => self.statistics.achievements_unlocked_count as f64,
+            StatKey::PlayTimeSeconds => self.statistics.play_time_seconds,
+            StatKey::PrestigeCount => self.prestige.prestige_count as f64,
+            StatKey::WorkersAssigned => self
+                .workers
+                .iter()
+                .filter(|w| w.assigned_building.is_some())
+                .count() as f64,
+            StatKey::Resource(id) => self.state.resources.amount(id).to_f64(),
+            StatKey::Earned(id) => self.statistics.earned(id).to_f64(),
+            StatKey::Building(id) => self
+                .buildings
+                .iter()
+                .find(|b| &b.id == id)
+                .map_or(0.0, |b| b.count as f64),
+            StatKey::Upgrade(id) => self
+                .upgrades
+                .iter()
+                .find(|u| &u.id == id)
+                .map_or(0.0, |u| u.owned as f64),
+        }
+    }
+
+    /// Update the progress of one achievement and unlock it if its
+    /// condition is met; true if it is unlocked afterwards
+    pub fn check_achievement(&mut self, achievement_id: &str) -> bool {
+        let Some(index) = self
+            .achievements
+            .iter()
+            .position(|a| a.id == achievement_id)
+        else {
+            return false;
+        };
+        if self.check_achievement_at(index) {
+            // Unlocking may complete achievements that count other achievements
+            self.check_all_achievements();
+        }
+        self.achievements[index].unlocked
+    }
+
+    /// Unlock every feature and achievement that is ready, after any action
+    /// or tick that may have moved a stat
+    pub fn check_milestones(&mut self) {
+        // A feature can be another's prerequisite and achievements can count
+        // on each other, so each kind repeats until nothing changes
+        while self.check_unlocks() {}
+        self.check_all_achievements();
+        while self.check_unlocks() {}
+    }
+
+    /// Update every achievement and unlock those whose condition is met
+    pub fn check_all_achievements(&mut self) {
+        // Each unlock raises achievements_unlocked, so repeat until nothing changes
+        loop {
+            let mut unlocked_any = false;
+            for index in 0..self.achievements.len() {
+                unlocked_any |= self.check_achievement_at(index);
+            }
+            if !unlocked_any {
+                break;
+            }
+        }
+    }
+
+    /// True only if the achievement at `index` was unlocked by this call
+    fn check_achievement_at(&mut self, index: usize) -> bool {
+        let now = self.clock.now();
+        // Conditions never read achievements, so they can be set aside while
+        // the stats are looked up
+        let mut achievements = std::mem::take(&mut self.achievements);
+        let achievement = &mut achievements[index];
+        let unlocked =
+            !achievement.unlocked && achievement.check_progress(&|stat| self.stat(stat), now);
+        let event = unlocked.then(|| GameEvent::AchievementUnlocked {
+            id: achievement.id.clone(),
+            name: achievement.name.clone(),
+            description: achievement.description.clone(),
+        });
+        self.achievements = achievements;
+
+        if let Some(event) = event {
+            self.emit(event);
+            self.statistics.achievements_unlocked_count += 1;
+            self.grant_rewards(index);
+        }
+        unlocked
+    }
+
+    /// Hand out the one-time rewards of the achievement at `index`, and pick
+    /// up its bonuses
+    fn grant_rewards(&mut self, index: usize) {
+        let achievement = &self.achievements[index];
+        let rewards = achievement.rewards.clone();
+        let has_effects = !achievement.effects.is_empty();
+        for reward in rewards {
+            match reward {
+                AchievementReward::Grant { resource, amount } => {
+                    self.earn(&resource, amount);
+                }
+                AchievementReward::UnlockRecipe { recipe } => {
+                    if let Some(recipe) = self.crafting_recipes.iter_mut().find(|r| r.id == recipe)
+                    {
+                        recipe.unlocked = true;
+                    }
+                }
+                AchievementReward::UnlockFeature { feature } => {
+                    if let Some(index) = self.unlocked_features.iter().position(|f| f.id == feature)
+                    {
+                        self.mark_feature_unlocked(index);
+                    }
+                }
+            }
+        }
+        if has_effects {
+            self.update_production();
+        }
+    }
+
+    pub fn is_feature_unlocked(&self, feature_id: &str) -> bool {
+        self.unlocked_features
+            .iter()
+            .any(|f| f.id == feature_id && f.unlocked)
+    }
+
+    /// Why content requiring `requires` cannot be used yet, if it cannot
+    fn lock_reason(&self, requires: Option<&str>) -> Option<FailureReason> {
+        let feature = requires?;
+        if self.is_feature_unlocked(feature) {
+            return None;
+        }
+        Some(FailureReason::Locked {
+            feature: Some(feature.to_string()),
+        })
+    }
+
+    /// Whether the feature could be unlocked now: its prerequisites are
+    /// unlocked and its condition is met
+    pub fn check_unlock(&self, feature_id: &str) -> bool {
+        let feature = match self.unlocked_features.iter().find(|f| f.id == feature_id) {
+            Some(f) => f,
+            None => return false,
+        };
+
+        if feature.unlocked {
+            return true;
+        }
+
+        feature
+            .prerequisites
+            .iter()
+            .all(|p| self.is_feature_unlocked(p))
+            && feature.condition.is_met(&|stat| self.stat(stat))
+    }
+
+    /// Update every feature's progress and unlock those that are ready;
+    /// true if any was unlocked
+    fn check_unlocks(&mut self) -> bool {
+        let mut unlocked_any = false;
+        for index in 0..self.unlocked_features.len() {
+            if self.unlocked_features[index].unlocked {
+                continue;
+            }
+            // Conditions never read features, so they can be set aside while
+            // the stats are looked up
+            let mut features = std::mem::take(&mut self.unlocked_features);
+            let ready = features[index]
+                .prerequisites
+                .iter()
+                .all(|p| features.iter().any(|f| &f.id == p && f.unlocked));
+            let met = features[index].check_progress(&|stat| self.stat(stat));
+            self.unlocked_features = features;
+
+            if ready && met {
+                self.mark_feature_unlocked(index);
+                unlocked_any = true;
+            }
+        }
+        unlocked_any
+    }
+
+    /// Serialize entire game state to SavedGame structure
+    pub fn save_game(&self) -> SavedGame {
+        SavedGame {
+            save_version: SAVE_VERSION,
+            state: self.state.clone(),
+            statistics: self.statistics.clone(),
+            upgrades: self.upgrades.clone(),
+            buildings: self.buildings.clone(),
+            workers: self.workers.clone(),
+            achievements: self.achievements.clone(),
+            crafting_recipes: self.crafting_recipes.clone(),
+            crafting_queue: self.crafting_queue.clone(),
+            unlocked_features: self.unlocked_features.clone(),
+            prestige: self.prestige.clone(),
+            prestige_upgrades: self.prestige_upgrades.clone(),
+            save_timestamp: self.clock.now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Load game state from SavedGame structure, repairing anything
+    /// validation rejects; content entries the save does not know about are
+    /// added fresh, and returned as "kind:id"
+    pub fn load_game(&mut self, mut saved: SavedGame) -> Vec<String> {
+        validation::repair(&mut saved, &self.content);
+        self.state = saved.state;
+        self.statistics = saved.statistics;
+        self.upgrades = saved.upgrades;
+        self.buildings = saved.buildings;
+        self.workers = saved.workers;
+        self.content.sync_saved_entities(
+            &mut self.buildings,
+            &mut self.upgrades,
+            &mut self.workers,
+        );
+        self.achievements = saved.achievements;
+        self.content.sync_achievements(&mut self.achievements);
+        self.crafting_recipes = saved.crafting_recipes;
+        self.content.sync_recipes(&mut self.crafting_recipes);
+        self.crafting_queue = saved.crafting_queue;
+        self.unlocked_features = saved.unlocked_features;
+        self.content.sync_unlocks(&mut self.unlocked_features);
+        self.prestige = saved.prestige;
+        self.prestige_upgrades = saved.prestige_upgrades;
+        self.content
+            .sync_prestige_upgrades(&mut self.prestige_upgrades);
+
+        let mut added = Vec::new();
+        let content = &self.content;
+        merge_new_entries(
+            &mut self.buildings,
+            content.new_buildings(),
+            "building",
+            |b| &b.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.upgrades,
+            content.new_upgrades(),
+            "upgrade",
+            |u| &u.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.workers,
+            content.new_workers(),
+            "worker",
+            |w| &w.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.achievements,
+            content.new_achievements(),
+            "achievement",
+            |a| &a.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.crafting_recipes,
+            content.new_recipes(),
+            "recipe",
+            |r| &r.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.unlocked_features,
+            content.new_unlocks(),
+            "unlock",
+            |u| &u.id,
+            &mut added,
+        );
+        merge_new_entries(
+            &mut self.prestige_upgrades,
+            content.new_prestige_upgrades(),
+            "prestige_upgrade",
+            |u| &u.id,
+            &mut added,
+        );
+
+        self.content.register_resources(&mut self.state.resources);
+        self.update_production();
+        self.emit_resource_changes();
+        added
+    }
+
+    /// Parse, migrate, repair and load a JSON save
+    pub fn load_json(&mut self, json: &str) -> Result<MigrationReport, SaveError> {
+        self.load_json_with_mode(json, LoadMode::Repair)
+    }
+
+    /// Like `load_json`; in `LoadMode::Strict` a save with problems is
+    /// rejected and the current game left as it was
+    pub fn load_json_with_mode(
+        &mut self,
+        json: &str,
+        mode: LoadMode,
+    ) -> Result<MigrationReport, SaveError> {
+        let (saved, report) = SavedGame::from_json_with_report(json)?;
+        self.load_checked(saved, report, mode)
+    }
+
+    /// Validate an up-to-date save, then load it as `mode` allows
+    fn load_checked(
+        &mut self,
+        saved: SavedGame,
+        mut report: MigrationReport,
+        mode: LoadMode,
+    ) -> Result<MigrationReport, SaveError> {
+        let problems = validation::validate(&saved, &self.content);
+        if mode == LoadMode::Strict && !problems.is_empty() {
+            return Err(SaveError::Invalid { problems });
+        }
+        report.repaired = problems;
+        report.content_added = self.load_game(saved);
+        self.last_migration = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Sign and verify export codes with `key`, e.g. a secret shared by a
+    /// group comparing scores, instead of the default key
+    pub fn set_export_key(&mut self, key: &[u8]) {
+        self.export_key = key.to_vec();
+    }
+
+    /// The current game as a signed JSON export code
+    pub fn export_code(&self) -> String {
+        self.export_code_as(SaveFormat::Json)
+    }
+
+    /// The current game as a signed export code in `format`
+    pub fn export_code_as(&self, format: SaveFormat) -> String {
+        let saved = self.save_game();
+        let payload = match format {
+            SaveFormat::Json => serde_json::to_vec(&saved).expect("saves always serialize"),
+            SaveFormat::Compact => compact::pack(&saved),
+        };
+        export::encode(format, &payload, &self.export_key)
+    }
+
+    /// How long the current game's export codes are in each format
+    pub fn export_size_report(&self) -> ExportSizeReport {
+        let saved = self.save_game();
+        let json = serde_json::to_vec(&saved).expect("saves always serialize");
+        let compressed = compact::pack(&saved);
+        ExportSizeReport {
+            json_bytes: json.len(),
+            json_code_length: export::encode(SaveFormat::Json, &json, &self.export_key).len(),
+            packed_bytes: compact::pack_uncompressed(&saved).len(),
+            compressed_bytes: compressed.len(),
+            compact_code_length: export::encode(SaveFormat::Compact, &compressed, &self.export_key)
+                .len(),
+        }
+    }
+
+    /// Load an export code in either format, reporting whether it was
+    /// verified; strict mode also rejects codes that are unsigned or were
+    /// modified after export
+    pub fn import_code(&mut self, code: &str, mode: LoadMode) -> Result<ImportReport, SaveError> {
+        let (format, payload, integrity) = export::decode(code, &self.export_key)?;
+        if mode == LoadMode::Strict {
+            match integrity {
+                Integrity::Verified => {}
+                Integrity::Modified => return Err(SaveError::Modified),
+                Integrity::Unsigned => return Err(SaveError::Unsigned),
+            }
+        }
+        let load = match format {
+            SaveFormat::Json => {
+                let json = String::from_utf8(payload)
+                    .map_err(|e| SaveError::Corrupted(format!("UTF8 conversion error: {}", e)))?;
+                self.load_json_with_mode(&json, mode)
+                    .map_err(|error| match error {
+                        // Unparseable and not vouched for by the checksum: damaged, not edited
+                        SaveError::Parse(message) if integrity != Integrity::Verified => {
+                            SaveError::Corrupted(message)
+                        }
+                        other => other,
+                    })?
+            }
+            // Always the current schema, so there is nothing to migrate
+            SaveFormat::Compact => {
+                let saved = compact::unpack(&payload, &self.content)?;
+                let report = MigrationReport {
+                    from_version: SAVE_VERSION,
+                    to_version: SAVE_VERSION,
+                    ..MigrationReport::default()
+                };
+                self.load_checked(saved, report, mode)?
+            }
+        };
+        Ok(ImportReport { integrity, load })
+    }
+
+    /// What the most recent `load_json` migrated, if anything was loaded
+    pub fn last_migration(&self) -> Option<&MigrationReport> {
+        self.last_migration.as_ref()
+    }
+
+    /// Write the game as the current save in `storage`, with a backup every
+    /// few minutes
+    pub fn save_to(&self, storage: &mut dyn SaveStorage) -> Result<(), SaveError> {
+        slots::write_current(storage, &self.save_game())
+    }
+
+    /// Load the current save from `storage`; `None` if there is none
+    pub fn load_from(
+        &mut self,
+        storage: &dyn SaveStorage,
+    ) -> Result<Option<MigrationReport>, SaveError> {
+        match slots::read_current(storage)? {
+            Some(json) => self.load_json(&json).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Keep the game as a backup right away, e.g. before it is overwritten
+    pub fn backup_to(&self, storage: &mut dyn SaveStorage) -> Result<SlotMetadata, SaveError> {
+        slots::write_backup(storage, &self.save_game())
+    }
+
+    /// Save the game to the named slot in `storage`
+    pub fn save_to_slot(
+        &self,
+        storage: &mut dyn SaveStorage,
+        name: &str,
+    ) -> Result<SlotMetadata, SaveError> {
+        slots::write_slot(storage, name, &self.save_game())
+    }
+
+    /// Load a slot or backup; the current save is left alone until the next save
+    pub fn load_slot(
+        &mut self,
+        storage: &dyn SaveStorage,
+        name: &str,
+    ) -> Result<MigrationReport, SaveError> {
+        let json = slots::read_slot(storage, name)?;
+        self.load_json(&json)
+    }
+
+    /// Load a slot or backup and make it the current save, keeping the game
+    /// it replaces as a backup
+    pub fn restore_slot(
+        &mut self,
+        storage: &mut dyn SaveStorage,
+        name: &str,
+    ) -> Result<MigrationReport, SaveError> {
+        // Read first, as the backup may rotate out the slot being restored
+        let json = slots::read_slot(storage, name)?;
+        self.backup_to(storage)?;
+        let report = self.load_json(&json)?;
+        self.save_to(storage)?;
+        Ok(report)
+    }
+}
+
+/// Put `saved` in content order, adding a fresh entry for every content id
+/// the save lacks; saved entries no longer in content are kept at the end
+fn merge_new_entries<T>(
+    saved: &mut Vec<T>,
+    fresh: Vec<T>,
+    kind: &str,
+    id: impl Fn(&T) -> &String,
+    added: &mut Vec<String>,
+) {
+    let mut remaining: Vec<Option<T>> = saved.drain(..).map(Some).collect();
+    for entry in fresh {
+        let position = remaining
+            .iter()
+            .position(|s| s.as_ref().is_some_and(|s| id(s) == id(&entry)));
+        match position.and_then(|i| remaining[i].take()) {
+            Some(existing) => saved.push(existing),
+            None => {
+                added.push(format!("{}:{}", kind, id(&entry)));
+                saved.push(entry);
+            }
+        }
+    }
+    saved.extend(remaining.into_iter().flatten());
+}
@@ -1,95 +1,79 @@
-use crate::entities::{Building, Upgrade, Worker};
-use crate::state::{GameState, Statistics};
-use crate::systems::{Achievement, CraftingRecipe, UnlockedFeature};
-use base64::{engine::general_purpose, Engine as _};
-use js_sys::Date;
-use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use crate::content::GameContent;
+use crate::core::clock::{BrowserClock, Clock};
+use crate::core::game_core::GameCore;
+use crate::persistence::slots;
+use crate::persistence::storage::{JsObjectStorage, LocalStorage};
+use crate::persistence::{LoadMode, SaveError, SaveFormat, SaveStorage, SavedGame};
+use crate::state::Notation;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
+/// WebAssembly facade over `GameCore`: forwards actions to the simulation;
+/// the page learns about the results by draining events
 #[wasm_bindgen]
 pub struct IdleGame {
-    state: Rc<RefCell<GameState>>,
-    upgrades: Vec<Upgrade>,
-    buildings: Vec<Building>,
-    workers: Vec<Worker>,
-    #[wasm_bindgen(skip)]
-    achievements: Vec<Achievement>,
-    #[wasm_bindgen(skip)]
-    crafting_recipes: Vec<CraftingRecipe>,
-    #[wasm_bindgen(skip)]
-    unlocked_features: Vec<UnlockedFeature>,
-    statistics: Rc<RefCell<Statistics>>,
+    core: GameCore,
+    /// Where saves go; localStorage is opened on first use unless set
+    storage: Option<Box<dyn SaveStorage>>,
 }
 
-/// Complete game save data structure for persistence
-#[derive(Serialize, Deserialize, Clone)]
-pub struct SavedGame {
-    pub state: GameState,
-    pub statistics: Statistics,
-    pub upgrades: Vec<Upgrade>,
-    pub buildings: Vec<Building>,
-    pub workers: Vec<Worker>,
-    pub achievements: Vec<Achievement>,
-    pub crafting_recipes: Vec<CraftingRecipe>,
-    pub unlocked_features: Vec<UnlockedFeature>,
-    pub save_timestamp: f64,
-    pub version: String,
+/// Browser window used for UI callbacks; there is none when the game runs
+/// natively (e.g. under `cargo test`), so callbacks become no-ops
+fn ui_window() -> Option<web_sys::Window> {
+    if cfg!(target_arch = "wasm32") {
+        web_sys::window()
+    } else {
+        None
+    }
 }
 
 impl IdleGame {
+    /// Create a new game whose timestamps and elapsed time come from `clock`
+    pub fn with_clock(clock: Rc<dyn Clock>) -> IdleGame {
+        IdleGame {
+            core: GameCore::new(clock),
+            storage: None,
+        }
+    }
+
+    pub fn core(&self) -> &GameCore {
+        &self.core
+    }
+
+    pub fn core_mut(&mut self) -> &mut GameCore {
+        &mut self.core
+    }
+
     /// Serialize entire game state to SavedGame structure
     pub fn save_game(&self) -> SavedGame {
-        SavedGame {
-            state: self.state.borrow().clone(),
-            statistics: self.statistics.borrow().clone(),
-            upgrades: self.upgrades.clone(),
-            buildings: self.buildings.clone(),
-            workers: self.workers.clone(),
-            achievements: self.achievements.clone(),
-            crafting_recipes: self.crafting_recipes.clone(),
-            unlocked_features: self.unlocked_features.clone(),
-            save_timestamp: Date::now(),
-            version: "0.2.6".to_string(),
-        }
+        self.core.save_game()
     }
 
     /// Load game state from SavedGame structure
     pub fn load_game(&mut self, saved: SavedGame) {
-        {
-            let mut state = self.state.borrow_mut();
-            state.coins = saved.state.coins;
-            state.wood = saved.state.wood;
-            state.stone = saved.state.stone;
-            state.coins_per_click = saved.state.coins_per_click;
-            state.coins_per_second = saved.state.coins_per_second;
-            state.wood_per_second = saved.state.wood_per_second;
-            state.stone_per_second = saved.state.stone_per_second;
-            state.autoclick_count = saved.state.autoclick_count;
-            state.total_clicks = saved.state.total_clicks;
-            state.last_update_time = saved.state.last_update_time;
-        }
+        self.core.load_game(saved);
+    }
 
-        {
-            let mut stats = self.statistics.borrow_mut();
-            stats.total_clicks = saved.statistics.total_clicks;
-            stats.total_coins_earned = saved.statistics.total_coins_earned;
-            stats.total_wood_earned = saved.statistics.total_wood_earned;
-            stats.total_stone_earned = saved.statistics.total_stone_earned;
-            stats.total_resources_crafted = saved.statistics.total_resources_crafted;
-            stats.achievements_unlocked_count = saved.statistics.achievements_unlocked_count;
-            stats.play_time_seconds = saved.statistics.play_time_seconds;
-            stats.buildings_purchased = saved.statistics.buildings_purchased;
-            stats.upgrades_purchased = saved.statistics.upgrades_purchased;
+    /// Save to `storage` instead of localStorage
+    pub fn set_storage(&mut self, storage: Box<dyn SaveStorage>) {
+        self.storage = Some(storage);
+    }
+
+    /// Best-effort backup before `action` replaces the game; a failed backup
+    /// is logged rather than blocking the player
+    fn backup_before(&mut self, action: &str) {
+        if ui_window().is_none() {
+            return;
+        }
+        if let Err(e) = self.backup_to_local_storage() {
+            web_sys::console::warn_2(&format!("Backup before {} failed:", action).into(), &e);
         }
+    }
+}
 
-        self.upgrades = saved.upgrades;
-        self.buildings = saved.buildings;
-        self.workers = saved.workers;
-        self.achievements = saved.achievements;
-        self.crafting_recipes = saved.crafting_recipes;
-        self.unlocked_features = saved.unlocked_features;
+impl Default for IdleGame {
+    fn default() -> Self {
+        IdleGame::new()
     }
 }
 
@@ -97,657 +81,162 @@ impl IdleGame {
 impl IdleGame {
     #[wasm_bindgen(constructor)]
     pub fn new() -> IdleGame {
-        let now = Date::now();
-
-        let achievements = vec![
-            // Click achievements
-            Achievement {
-                id: "click_novice_10".to_string(),
-                name: "点击新手".to_string(),
-                description: "点击 10 次".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 10.0,
-                category: "clicks".to_string(),
-            },
-            Achievement {
-                id: "click_master_100".to_string(),
-                name: "点击大师".to_string(),
-                description: "点击 100 次".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 100.0,
-                category: "clicks".to_string(),
-            },
-            Achievement {
-                id: "click_legend_1000".to_string(),
-                name: "点击传奇".to_string(),
-                description: "点击 1000 次".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 1000.0,
-                category: "clicks".to_string(),
-            },
-            // Resource achievements
-            Achievement {
-                id: "first_coins_100".to_string(),
-                name: "第一桶金".to_string(),
-                description: "获得 100 金币".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 100.0,
-                category: "resources".to_string(),
-            },
-            Achievement {
-                id: "wood_collector_1000".to_string(),
-                name: "木材收集者".to_string(),
-                description: "获得 1000 木头".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 1000.0,
-                category: "resources".to_string(),
-            },
-            Achievement {
-                id: "stone_hoarder_5000".to_string(),
-                name: "石头囤积者".to_string(),
-                description: "获得 5000 石头".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 5000.0,
-                category: "resources".to_string(),
-            },
-            // Building achievements
-            Achievement {
-                id: "first_building".to_string(),
-                name: "第一座建筑".to_string(),
-                description: "购买第一座建筑".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 1.0,
-                category: "buildings".to_string(),
-            },
-            Achievement {
-                id: "building_enthusiast_10".to_string(),
-                name: "建筑爱好者".to_string(),
-                description: "购买 10 座建筑".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 10.0,
-                category: "buildings".to_string(),
-            },
-            Achievement {
-                id: "building_tycoon_50".to_string(),
-                name: "建筑大亨".to_string(),
-                description: "购买 50 座建筑".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 50.0,
-                category: "buildings".to_string(),
-            },
-            // Crafting achievements
-            Achievement {
-                id: "first_craft".to_string(),
-                name: "第一次制作".to_string(),
-                description: "制作第一个物品".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 1.0,
-                category: "crafting".to_string(),
-            },
-            Achievement {
-                id: "craft_master_100".to_string(),
-                name: "制作大师".to_string(),
-                description: "制作 100 个物品".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 100.0,
-                category: "crafting".to_string(),
-            },
-            // Unlock achievements
-            Achievement {
-                id: "first_unlock".to_string(),
-                name: "首次解锁".to_string(),
-                description: "解锁第一个成就".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 1.0,
-                category: "unlocks".to_string(),
-            },
-            Achievement {
-                id: "progress_master_5".to_string(),
-                name: "进度大师".to_string(),
-                description: "解锁 5 个成就".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                progress: 0.0,
-                requirement: 5.0,
-                category: "unlocks".to_string(),
-            },
-        ];
+        IdleGame::with_clock(Rc::new(BrowserClock))
+    }
 
-        IdleGame {
-            state: Rc::new(RefCell::new(GameState {
-                coins: 0.0,
-                wood: 0.0,
-                stone: 0.0,
-                coins_per_click: 1.0,
-                coins_per_second: 0.0,
-                wood_per_second: 0.0,
-                stone_per_second: 0.0,
-                autoclick_count: 0,
-                total_clicks: 0,
-                last_update_time: now,
-            })),
-            statistics: Rc::new(RefCell::new(Statistics {
-                total_clicks: 0,
-                total_coins_earned: 0.0,
-                total_wood_earned: 0.0,
-                total_stone_earned: 0.0,
-                total_resources_crafted: 0,
-                achievements_unlocked_count: 0,
-                play_time_seconds: 0.0,
-                buildings_purchased: 0,
-                upgrades_purchased: 0,
-            })),
-            achievements: achievements,
-            crafting_recipes: vec![
-                CraftingRecipe {
-                    id: "coins_to_wood".to_string(),
-                    name: "金币换木材".to_string(),
-                    input_resource: "coins".to_string(),
-                    input_amount: 100.0,
-                    output_resource: "wood".to_string(),
-                    output_amount: 10.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "wood_to_coins".to_string(),
-                    name: "木材换金币".to_string(),
-                    input_resource: "wood".to_string(),
-                    input_amount: 10.0,
-                    output_resource: "coins".to_string(),
-                    output_amount: 100.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "coins_to_stone".to_string(),
-                    name: "金币换石头".to_string(),
-                    input_resource: "coins".to_string(),
-                    input_amount: 100.0,
-                    output_resource: "stone".to_string(),
-                    output_amount: 1.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "stone_to_coins".to_string(),
-                    name: "石头换金币".to_string(),
-                    input_resource: "stone".to_string(),
-                    input_amount: 1.0,
-                    output_resource: "coins".to_string(),
-                    output_amount: 100.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "wood_to_stone".to_string(),
-                    name: "木材换石头".to_string(),
-                    input_resource: "wood".to_string(),
-                    input_amount: 10.0,
-                    output_resource: "stone".to_string(),
-                    output_amount: 1.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "stone_to_wood".to_string(),
-                    name: "石头换木材".to_string(),
-                    input_resource: "stone".to_string(),
-                    input_amount: 1.0,
-                    output_resource: "wood".to_string(),
-                    output_amount: 10.0,
-                    unlocked: true,
-                },
-            ],
-            upgrades: vec![
-                Upgrade {
-                    name: "Better Click".to_string(),
-                    cost: 10.0,
-                    production_increase: 1.0,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Autoclicker Lv1".to_string(),
-                    cost: 50.0,
-                    production_increase: 1.0,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Lumberjack Efficiency".to_string(),
-                    cost: 20.0,
-                    production_increase: 0.2,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Stone Mason Skill".to_string(),
-                    cost: 25.0,
-                    production_increase: 0.3,
-                    owned: 0,
-                    unlocked: true,
-                },
-            ],
-            buildings: vec![
-                Building {
-                    name: "Coin Mine".to_string(),
-                    cost: 15.0,
-                    production_rate: 0.1,
-                    count: 0,
-                },
-                Building {
-                    name: "Coin Factory".to_string(),
-                    cost: 100.0,
-                    production_rate: 1.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Coin Corporation".to_string(),
-                    cost: 500.0,
-                    production_rate: 5.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Woodcutter".to_string(),
-                    cost: 20.0,
-                    production_rate: 0.2,
-                    count: 0,
-                },
-                Building {
-                    name: "Lumber Mill".to_string(),
-                    cost: 80.0,
-                    production_rate: 1.5,
-                    count: 0,
-                },
-                Building {
-                    name: "Forest Workshop".to_string(),
-                    cost: 400.0,
-                    production_rate: 4.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Stone Quarry".to_string(),
-                    cost: 25.0,
-                    production_rate: 0.15,
-                    count: 0,
-                },
-                Building {
-                    name: "Rock Crusher".to_string(),
-                    cost: 90.0,
-                    production_rate: 1.2,
-                    count: 0,
-                },
-                Building {
-                    name: "Mason Workshop".to_string(),
-                    cost: 450.0,
-                    production_rate: 4.5,
-                    count: 0,
-                },
-            ],
-            workers: vec![
-                Worker {
-                    name: "矿工".to_string(),
-                    skills: "mining".to_string(),
-                    background: "擅长挖矿的工人".to_string(),
-                    preferences: "Coin Mine".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "伐木工".to_string(),
-                    skills: "logging".to_string(),
-                    background: "擅长伐木的工人".to_string(),
-                    preferences: "Woodcutter".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "石匠".to_string(),
-                    skills: "masonry".to_string(),
-                    background: "擅长采石的工人".to_string(),
-                    preferences: "Stone Quarry".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "工厂工人".to_string(),
-                    skills: "factory".to_string(),
-                    background: "擅长工厂生产的工人".to_string(),
-                    preferences: "Coin Factory".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "高级工匠".to_string(),
-                    skills: "crafting".to_string(),
-                    background: "擅长高级制作的工匠".to_string(),
-                    preferences: "Mason Workshop".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-            ],
-            unlocked_features: vec![
-                UnlockedFeature {
-                    id: "workers_tab".to_string(),
-                    name: "工人面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 50.0,
-                },
-                UnlockedFeature {
-                    id: "advanced_buildings".to_string(),
-                    name: "高级建筑".to_string(),
-                    feature_type: "building".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "buildings_owned".to_string(),
-                    requirement_value: 20.0,
-                },
-                UnlockedFeature {
-                    id: "prestige_system".to_string(),
-                    name: "转生系统".to_string(),
-                    feature_type: "mechanic".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_coins".to_string(),
-                    requirement_value: 10000.0,
-                },
-                UnlockedFeature {
-                    id: "statistics_panel".to_string(),
-                    name: "统计面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 10.0,
-                },
-                UnlockedFeature {
-                    id: "achievements_panel".to_string(),
-                    name: "成就面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 25.0,
-                },
-            ],
-        }
+    /// Create a new game with `content_json` merged over the embedded content
+    #[wasm_bindgen]
+    pub fn with_content(content_json: &str) -> Result<IdleGame, JsValue> {
+        let content = GameContent::embedded_with_override(content_json)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(IdleGame {
+            core: GameCore::with_content(Rc::new(BrowserClock), content),
+            storage: None,
+        })
     }
 
     #[wasm_bindgen]
     pub fn click_action(&mut self) {
-        // First, update statistics (release borrow immediately)
-        {
-            let mut stats = self.statistics.borrow_mut();
-            stats.total_clicks += 1;
-            stats.total_coins_earned += self.state.borrow().coins_per_click;
-        } // stats borrow released here
-
-        // Then update game state
-        {
-            let mut state = self.state.borrow_mut();
-            let earned = state.coins_per_click;
-            state.coins += earned;
-            state.total_clicks += 1;
-        } // state borrow released here
-
-        // Now check achievements (no conflicting borrows)
-        self.check_achievement("click_novice_10");
-        self.check_achievement("click_master_100");
-        self.check_achievement("click_legend_1000");
-
-        self.update_resources_only();
+        self.core.click_action();
     }
 
     #[wasm_bindgen]
     pub fn buy_upgrade(&mut self, index: usize) -> bool {
-        if index >= self.upgrades.len() {
-            return false;
-        }
-
-        let upgrade_cost = self.upgrades[index].cost;
-        let state = self.state.borrow();
-
-        if state.coins + 1e-10 >= upgrade_cost {
-            drop(state);
-            let mut state = self.state.borrow_mut();
-            state.coins -= upgrade_cost;
-
-            if self.upgrades[index].name == "Better Click" {
-                state.coins_per_click += self.upgrades[index].production_increase;
-            } else if self.upgrades[index].name.starts_with("Autoclicker") {
-                state.autoclick_count += 1;
-            }
-
-            self.upgrades[index].owned += 1;
-            self.upgrades[index].cost = self.upgrades[index].cost * 1.5;
-            drop(state);
-
-            let mut stats = self.statistics.borrow_mut();
-            stats.upgrades_purchased += 1;
-            drop(stats);
-
-            self.update_production();
-            self.update_resources_only();
-            self.update_upgrades_only();
-            true
-        } else {
-            drop(state);
-            self.update_resources_only();
-            false
-        }
+        self.core.buy_upgrade(index)
     }
 
     #[wasm_bindgen]
     pub fn buy_building(&mut self, index: usize) -> bool {
-        if index >= self.buildings.len() {
-            return false;
-        }
-
-        let building_cost = self.buildings[index].cost;
-        let state = self.state.borrow();
-
-        if state.coins + 1e-10 >= building_cost {
-            drop(state);
-            let mut state = self.state.borrow_mut();
-            state.coins -= building_cost;
-            self.buildings[index].count += 1;
-            self.buildings[index].cost *= 1.15;
-            drop(state);
-
-            let mut stats = self.statistics.borrow_mut();
-            stats.buildings_purchased += 1;
-            drop(stats);
-
-            self.check_achievement("first_building");
-            self.check_achievement("building_enthusiast_10");
-            self.check_achievement("building_tycoon_50");
-
-            self.update_production();
-            self.update_resources_only();
-            self.update_buildings_only();
-            true
-        } else {
-            drop(state);
-            self.update_resources_only();
-            false
-        }
+        self.core.buy_building(index)
     }
 
     #[wasm_bindgen]
     pub fn craft_resource(&mut self, recipe_id: &str) -> bool {
-        let recipe = match self.crafting_recipes.iter().find(|r| r.id == recipe_id) {
-            Some(r) => r.clone(),
-            None => return false,
-        };
-
-        let mut state = self.state.borrow_mut();
-        let input_amount = match recipe.input_resource.as_str() {
-            "coins" => state.coins,
-            "wood" => state.wood,
-            "stone" => state.stone,
-            _ => return false,
-        };
-
-        if input_amount + 1e-10 >= recipe.input_amount {
-            match recipe.input_resource.as_str() {
-                "coins" => state.coins -= recipe.input_amount,
-                "wood" => state.wood -= recipe.input_amount,
-                "stone" => state.stone -= recipe.input_amount,
-                _ => return false,
-            }
+        self.core.craft_resource(recipe_id)
+    }
 
-            match recipe.output_resource.as_str() {
-                "coins" => state.coins += recipe.output_amount,
-                "wood" => state.wood += recipe.output_amount,
-                "stone" => state.stone += recipe.output_amount,
-                _ => return false,
-            }
+    /// Craft up to `times` times in one batch; returns how many happened
+    #[wasm_bindgen]
+    pub fn craft_resource_times(&mut self, recipe_id: &str, times: u32) -> u32 {
+        self.core.craft_resource_times(recipe_id, times)
+    }
 
-            drop(state);
+    /// Craft as many times as affordable; returns how many happened
+    #[wasm_bindgen]
+    pub fn craft_max(&mut self, recipe_id: &str) -> u32 {
+        self.core.craft_max(recipe_id)
+    }
 
-            let mut stats = self.statistics.borrow_mut();
-            stats.total_resources_crafted += 1;
-            drop(stats);
+    /// All `GameEvent`s raised since the previous call, as an array of
+    /// objects tagged by `type`
+    #[wasm_bindgen]
+    pub fn drain_events(&mut self) -> JsValue {
+        let events = self.core.drain_events();
+        serde_wasm_bindgen::to_value(&events).unwrap_or(JsValue::NULL)
+    }
 
-            self.check_achievement("first_craft");
-            self.check_achievement("craft_master_100");
+    #[wasm_bindgen]
+    pub fn get_buildings(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.priced_buildings()).unwrap_or(JsValue::NULL)
+    }
 
-            self.update_resources_only();
-            true
-        } else {
-            drop(state);
-            self.update_resources_only();
-            false
-        }
+    #[wasm_bindgen]
+    pub fn get_upgrades(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.upgrades).unwrap_or(JsValue::NULL)
     }
 
     #[wasm_bindgen]
     pub fn get_coins(&self) -> f64 {
-        self.state.borrow().coins
+        self.core.get_coins()
     }
 
     #[wasm_bindgen]
     pub fn get_wood(&self) -> f64 {
-        self.state.borrow().wood
+        self.core.get_wood()
     }
 
     #[wasm_bindgen]
     pub fn get_stone(&self) -> f64 {
-        self.state.borrow().stone
+        self.core.get_stone()
     }
 
     #[wasm_bindgen]
     pub fn get_coins_per_second(&self) -> f64 {
-        self.state.borrow().coins_per_second
+        self.core.get_coins_per_second()
     }
 
     #[wasm_bindgen]
     pub fn get_wood_per_second(&self) -> f64 {
-        self.state.borrow().wood_per_second
+        self.core.get_wood_per_second()
     }
 
     #[wasm_bindgen]
     pub fn get_stone_per_second(&self) -> f64 {
-        self.state.borrow().stone_per_second
+        self.core.get_stone_per_second()
     }
 
     #[wasm_bindgen]
     pub fn get_coins_per_click(&self) -> f64 {
-        self.state.borrow().coins_per_click
+        self.core.get_coins_per_click()
     }
 
     #[wasm_bindgen]
-    pub fn get_statistics(&self) -> JsValue {
-        serde_wasm_bindgen::to_value(&self.statistics.borrow().clone()).unwrap_or(JsValue::NULL)
+    pub fn get_resource(&self, id: &str) -> f64 {
+        self.core.get_resource(id)
     }
 
     #[wasm_bindgen]
-    pub fn assign_worker(&mut self, worker_index: usize, building_id: &str) -> bool {
-        if worker_index >= self.workers.len() {
-            return false;
-        }
-
-        let building_exists = self.buildings.iter().any(|b| b.name == building_id);
-        if !building_exists {
-            return false;
-        }
-
-        self.workers[worker_index].assigned_building = Some(building_id.to_string());
-
-        let preference = &self.workers[worker_index].preferences;
+    pub fn get_resource_rate(&self, id: &str) -> f64 {
+        self.core.get_resource_rate(id)
+    }
 
-        let mut efficiency = 1.0;
+    /// Amount of a resource rounded down for display, in "short",
+    /// "scientific" or "engineering" notation
+    #[wasm_bindgen]
+    pub fn get_resource_formatted(&self, id: &str, notation: &str) -> String {
+        self.core
+            .resource_amount(id)
+            .floor()
+            .format(Notation::parse(notation))
+    }
 
-        if preference == building_id {
-            efficiency += 0.2;
-        }
+    /// Every resource with its amount, rate, lifetime total and display metadata
+    #[wasm_bindgen]
+    pub fn get_resources(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.state.resources).unwrap_or(JsValue::NULL)
+    }
 
-        efficiency += (self.workers[worker_index].level as f64) * 0.05;
+    #[wasm_bindgen]
+    pub fn get_statistics(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.statistics).unwrap_or(JsValue::NULL)
+    }
 
-        self.workers[worker_index].efficiency_multiplier = efficiency;
+    /// Migrations applied by the last load from storage or import, or null
+    #[wasm_bindgen]
+    pub fn get_migration_report(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.last_migration()).unwrap_or(JsValue::NULL)
+    }
 
-        self.update_production();
+    #[wasm_bindgen]
+    pub fn assign_worker(&mut self, worker_index: usize, building_id: &str) -> bool {
+        self.core.assign_worker(worker_index, building_id)
+    }
 
-        true
+    #[wasm_bindgen]
+    pub fn unassign_worker(&mut self, worker_index: usize) -> bool {
+        self.core.unassign_worker(worker_index)
     }
 
     #[wasm_bindgen]
     pub fn get_worker_production_bonus(&self, worker_index: usize) -> f64 {
-        if worker_index >= self.workers.len() {
-            return 0.0;
-        }
-
-        let worker = &self.workers[worker_index];
-        if worker.assigned_building.is_none() {
-            return 0.0;
-        }
-
-        worker.efficiency_multiplier - 1.0
+        self.core.get_worker_production_bonus(worker_index)
     }
 
     #[wasm_bindgen]
     pub fn get_workers(&self) -> js_sys::Array {
         let workers_array = js_sys::Array::new();
 
-        for worker in self.workers.iter() {
+        for worker in self.core.workers.iter() {
             let worker_obj = js_sys::Object::new();
 
             js_sys::Reflect::set(
@@ -788,6 +277,16 @@ impl IdleGame {
             )
             .unwrap();
 
+            js_sys::Reflect::set(
+                &worker_obj,
+                &JsValue::from_str("requires"),
+                &match &worker.requires {
+                    Some(feature) => JsValue::from_str(feature),
+                    None => JsValue::NULL,
+                },
+            )
+            .unwrap();
+
             js_sys::Reflect::set(
                 &worker_obj,
                 &JsValue::from_str("level"),
@@ -824,214 +323,52 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn assign_worker_to_building(&mut self, worker_index: usize, building_id: &str) -> bool {
-        if worker_index >= self.workers.len() {
-            return false;
-        }
-
-        let building_exists = self.buildings.iter().any(|b| b.name == building_id);
-        if !building_exists {
-            return false;
-        }
-
-        self.workers[worker_index].assigned_building = Some(building_id.to_string());
-
-        let _skill = &self.workers[worker_index].skills;
-        let preference = &self.workers[worker_index].preferences;
-
-        let mut efficiency = 1.0;
-
-        if preference == building_id {
-            efficiency += 0.2;
-        }
-
-        efficiency += (self.workers[worker_index].level as f64) * 0.05;
-
-        self.workers[worker_index].efficiency_multiplier = efficiency;
-
-        self.update_production();
-
-        true
+        self.core.assign_worker(worker_index, building_id)
     }
 
-    fn get_worker_bonus_for_building(&self, building_name: &str) -> f64 {
-        let mut total_bonus = 1.0;
-
-        for worker in &self.workers {
-            if let Some(ref assigned) = worker.assigned_building {
-                if assigned == building_name {
-                    total_bonus += worker.efficiency_multiplier - 1.0;
-                }
-            }
-        }
-
-        total_bonus
+    #[wasm_bindgen]
+    pub fn game_loop(&mut self) {
+        self.core.game_loop();
     }
 
-    fn update_production(&mut self) {
-        let mut total_cps = 0.0;
-        let mut total_wps = 0.0;
-        let mut total_sps = 0.0;
-
-        for building in &self.buildings {
-            let base_production = building.production_rate * building.count as f64;
-            let worker_bonus = self.get_worker_bonus_for_building(&building.name);
-            let boosted_production = base_production * worker_bonus;
-
-            match building.name.as_str() {
-                "Coin Mine" | "Coin Factory" | "Coin Corporation" => {
-                    total_cps += boosted_production;
-                }
-                "Woodcutter" | "Lumber Mill" | "Forest Workshop" => {
-                    total_wps += boosted_production;
-                }
-                "Stone Quarry" | "Rock Crusher" | "Mason Workshop" => {
-                    total_sps += boosted_production;
-                }
-                _ => {}
-            }
-        }
-
-        for upgrade in &self.upgrades {
-            if upgrade.name == "Lumberjack Efficiency" {
-                total_wps += upgrade.production_increase * upgrade.owned as f64;
-            }
-            if upgrade.name == "Stone Mason Skill" {
-                total_sps += upgrade.production_increase * upgrade.owned as f64;
-            }
-        }
-
-        total_cps = if total_cps.is_finite() && total_cps >= 0.0 {
-            total_cps
-        } else {
-            0.0
-        };
-        total_wps = if total_wps.is_finite() && total_wps >= 0.0 {
-            total_wps
-        } else {
-            0.0
-        };
-        total_sps = if total_sps.is_finite() && total_sps >= 0.0 {
-            total_sps
-        } else {
-            0.0
-        };
-
-        let mut state = self.state.borrow_mut();
-        state.coins_per_second = total_cps;
-        state.wood_per_second = total_wps;
-        state.stone_per_second = total_sps;
-    }
-
-    fn grant_worker_xp(&mut self, elapsed: f64) {
-        for i in 0..self.workers.len() {
-            if self.workers[i].assigned_building.is_some() {
-                let xp_gain = 10.0 * elapsed;
-                self.workers[i].xp += xp_gain;
-
-                while self.workers[i].xp >= self.workers[i].xp_to_next_level {
-                    self.workers[i].xp -= self.workers[i].xp_to_next_level;
-                    self.workers[i].level += 1;
-                    self.workers[i].xp_to_next_level =
-                        (self.workers[i].xp_to_next_level * 1.5).ceil();
-
-                    let preference = &self.workers[i].preferences;
-                    let assigned = self.workers[i].assigned_building.as_ref().unwrap();
-                    let mut efficiency = 1.0;
-
-                    if preference == assigned {
-                        efficiency += 0.2;
-                    }
-                    efficiency += (self.workers[i].level as f64) * 0.05;
-                    self.workers[i].efficiency_multiplier = efficiency;
-                }
-            }
+    /// Report of the last offline catch-up (null if none since the last call)
+    #[wasm_bindgen]
+    pub fn take_offline_report(&mut self) -> JsValue {
+        match self.core.take_offline_report() {
+            Some(report) => serde_wasm_bindgen::to_value(&report).unwrap_or(JsValue::NULL),
+            None => JsValue::NULL,
         }
-
-        // Don't call update_production here to avoid borrow conflicts
-        // Caller should call it after releasing other borrows
     }
 
     #[wasm_bindgen]
-    pub fn game_loop(&mut self) {
-        let now = Date::now();
-
-        let (new_coins, new_wood, new_stone, new_last_update_time, elapsed) = {
-            let state = self.state.borrow();
-            let elapsed = (now - state.last_update_time) / 1000.0;
-
-            if elapsed > 0.0 && elapsed < 3600.0 {
-                let mut new_coins = state.coins + state.coins_per_second * elapsed;
-                let new_wood = state.wood + state.wood_per_second * elapsed;
-                let new_stone = state.stone + state.stone_per_second * elapsed;
-
-                new_coins = new_coins.max(0.0);
-
-                let autoclicks_per_second = 10.0;
-                let clamped_autoclick_effect = state.coins_per_click
-                    * state.autoclick_count as f64
-                    * autoclicks_per_second
-                    * elapsed;
-                if state.autoclick_count > 0 && clamped_autoclick_effect.is_finite() {
-                    new_coins += clamped_autoclick_effect;
-                }
-
-                (new_coins, new_wood, new_stone, now, elapsed)
-            } else {
-                (
-                    state.coins,
-                    state.wood,
-                    state.stone,
-                    state.last_update_time,
-                    0.0,
-                )
-            }
-        };
+    pub fn get_offline_config(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.offline_config).unwrap_or(JsValue::NULL)
+    }
 
-        {
-            let mut state = self.state.borrow_mut();
-            state.coins = if new_coins.is_finite() {
-                new_coins
-            } else {
-                state.coins
-            };
-            state.wood = if new_wood.is_finite() {
-                new_wood
-            } else {
-                state.wood
-            };
-            state.stone = if new_stone.is_finite() {
-                new_stone
-            } else {
-                state.stone
-            };
-            state.last_update_time = new_last_update_time;
+    /// Set the longest credited absence (seconds), the offline production rate
+    /// (0..1) and, optionally, the gap (seconds) after which that rate applies
+    #[wasm_bindgen]
+    pub fn set_offline_config(
+        &mut self,
+        max_seconds: f64,
+        efficiency: f64,
+        threshold_seconds: Option<f64>,
+    ) {
+        let config = &mut self.core.offline_config;
+        if max_seconds.is_finite() {
+            config.max_seconds = max_seconds.max(0.0);
         }
-
-        // Update statistics (release borrow before calling other methods)
-        if elapsed > 0.0 {
-            {
-                let mut stats = self.statistics.borrow_mut();
-                stats.play_time_seconds += elapsed;
-            }
-
-            // Grant XP to assigned workers based on production
-            self.grant_worker_xp(elapsed);
-
-            // Update production after worker XP changes (must be after grant_worker_xp)
-            self.update_production();
+        if efficiency.is_finite() {
+            config.efficiency = efficiency.clamp(0.0, 1.0);
+        }
+        if let Some(threshold) = threshold_seconds.filter(|t| t.is_finite()) {
+            config.threshold_seconds = threshold.max(0.0);
         }
-
-        self.check_achievement("first_coins_100");
-        self.check_achievement("wood_collector_1000");
-        self.check_achievement("stone_hoarder_5000");
-        self.check_achievement("craft_master_100");
-
-        self.update_resources_only();
     }
 
     #[wasm_bindgen]
     pub fn update_resources_only(&self) {
-        let window = match web_sys::window() {
+        let window = match ui_window() {
             Some(win) => win,
             None => return,
         };
@@ -1064,23 +401,20 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn update_upgrades_only(&self) {
-        let window = match web_sys::window() {
+        let window = match ui_window() {
             Some(win) => win,
-            None => {
-                web_sys::console::log_1(&"update_upgrades_only: window is None".into());
-                return;
-            }
+            None => return,
         };
         let global_obj = window.as_ref();
 
         web_sys::console::log_1(
             &format!(
                 "update_upgrades_only: upgrades count={}",
-                self.upgrades.len()
+                self.core.upgrades.len()
             )
             .into(),
         );
-        for (i, upgrade) in self.upgrades.iter().enumerate() {
+        for (i, upgrade) in self.core.upgrades.iter().enumerate() {
             web_sys::console::log_1(
                 &format!(
                     "  upgrade[{}]: name={}, cost={}",
@@ -1090,7 +424,7 @@ impl IdleGame {
             );
         }
 
-        let upgrades_serialized = match serde_wasm_bindgen::to_value(&self.upgrades) {
+        let upgrades_serialized = match serde_wasm_bindgen::to_value(&self.core.upgrades) {
             Ok(val) => {
                 web_sys::console::log_1(&"update_upgrades_only: serialization OK".into());
                 val
@@ -1129,13 +463,14 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn update_buildings_only(&self) {
-        let window = match web_sys::window() {
+        let window = match ui_window() {
             Some(win) => win,
             None => return,
         };
         let global_obj = window.as_ref();
 
-        let buildings_serialized = match serde_wasm_bindgen::to_value(&self.buildings) {
+        let buildings_serialized = match serde_wasm_bindgen::to_value(&self.core.priced_buildings())
+        {
             Ok(val) => val,
             Err(_) => return,
         };
@@ -1149,7 +484,7 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn update_ui(&self) {
-        let window = match web_sys::window() {
+        let window = match ui_window() {
             Some(win) => win,
             None => return,
         };
@@ -1179,7 +514,7 @@ impl IdleGame {
             );
         }
 
-        let upgrades_serialized = match serde_wasm_bindgen::to_value(&self.upgrades) {
+        let upgrades_serialized = match serde_wasm_bindgen::to_value(&self.core.upgrades) {
             Ok(val) => val,
             Err(_) => return,
         };
@@ -1190,7 +525,8 @@ impl IdleGame {
             let _ = update_upgrades.call1(&JsValue::NULL, &upgrades_serialized);
         }
 
-        let buildings_serialized = match serde_wasm_bindgen::to_value(&self.buildings) {
+        let buildings_serialized = match serde_wasm_bindgen::to_value(&self.core.priced_buildings())
+        {
             Ok(val) => val,
             Err(_) => return,
         };
@@ -1204,138 +540,85 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn get_crafting_recipes(&self) -> JsValue {
-        match serde_wasm_bindgen::to_value(&self.crafting_recipes) {
+        match serde_wasm_bindgen::to_value(&self.core.crafting_recipes) {
             Ok(val) => val,
             Err(_) => JsValue::NULL,
         }
     }
 
+    /// Crafts in progress or waiting for a slot, oldest first
     #[wasm_bindgen]
-    pub fn unlock_feature(&mut self, feature_id: &str) -> bool {
-        if !self.check_unlock(feature_id) {
-            return false;
+    pub fn get_crafting_queue(&self) -> JsValue {
+        match serde_wasm_bindgen::to_value(&self.core.crafting_queue) {
+            Ok(val) => val,
+            Err(_) => JsValue::NULL,
         }
+    }
 
-        let feature = match self
-            .unlocked_features
-            .iter_mut()
-            .find(|f| f.id == feature_id)
-        {
-            Some(f) => f,
-            None => return false,
-        };
+    #[wasm_bindgen]
+    pub fn get_crafting_slots(&self) -> u32 {
+        self.core.crafting_slots()
+    }
 
-        if feature.unlocked {
-            return true;
-        }
+    #[wasm_bindgen]
+    pub fn get_worker_slots(&self) -> u32 {
+        self.core.worker_slots()
+    }
 
-        feature.unlocked = true;
-        feature.unlock_timestamp = Some(Date::now());
-        true
+    /// Cancel the queued craft at `index`, refunding its inputs
+    #[wasm_bindgen]
+    pub fn cancel_craft(&mut self, index: usize) -> bool {
+        self.core.cancel_craft(index)
+    }
+
+    #[wasm_bindgen]
+    pub fn unlock_feature(&mut self, feature_id: &str) -> bool {
+        self.core.unlock_feature(feature_id)
     }
 
     #[wasm_bindgen]
     pub fn get_unlocks(&self) -> JsValue {
-        match serde_wasm_bindgen::to_value(&self.unlocked_features) {
+        match serde_wasm_bindgen::to_value(&self.core.unlocked_features) {
             Ok(val) => val,
             Err(_) => JsValue::NULL,
         }
     }
 
+    /// Trade the current run for prestige points, keeping statistics,
+    /// achievements and unlocks; false if no point is claimable yet
     #[wasm_bindgen]
-    pub fn reset_game(&mut self) {
-        // Reset Statistics to zeros
-        {
-            let mut stats = self.statistics.borrow_mut();
-            stats.total_clicks = 0;
-            stats.total_coins_earned = 0.0;
-            stats.total_wood_earned = 0.0;
-            stats.total_stone_earned = 0.0;
-            stats.total_resources_crafted = 0;
-            stats.achievements_unlocked_count = 0;
-            stats.play_time_seconds = 0.0;
-            stats.buildings_purchased = 0;
-            stats.upgrades_purchased = 0;
-        }
-
-        // Reset Achievements (unlocked=false, progress=0)
-        for achievement in self.achievements.iter_mut() {
-            achievement.unlocked = false;
-            achievement.unlock_timestamp = None;
-            achievement.progress = 0.0;
-        }
-
-        // Reset Crafting progress (all recipes unlocked=true as in new())
-        for recipe in self.crafting_recipes.iter_mut() {
-            recipe.unlocked = true;
-        }
-
-        // Reset Unlocks (unlocked=false)
-        for feature in self.unlocked_features.iter_mut() {
-            feature.unlocked = false;
-            feature.unlock_timestamp = None;
-        }
+    pub fn prestige(&mut self) -> bool {
+        self.core.prestige()
+    }
 
-        // Reset Workers (level=1, assigned_building=None, xp=0, etc.)
-        for worker in self.workers.iter_mut() {
-            worker.assigned_building = None;
-            worker.level = 1;
-            worker.efficiency_multiplier = 1.0;
-            worker.xp = 0.0;
-            worker.xp_to_next_level = 100.0;
-        }
+    /// Prestige points, pending points, multiplier and the lifetime coins
+    /// needed for the next point
+    #[wasm_bindgen]
+    pub fn get_prestige(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.prestige_summary()).unwrap_or(JsValue::NULL)
+    }
 
-        // Reset game state (coins, wood, stone, etc.)
-        {
-            let mut state = self.state.borrow_mut();
-            state.coins = 0.0;
-            state.wood = 0.0;
-            state.stone = 0.0;
-            state.coins_per_click = 1.0;
-            state.coins_per_second = 0.0;
-            state.wood_per_second = 0.0;
-            state.stone_per_second = 0.0;
-            state.autoclick_count = 0;
-            state.total_clicks = 0;
-            state.last_update_time = Date::now();
-        }
+    /// Perks bought with prestige points, with their levels and costs
+    #[wasm_bindgen]
+    pub fn get_prestige_upgrades(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.prestige_upgrades).unwrap_or(JsValue::NULL)
+    }
 
-        // Reset Upgrades (owned=0, cost=initial, unlocked=true)
-        for (i, upgrade) in self.upgrades.iter_mut().enumerate() {
-            upgrade.owned = 0;
-            upgrade.unlocked = true;
-            // Reset costs to initial values
-            match i {
-                0 => upgrade.cost = 10.0, // Better Click
-                1 => upgrade.cost = 50.0, // Autoclicker Lv1
-                2 => upgrade.cost = 20.0, // Lumberjack Efficiency
-                3 => upgrade.cost = 25.0, // Stone Mason Skill
-                _ => {}
-            }
-        }
+    #[wasm_bindgen]
+    pub fn buy_prestige_upgrade(&mut self, index: usize) -> bool {
+        self.core.buy_prestige_upgrade(index)
+    }
 
-        // Reset Buildings (count=0, cost=initial)
-        for (i, building) in self.buildings.iter_mut().enumerate() {
-            building.count = 0;
-            // Reset costs to initial values
-            match i {
-                0 => building.cost = 15.0,  // Coin Mine
-                1 => building.cost = 100.0, // Coin Factory
-                2 => building.cost = 500.0, // Coin Corporation
-                3 => building.cost = 20.0,  // Woodcutter
-                4 => building.cost = 80.0,  // Lumber Mill
-                5 => building.cost = 400.0, // Forest Workshop
-                6 => building.cost = 25.0,  // Stone Quarry
-                7 => building.cost = 90.0,  // Rock Crusher
-                8 => building.cost = 450.0, // Mason Workshop
-                _ => {}
-            }
-        }
+    /// Start over; the game being reset is kept as a backup first
+    #[wasm_bindgen]
+    pub fn reset_game(&mut self) {
+        self.backup_before("reset");
+        self.core.reset_game();
     }
 
     #[wasm_bindgen(js_name = get_achievements)]
     pub fn get_achievements_js(&self) -> JsValue {
-        match serde_wasm_bindgen::to_value(&self.achievements) {
+        match serde_wasm_bindgen::to_value(&self.core.achievements) {
             Ok(val) => val,
             Err(_) => JsValue::NULL,
         }
@@ -1343,214 +626,172 @@ impl IdleGame {
 
     #[wasm_bindgen]
     pub fn check_achievement(&mut self, achievement_id: &str) -> bool {
-        // First, read state and stats (release borrows immediately)
-        let (
-            total_clicks,
-            coins,
-            wood,
-            stone,
-            buildings_purchased,
-            total_resources_crafted,
-            achievements_unlocked_count,
-        ) = {
-            let state = self.state.borrow();
-            let stats = self.statistics.borrow();
-            (
-                state.total_clicks as f64,
-                state.coins,
-                state.wood,
-                state.stone,
-                stats.buildings_purchased as f64,
-                stats.total_resources_crafted as f64,
-                stats.achievements_unlocked_count as f64,
-            )
-        }; // All borrows released here
-
-        // Find and update the achievement (separate borrow)
-        let mut unlocked_this_call = false;
-        {
-            let achievement = match self
-                .achievements
-                .iter_mut()
-                .find(|a| a.id == achievement_id)
-            {
-                Some(a) => a,
-                None => return false,
-            };
-
-            if achievement.unlocked {
-                return true;
-            }
-
-            let current_value = match achievement.category.as_str() {
-                "clicks" => total_clicks,
-                "resources" => {
-                    if achievement.id == "first_coins_100" {
-                        coins
-                    } else if achievement.id == "wood_collector_1000" {
-                        wood
-                    } else if achievement.id == "stone_hoarder_5000" {
-                        stone
-                    } else {
-                        0.0
-                    }
-                }
-                "buildings" => buildings_purchased,
-                "crafting" => total_resources_crafted,
-                "unlocks" => achievements_unlocked_count,
-                _ => 0.0,
-            };
-
-            achievement.progress = current_value;
-
-            if achievement.progress >= achievement.requirement {
-                achievement.unlocked = true;
-                achievement.unlock_timestamp = Some(Date::now());
-                unlocked_this_call = true;
-            }
-        } // achievement borrow released here
-
-        // Update statistics if unlocked (separate borrow)
-        if unlocked_this_call {
-            {
-                let mut stats_mut = self.statistics.borrow_mut();
-                stats_mut.achievements_unlocked_count += 1;
-            } // stats borrow released here
-
-            // Recursively check dependent achievements (all previous borrows released)
-            self.check_achievement("first_unlock");
-            self.check_achievement("progress_master_5");
-
-            return true;
-        }
-
-        false
+        self.core.check_achievement(achievement_id)
     }
 
     pub fn check_all_achievements(&mut self) {
-        let achievement_ids: Vec<String> = self.achievements.iter().map(|a| a.id.clone()).collect();
-        for id in achievement_ids {
-            self.check_achievement(&id);
-        }
+        self.core.check_all_achievements();
     }
 
-    pub fn check_unlock(&mut self, feature_id: &str) -> bool {
-        let state = self.state.borrow();
-        let stats = self.statistics.borrow();
-
-        let feature = match self
-            .unlocked_features
-            .iter_mut()
-            .find(|f| f.id == feature_id)
-        {
-            Some(f) => f,
-            None => return false,
-        };
-
-        if feature.unlocked {
-            return true;
-        }
-
-        let current_value = match feature.requirement_type.as_str() {
-            "total_clicks" => state.total_clicks as f64,
-            "total_coins" => state.coins,
-            "buildings_owned" => stats.buildings_purchased as f64,
-            _ => 0.0,
-        };
-
-        drop(stats);
-        drop(state);
-
-        current_value >= feature.requirement_value
+    pub fn check_unlock(&self, feature_id: &str) -> bool {
+        self.core.check_unlock(feature_id)
     }
 }
 
 #[wasm_bindgen]
 impl IdleGame {
-    /// Save game to localStorage via JS interop
+    /// Save the game to the storage backend (localStorage unless another
+    /// was set); at most every few minutes it is also kept as a backup
     #[wasm_bindgen(js_name = saveToLocalStorage)]
-    pub fn save_to_local_storage(&self) -> Result<(), JsValue> {
-        let saved_game = self.save_game();
-
-        // Serialize to JSON
-        let json_str = serde_json::to_string(&saved_game)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-
-        // Save to localStorage using JavaScript
-        let window = web_sys::window().ok_or_else(|| JsValue::from_str("Window not available"))?;
-
-        let local_storage = window
-            .local_storage()
-            .map_err(|e| JsValue::from_str(&format!("localStorage access error: {:?}", e)))?
-            .ok_or_else(|| JsValue::from_str("localStorage not available"))?;
-
-        local_storage
-            .set_item("idle_game_save", &json_str)
-            .map_err(|e| JsValue::from_str(&format!("localStorage set error: {:?}", e)))?;
-
-        Ok(())
+    pub fn save_to_local_storage(&mut self) -> Result<(), JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        self.core.save_to(storage).map_err(|e| save_error_to_js(&e))
     }
 
-    /// Load game from localStorage via JS interop
+    /// Load the current save from the storage backend; false if there is none
     #[wasm_bindgen(js_name = loadFromLocalStorage)]
     pub fn load_from_local_storage(&mut self) -> Result<bool, JsValue> {
-        let window = web_sys::window().ok_or_else(|| JsValue::from_str("Window not available"))?;
-
-        let local_storage = window
-            .local_storage()
-            .map_err(|e| JsValue::from_str(&format!("localStorage access error: {:?}", e)))?
-            .ok_or_else(|| JsValue::from_str("localStorage not available"))?;
-
-        // Try to get saved game data
-        let saved_data = local_storage
-            .get_item("idle_game_save")
-            .map_err(|e| JsValue::from_str(&format!("localStorage get error: {:?}", e)))?;
-
-        match saved_data {
-            Some(json_str) => {
-                // Deserialize from JSON
-                let saved_game: SavedGame = serde_json::from_str(&json_str)
-                    .map_err(|e| JsValue::from_str(&format!("Deserialization error: {}", e)))?;
-
-                // Load the saved game state
-                self.load_game(saved_game);
-                Ok(true)
-            }
-            None => Ok(false), // No saved game found
-        }
-    }
-
-    /// Export game save to BASE64 string
+        let storage = storage_mut(&mut self.storage)?;
+        let report = self
+            .core
+            .load_from(storage)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(report.is_some())
+    }
+
+    /// Keep the current game as a backup right away, e.g. before something
+    /// that overwrites it
+    #[wasm_bindgen(js_name = backupToLocalStorage)]
+    pub fn backup_to_local_storage(&mut self) -> Result<JsValue, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        let metadata = self
+            .core
+            .backup_to(storage)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&metadata).unwrap_or(JsValue::NULL))
+    }
+
+    /// Metadata of every save slot and backup: name, kind ("manual" or
+    /// "backup"), time saved, play time, coins and save version
+    #[wasm_bindgen(js_name = listSaveSlots)]
+    pub fn list_save_slots(&mut self) -> Result<JsValue, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        let slots = slots::list(storage).map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&slots).unwrap_or(JsValue::NULL))
+    }
+
+    /// Save the current game to the named slot, replacing what it held
+    #[wasm_bindgen(js_name = saveToSlot)]
+    pub fn save_to_slot(&mut self, name: &str) -> Result<JsValue, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        let metadata = self
+            .core
+            .save_to_slot(storage, name)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&metadata).unwrap_or(JsValue::NULL))
+    }
+
+    /// Load a slot or backup into the game and return the migration report;
+    /// the current save in storage is left alone until the next save
+    #[wasm_bindgen(js_name = loadFromSlot)]
+    pub fn load_from_slot(&mut self, name: &str) -> Result<JsValue, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        let report = self
+            .core
+            .load_slot(storage, name)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&report).unwrap_or(JsValue::NULL))
+    }
+
+    /// Load a slot or backup and make it the current save straight away; the
+    /// game it replaces is kept as a backup
+    #[wasm_bindgen(js_name = restoreSlot)]
+    pub fn restore_slot(&mut self, name: &str) -> Result<JsValue, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        let report = self
+            .core
+            .restore_slot(storage, name)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&report).unwrap_or(JsValue::NULL))
+    }
+
+    /// Delete a slot or backup; false if there was no such slot
+    #[wasm_bindgen(js_name = deleteSlot)]
+    pub fn delete_slot(&mut self, name: &str) -> Result<bool, JsValue> {
+        let storage = storage_mut(&mut self.storage)?;
+        slots::delete_slot(storage, name).map_err(|e| save_error_to_js(&e))
+    }
+
+    /// Save to a JS object with synchronous `getItem`, `setItem` and
+    /// `removeItem` methods instead of localStorage
+    #[wasm_bindgen(js_name = setStorageBackend)]
+    pub fn set_storage_backend(&mut self, backend: JsValue) {
+        self.set_storage(Box::new(JsObjectStorage::new(backend)));
+    }
+
+    /// Export game save as a signed code: a format header, the BASE64
+    /// payload and an HMAC over both; `format` is "json" (the default) or
+    /// "compact" for a much shorter binary, compressed code
     #[wasm_bindgen(js_name = exportToBase64)]
-    pub fn export_to_base64(&self) -> Result<String, JsValue> {
-        let saved_game = self.save_game();
-
-        // Serialize to JSON
-        let json_str = serde_json::to_string(&saved_game)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+    pub fn export_to_base64(&self, format: Option<String>) -> Result<String, JsValue> {
+        let format = format.map_or(SaveFormat::Json, |name| SaveFormat::parse(&name));
+        Ok(self.core.export_code_as(format))
+    }
 
-        // Encode to BASE64
-        let base64_str = general_purpose::STANDARD.encode(&json_str);
-        Ok(base64_str)
+    /// Byte sizes of the current game in the JSON and compact export formats
+    #[wasm_bindgen(js_name = getExportSizeReport)]
+    pub fn get_export_size_report(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.core.export_size_report()).unwrap_or(JsValue::NULL)
     }
 
-    /// Import game save from BASE64 string
+    /// Import a save code and return its integrity ("verified", "modified" or
+    /// "unsigned") with the migration report; with `strict` an unsigned or
+    /// modified code, or a save that fails validation, is rejected instead of
+    /// repaired. The signing key ships with the client, so integrity only
+    /// flags hand edits and must not be relied on for security
     #[wasm_bindgen(js_name = importFromBase64)]
-    pub fn import_from_base64(&mut self, base64_str: &str) -> Result<(), JsValue> {
-        // Decode from BASE64
-        let json_bytes = general_purpose::STANDARD
-            .decode(base64_str)
-            .map_err(|e| JsValue::from_str(&format!("BASE64 decode error: {}", e)))?;
+    pub fn import_from_base64(
+        &mut self,
+        base64_str: &str,
+        strict: Option<bool>,
+    ) -> Result<JsValue, JsValue> {
+        let mode = if strict.unwrap_or(false) {
+            LoadMode::Strict
+        } else {
+            LoadMode::Repair
+        };
+        self.backup_before("import");
+        let report = self
+            .core
+            .import_code(base64_str, mode)
+            .map_err(|e| save_error_to_js(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&report).unwrap_or(JsValue::NULL))
+    }
 
-        let json_str = String::from_utf8(json_bytes)
-            .map_err(|e| JsValue::from_str(&format!("UTF8 conversion error: {}", e)))?;
+    /// Sign and verify save codes with a shared secret instead of the default key
+    #[wasm_bindgen(js_name = setExportKey)]
+    pub fn set_export_key(&mut self, key: &str) {
+        self.core.set_export_key(key.as_bytes());
+    }
+}
 
-        // Deserialize from JSON
-        let saved_game: SavedGame = serde_json::from_str(&json_str)
-            .map_err(|e| JsValue::from_str(&format!("Deserialization error: {}", e)))?;
+/// The storage backend, opening localStorage the first time none was set
+fn storage_mut(
+    storage: &mut Option<Box<dyn SaveStorage>>,
+) -> Result<&mut dyn SaveStorage, JsValue> {
+    if storage.is_none() {
+        let local = LocalStorage::open().map_err(|e| save_error_to_js(&e))?;
+        *storage = Some(Box::new(local));
+    }
+    Ok(storage.as_deref_mut().expect("storage was just opened"))
+}
 
-        // Load the saved game state
-        self.load_game(saved_game);
-        Ok(())
+/// A JS `Error` carrying the message, with the structured error (including
+/// any validation problems) under `details`
+fn save_error_to_js(error: &SaveError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    if let Ok(details) = serde_wasm_bindgen::to_value(error) {
+        let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("details"), &details);
     }
+    js_error.into()
 }
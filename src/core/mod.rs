@@ -1,3 +1,11 @@
+pub mod clock;
+pub mod events;
+pub mod game_core;
 pub mod idle_game;
+pub mod rng;
 
+pub use crate::persistence::SavedGame;
+pub use clock::{BrowserClock, Clock, ManualClock, MonotonicClock};
+pub use events::{FailureReason, GameEvent, PurchaseTarget};
+pub use game_core::GameCore;
 pub use idle_game::IdleGame;
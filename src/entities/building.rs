@@ -1,9 +1,54 @@
+use crate::state::{BigNum, ResourceId, ResourceRate};
 use serde::{Deserialize, Serialize};
 
+/// A recipe a building crafts on its own, drawing inputs from the stockpile
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Converter {
+    /// Id of the crafting recipe to run
+    pub recipe: String,
+    /// Crafts per second for each owned building
+    pub rate: f64,
+    /// Fraction of the next craft already done
+    #[serde(default)]
+    pub progress: f64,
+    /// Input that ran out, while the converter waits for more of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stalled_on: Option<ResourceId>,
+    /// The recipe is locked for the player, so the converter stays idle too
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+}
+
+impl Converter {
+    pub fn new(recipe: &str, rate: f64) -> Converter {
+        Converter {
+            recipe: recipe.to_string(),
+            rate,
+            progress: 0.0,
+            stalled_on: None,
+            locked: false,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Building {
+    /// Stable content id; empty in saves made before ids existed
+    #[serde(default)]
+    pub id: String,
     pub name: String,
-    pub cost: f64,
-    pub production_rate: f64,
+    pub cost: BigNum,
+    /// Resources each owned building produces per second
+    #[serde(default)]
+    pub outputs: Vec<ResourceRate>,
+    /// Resources each owned building consumes per second
+    #[serde(default)]
+    pub inputs: Vec<ResourceRate>,
+    /// Feature that must be unlocked before it can be bought
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    /// Recipe the building runs continuously, if it is a converter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub converter: Option<Converter>,
     pub count: u32,
 }
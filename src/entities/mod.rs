@@ -2,6 +2,6 @@ pub mod building;
 pub mod upgrade;
 pub mod worker;
 
-pub use building::Building;
+pub use building::{Building, Converter};
 pub use upgrade::Upgrade;
 pub use worker::Worker;
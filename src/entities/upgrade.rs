@@ -1,10 +1,27 @@
+use crate::state::BigNum;
+use crate::systems::UpgradeEffect;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Upgrade {
+    /// Stable content id; empty in saves made before ids existed
+    #[serde(default)]
+    pub id: String,
     pub name: String,
-    pub cost: f64,
-    pub production_increase: f64,
+    pub cost: BigNum,
+    /// What each owned level does
+    #[serde(default)]
+    pub effects: Vec<UpgradeEffect>,
     pub owned: u32,
     pub unlocked: bool,
+    /// Most levels that can be owned; unlimited when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_level: Option<u32>,
+}
+
+impl Upgrade {
+    /// Whether every level there is has been bought
+    pub fn is_maxed(&self) -> bool {
+        self.max_level.is_some_and(|max| self.owned >= max)
+    }
 }
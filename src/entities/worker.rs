@@ -2,10 +2,18 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Worker {
+    /// Stable content id; empty in saves made before ids existed
+    #[serde(default)]
+    pub id: String,
     pub name: String,
     pub skills: String,
     pub background: String,
+    /// Id of the building this worker is most efficient in
     pub preferences: String,
+    /// Feature that must be unlocked before the worker can be assigned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    /// Id of the building the worker is assigned to
     pub assigned_building: Option<String>,
     pub level: u32,
     pub efficiency_multiplier: f64,
@@ -1,19 +1,41 @@
 use wasm_bindgen::prelude::*;
 
+pub mod content;
 pub mod core;
 pub mod entities;
+pub mod persistence;
 pub mod state;
 pub mod systems;
 pub mod ui;
 #[cfg(test)]
 pub mod test_utils;
 
-pub use core::IdleGame;
+pub use core::{GameCore, IdleGame};
 pub use entities::{Building, Upgrade, Worker};
-pub use state::{GameState, Statistics};
+pub use state::{BigNum, GameState, Notation, ResourceId, ResourceRegistry, Statistics};
 pub use systems::{Achievement, CraftingRecipe, UnlockedFeature};
 
 #[wasm_bindgen]
 pub fn init_game() -> IdleGame {
     IdleGame::new()
 }
+
+/// Start a game whose content is the embedded default with `content_json` merged on top
+#[wasm_bindgen]
+pub fn init_game_with_content(content_json: &str) -> Result<IdleGame, JsValue> {
+    IdleGame::with_content(content_json)
+}
+
+/// Format a number, or a big-number string such as "1.5e300" from a save, in
+/// "short", "scientific" or "engineering" notation
+#[wasm_bindgen]
+pub fn format_number(value: JsValue, notation: &str) -> String {
+    let number = match value.as_f64() {
+        Some(number) => BigNum::from_f64(number),
+        None => value
+            .as_string()
+            .and_then(|text| BigNum::parse(&text))
+            .unwrap_or(BigNum::ZERO),
+    };
+    number.format(Notation::parse(notation))
+}
@@ -0,0 +1,373 @@
+use super::{SaveError, SavedGame, SAVE_VERSION};
+use crate::content::GameContent;
+use crate::state::{BigNum, GameState, ResourceId, Statistics};
+use crate::systems::{CraftingJob, PrestigeState};
+use serde::{Deserialize, Serialize};
+
+/// Deflate level used for compact saves
+const COMPRESSION_LEVEL: u8 = 9;
+/// Largest packed save accepted, so a crafted code cannot exhaust memory
+const MAX_UNPACKED_BYTES: usize = 1 << 20;
+
+/// A `BigNum` as its raw parts; postcard cannot carry the number-or-string
+/// form used in JSON
+#[derive(Serialize, Deserialize)]
+struct PackedNum(f64, i64);
+
+impl From<BigNum> for PackedNum {
+    fn from(value: BigNum) -> PackedNum {
+        let (mantissa, exponent) = value.parts();
+        PackedNum(mantissa, exponent)
+    }
+}
+
+impl From<PackedNum> for BigNum {
+    fn from(value: PackedNum) -> BigNum {
+        BigNum::new(value.0, value.1)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedResource {
+    id: String,
+    amount: PackedNum,
+    lifetime_earned: PackedNum,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedStatistics {
+    total_clicks: u32,
+    total_coins_earned: PackedNum,
+    total_wood_earned: PackedNum,
+    total_stone_earned: PackedNum,
+    total_resources_crafted: u32,
+    achievements_unlocked_count: u32,
+    play_time_seconds: f64,
+    buildings_purchased: u32,
+    upgrades_purchased: u32,
+    total_earned: Vec<(ResourceId, PackedNum)>,
+}
+
+/// Id, count or level owned, and current cost
+#[derive(Serialize, Deserialize)]
+struct PackedPurchase {
+    id: String,
+    owned: u32,
+    cost: PackedNum,
+    unlocked: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedWorker {
+    id: String,
+    assigned_building: Option<String>,
+    level: u32,
+    efficiency_multiplier: f64,
+    xp: f64,
+    xp_to_next_level: f64,
+}
+
+/// Partial craft and stall of a converter building; its recipe and rate come
+/// from content
+#[derive(Serialize, Deserialize)]
+struct PackedConverter {
+    building: String,
+    progress: f64,
+    stalled_on: Option<ResourceId>,
+    locked: bool,
+}
+
+/// Unlock state of an achievement, recipe or feature
+#[derive(Serialize, Deserialize)]
+struct PackedFlag {
+    id: String,
+    unlocked: bool,
+    timestamp: Option<f64>,
+    progress: f64,
+}
+
+/// Only what changes while playing, keyed by content id; names, flows and
+/// descriptions come back from content when unpacking
+#[derive(Serialize, Deserialize)]
+struct PackedSave {
+    save_timestamp: f64,
+    total_clicks: u32,
+    last_update_time: f64,
+    resources: Vec<PackedResource>,
+    statistics: PackedStatistics,
+    buildings: Vec<PackedPurchase>,
+    upgrades: Vec<PackedPurchase>,
+    workers: Vec<PackedWorker>,
+    achievements: Vec<PackedFlag>,
+    recipes: Vec<PackedFlag>,
+    unlocks: Vec<PackedFlag>,
+    prestige_points: PackedNum,
+    prestige_points_earned: PackedNum,
+    prestige_count: u32,
+    prestige_upgrades: Vec<PackedPurchase>,
+    crafting_queue: Vec<CraftingJob>,
+    converters: Vec<PackedConverter>,
+}
+
+impl PackedSave {
+    fn from_saved(saved: &SavedGame) -> PackedSave {
+        let statistics = &saved.statistics;
+        PackedSave {
+            save_timestamp: saved.save_timestamp,
+            total_clicks: saved.state.total_clicks,
+            last_update_time: saved.state.last_update_time,
+            resources: saved
+                .state
+                .resources
+                .iter()
+                .map(|r| PackedResource {
+                    id: r.id.clone(),
+                    amount: r.amount.into(),
+                    lifetime_earned: r.lifetime_earned.into(),
+                })
+                .collect(),
+            statistics: PackedStatistics {
+                total_clicks: statistics.total_clicks,
+                total_coins_earned: statistics.total_coins_earned.into(),
+                total_wood_earned: statistics.total_wood_earned.into(),
+                total_stone_earned: statistics.total_stone_earned.into(),
+                total_resources_crafted: statistics.total_resources_crafted,
+                achievements_unlocked_count: statistics.achievements_unlocked_count,
+                play_time_seconds: statistics.play_time_seconds,
+                buildings_purchased: statistics.buildings_purchased,
+                upgrades_purchased: statistics.upgrades_purchased,
+                total_earned: statistics
+                    .total_earned
+                    .iter()
+                    .map(|(id, amount)| (id.clone(), (*amount).into()))
+                    .collect(),
+            },
+            buildings: saved
+                .buildings
+                .iter()
+                .map(|b| PackedPurchase {
+                    id: b.id.clone(),
+                    owned: b.count,
+                    cost: b.cost.into(),
+                    unlocked: true,
+                })
+                .collect(),
+            upgrades: saved
+                .upgrades
+                .iter()
+                .map(|u| PackedPurchase {
+                    id: u.id.clone(),
+                    owned: u.owned,
+                    cost: u.cost.into(),
+                    unlocked: u.unlocked,
+                })
+                .collect(),
+            workers: saved
+                .workers
+                .iter()
+                .map(|w| PackedWorker {
+                    id: w.id.clone(),
+                    assigned_building: w.assigned_building.clone(),
+                    level: w.level,
+                    efficiency_multiplier: w.efficiency_multiplier,
+                    xp: w.xp,
+                    xp_to_next_level: w.xp_to_next_level,
+                })
+                .collect(),
+            achievements: saved
+                .achievements
+                .iter()
+                .map(|a| PackedFlag {
+                    id: a.id.clone(),
+                    unlocked: a.unlocked,
+                    timestamp: a.unlock_timestamp,
+                    progress: a.progress,
+                })
+                .collect(),
+            recipes: saved
+                .crafting_recipes
+                .iter()
+                .map(|r| PackedFlag {
+                    id: r.id.clone(),
+                    unlocked: r.unlocked,
+                    timestamp: None,
+                    progress: 0.0,
+                })
+                .collect(),
+            unlocks: saved
+                .unlocked_features
+                .iter()
+                .map(|u| PackedFlag {
+                    id: u.id.clone(),
+                    unlocked: u.unlocked,
+                    timestamp: u.unlock_timestamp,
+                    progress: 0.0,
+                })
+                .collect(),
+            prestige_points: saved.prestige.points.into(),
+            prestige_points_earned: saved.prestige.total_points_earned.into(),
+            prestige_count: saved.prestige.prestige_count,
+            prestige_upgrades: saved
+                .prestige_upgrades
+                .iter()
+                .map(|u| PackedPurchase {
+                    id: u.id.clone(),
+                    owned: u.owned,
+                    cost: u.cost.into(),
+                    unlocked: u.unlocked,
+                })
+                .collect(),
+            crafting_queue: saved.crafting_queue.clone(),
+            converters: saved
+                .buildings
+                .iter()
+                .filter_map(|b| {
+                    let converter = b.converter.as_ref()?;
+                    Some(PackedConverter {
+                        building: b.id.clone(),
+                        progress: converter.progress,
+                        stalled_on: converter.stalled_on.clone(),
+                        locked: converter.locked,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// A full save built from fresh `content` entities with the packed state
+    /// applied; entries whose id is not in content are dropped
+    fn into_saved(self, content: &GameContent) -> SavedGame {
+        let mut state = GameState::new(content.new_resources(), self.last_update_time);
+        state.total_clicks = self.total_clicks;
+        for packed in self.resources {
+            if let Some(resource) = state.resources.get_mut(&packed.id) {
+                resource.amount = packed.amount.into();
+                resource.lifetime_earned = packed.lifetime_earned.into();
+            }
+        }
+
+        let packed = self.statistics;
+        let statistics = Statistics {
+            total_clicks: packed.total_clicks,
+            total_coins_earned: packed.total_coins_earned.into(),
+            total_wood_earned: packed.total_wood_earned.into(),
+            total_stone_earned: packed.total_stone_earned.into(),
+            total_resources_crafted: packed.total_resources_crafted,
+            achievements_unlocked_count: packed.achievements_unlocked_count,
+            play_time_seconds: packed.play_time_seconds,
+            buildings_purchased: packed.buildings_purchased,
+            upgrades_purchased: packed.upgrades_purchased,
+            total_earned: packed
+                .total_earned
+                .into_iter()
+                .map(|(id, amount)| (id, amount.into()))
+                .collect(),
+        };
+
+        let mut buildings = content.new_buildings();
+        for packed in self.buildings {
+            if let Some(building) = buildings.iter_mut().find(|b| b.id == packed.id) {
+                building.count = packed.owned;
+                building.cost = packed.cost.into();
+            }
+        }
+        for packed in self.converters {
+            let converter = buildings
+                .iter_mut()
+                .find(|b| b.id == packed.building)
+                .and_then(|b| b.converter.as_mut());
+            if let Some(converter) = converter {
+                converter.progress = packed.progress;
+                converter.stalled_on = packed.stalled_on;
+                converter.locked = packed.locked;
+            }
+        }
+        let mut upgrades = content.new_upgrades();
+        for packed in self.upgrades {
+            if let Some(upgrade) = upgrades.iter_mut().find(|u| u.id == packed.id) {
+                upgrade.owned = packed.owned;
+                upgrade.cost = packed.cost.into();
+                upgrade.unlocked = packed.unlocked;
+            }
+        }
+        let mut prestige_upgrades = content.new_prestige_upgrades();
+        for packed in self.prestige_upgrades {
+            if let Some(upgrade) = prestige_upgrades.iter_mut().find(|u| u.id == packed.id) {
+                upgrade.owned = packed.owned;
+                upgrade.cost = packed.cost.into();
+                upgrade.unlocked = packed.unlocked;
+            }
+        }
+        let mut workers = content.new_workers();
+        for packed in self.workers {
+            if let Some(worker) = workers.iter_mut().find(|w| w.id == packed.id) {
+                worker.assigned_building = packed.assigned_building;
+                worker.level = packed.level;
+                worker.efficiency_multiplier = packed.efficiency_multiplier;
+                worker.xp = packed.xp;
+                worker.xp_to_next_level = packed.xp_to_next_level;
+            }
+        }
+        let mut achievements = content.new_achievements();
+        for packed in self.achievements {
+            if let Some(achievement) = achievements.iter_mut().find(|a| a.id == packed.id) {
+                achievement.unlocked = packed.unlocked;
+                achievement.unlock_timestamp = packed.timestamp;
+                achievement.progress = packed.progress;
+            }
+        }
+        let mut crafting_recipes = content.new_recipes();
+        for packed in self.recipes {
+            if let Some(recipe) = crafting_recipes.iter_mut().find(|r| r.id == packed.id) {
+                recipe.unlocked = packed.unlocked;
+            }
+        }
+        let mut unlocked_features = content.new_unlocks();
+        for packed in self.unlocks {
+            if let Some(feature) = unlocked_features.iter_mut().find(|u| u.id == packed.id) {
+                feature.unlocked = packed.unlocked;
+                feature.unlock_timestamp = packed.timestamp;
+            }
+        }
+
+        SavedGame {
+            save_version: SAVE_VERSION,
+            state,
+            statistics,
+            upgrades,
+            buildings,
+            workers,
+            achievements,
+            crafting_recipes,
+            crafting_queue: self.crafting_queue,
+            unlocked_features,
+            prestige: PrestigeState {
+                points: self.prestige_points.into(),
+                total_points_earned: self.prestige_points_earned.into(),
+                prestige_count: self.prestige_count,
+            },
+            prestige_upgrades,
+            save_timestamp: self.save_timestamp,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// Binary-packed mutable state, before compression
+pub fn pack_uncompressed(saved: &SavedGame) -> Vec<u8> {
+    postcard::to_allocvec(&PackedSave::from_saved(saved)).expect("saves always serialize")
+}
+
+/// Mutable state of `saved`, binary-packed and deflated
+pub fn pack(saved: &SavedGame) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec(&pack_uncompressed(saved), COMPRESSION_LEVEL)
+}
+
+/// Inverse of `pack`, filling in everything else from `content`
+pub fn unpack(bytes: &[u8], content: &GameContent) -> Result<SavedGame, SaveError> {
+    let packed = miniz_oxide::inflate::decompress_to_vec_with_limit(bytes, MAX_UNPACKED_BYTES)
+        .map_err(|e| SaveError::Corrupted(format!("decompression failed: {:?}", e.status)))?;
+    let save: PackedSave = postcard::from_bytes(&packed)
+        .map_err(|e| SaveError::Corrupted(format!("binary decode error: {}", e)))?;
+    Ok(save.into_saved(content))
+}
@@ -0,0 +1,132 @@
+use super::{MigrationReport, SaveError};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// First field of an export code with a JSON payload
+pub const EXPORT_HEADER: &str = "IG1";
+/// First field of an export code with a compact payload
+pub const COMPACT_EXPORT_HEADER: &str = "IGZ1";
+
+/// Key used to sign exports unless the game is given another one. It ships
+/// inside the public client, so anyone can re-sign an edited code with it:
+/// signatures only make casual edits evident and are not a security boundary
+pub const DEFAULT_EXPORT_KEY: &[u8] = b"idle-game-export-v1";
+
+/// Payload layout of an export code
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveFormat {
+    /// The full `SavedGame` as JSON
+    #[default]
+    Json,
+    /// Mutable state only, binary-packed and compressed
+    Compact,
+}
+
+impl SaveFormat {
+    /// Parse a format name from the frontend; anything unknown is `Json`
+    pub fn parse(name: &str) -> SaveFormat {
+        match name {
+            "compact" => SaveFormat::Compact,
+            _ => SaveFormat::Json,
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            SaveFormat::Json => EXPORT_HEADER,
+            SaveFormat::Compact => COMPACT_EXPORT_HEADER,
+        }
+    }
+
+    fn from_header(header: &str) -> Option<SaveFormat> {
+        [SaveFormat::Json, SaveFormat::Compact]
+            .into_iter()
+            .find(|format| format.header() == header)
+    }
+}
+
+/// Whether an imported save is exactly what a game exported, as far as the
+/// signing key can tell; with the default key this is tamper evidence against
+/// hand edits, not proof that nobody altered the save
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Integrity {
+    /// The checksum matches the payload under the current key
+    Verified,
+    /// Well-formed, but the payload changed after it was exported
+    Modified,
+    /// Plain base64 JSON from before exports were signed
+    Unsigned,
+}
+
+/// Outcome of a successful import
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ImportReport {
+    pub integrity: Integrity,
+    pub load: MigrationReport,
+}
+
+/// How large the current game is in each export format, in bytes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExportSizeReport {
+    pub json_bytes: usize,
+    pub json_code_length: usize,
+    /// Binary-packed state before compression
+    pub packed_bytes: usize,
+    pub compressed_bytes: usize,
+    pub compact_code_length: usize,
+}
+
+fn signature(key: &[u8], signed: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(signed.as_bytes());
+    mac
+}
+
+/// Export code `<header>.<base64 payload>.<base64 HMAC-SHA256>`, the HMAC
+/// covering the header and payload
+pub fn encode(format: SaveFormat, payload: &[u8], key: &[u8]) -> String {
+    let signed = format!(
+        "{}.{}",
+        format.header(),
+        general_purpose::STANDARD.encode(payload)
+    );
+    let mac = signature(key, &signed).finalize().into_bytes();
+    format!("{}.{}", signed, general_purpose::STANDARD.encode(mac))
+}
+
+/// Payload of an export code, its format and whether it is intact; plain
+/// base64 JSON codes from older versions are accepted as `Unsigned`
+pub fn decode(code: &str, key: &[u8]) -> Result<(SaveFormat, Vec<u8>, Integrity), SaveError> {
+    let code = code.trim();
+    let parts: Vec<&str> = code.split('.').collect();
+    match parts.as_slice() {
+        [_] => Ok((SaveFormat::Json, decode_base64(code)?, Integrity::Unsigned)),
+        [header, payload, mac] => {
+            let format = SaveFormat::from_header(header)
+                .ok_or_else(|| SaveError::Corrupted(format!("unknown export format {}", header)))?;
+            let bytes = decode_base64(payload)?;
+            let mac = general_purpose::STANDARD
+                .decode(mac)
+                .map_err(|_| SaveError::Corrupted("checksum is not base64".to_string()))?;
+            let signed = format!("{}.{}", header, payload);
+            let integrity = match signature(key, &signed).verify_slice(&mac) {
+                Ok(()) => Integrity::Verified,
+                Err(_) => Integrity::Modified,
+            };
+            Ok((format, bytes, integrity))
+        }
+        _ => Err(SaveError::Corrupted("malformed export code".to_string())),
+    }
+}
+
+fn decode_base64(payload: &str) -> Result<Vec<u8>, SaveError> {
+    general_purpose::STANDARD
+        .decode(payload)
+        .map_err(|e| SaveError::Corrupted(format!("BASE64 decode error: {}", e)))
+}
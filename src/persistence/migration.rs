@@ -0,0 +1,201 @@
+use super::{SaveError, SaveProblem};
+use crate::state::resources::{COINS, STONE, WOOD};
+use crate::state::{GameState, ResourceRegistry, Statistics};
+use crate::systems::PrestigeState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Schema version written by `save_game`; bump it and append a step to
+/// `MIGRATIONS` whenever the shape of `SavedGame` changes
+pub const SAVE_VERSION: u32 = 6;
+
+/// One upgrade of raw save JSON from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "moved fixed coins/wood/stone fields into the resource registry",
+        apply: move_fixed_resources,
+    },
+    Migration {
+        from: 1,
+        description: "filled in fields missing from saves made before schema versions",
+        apply: fill_missing_fields,
+    },
+    Migration {
+        from: 2,
+        description: "added prestige progress",
+        apply: add_prestige,
+    },
+    Migration {
+        from: 3,
+        description: "added the prestige upgrade shop",
+        apply: add_prestige_upgrades,
+    },
+    Migration {
+        from: 4,
+        description: "turned recipe inputs and outputs into lists",
+        apply: list_recipe_ingredients,
+    },
+    Migration {
+        from: 5,
+        description: "added the crafting queue",
+        apply: add_crafting_queue,
+    },
+];
+
+/// What loading a save changed to bring it up to date
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Descriptions of the migrations applied, oldest first
+    pub steps: Vec<String>,
+    /// Content entries the save did not know about, as "kind:id"
+    pub content_added: Vec<String>,
+    /// Problems found by validation and repaired
+    pub repaired: Vec<SaveProblem>,
+}
+
+impl MigrationReport {
+    /// True when the save was already current and valid, so nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty() && self.content_added.is_empty() && self.repaired.is_empty()
+    }
+}
+
+/// Schema version of raw save JSON; saves from before `save_version` are
+/// told apart by the shape of their state
+pub fn detect_version(save: &Value) -> u32 {
+    if let Some(version) = save.get("save_version").and_then(Value::as_u64) {
+        return version as u32;
+    }
+    match save.get("state").and_then(|state| state.get("resources")) {
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+/// Upgrade raw save JSON in place to `SAVE_VERSION`
+pub fn migrate(save: &mut Value) -> Result<MigrationReport, SaveError> {
+    let from_version = detect_version(save);
+    if from_version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion {
+            found: from_version,
+            supported: SAVE_VERSION,
+        });
+    }
+    let object = save
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Parse("save is not a JSON object".to_string()))?;
+
+    let mut report = MigrationReport {
+        from_version,
+        to_version: SAVE_VERSION,
+        ..MigrationReport::default()
+    };
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        (migration.apply)(object);
+        object.insert("save_version".to_string(), json!(migration.from + 1));
+        report.steps.push(migration.description.to_string());
+    }
+    Ok(report)
+}
+
+/// 0 → 1: the state had `coins`, `wood`, `stone` and `*_per_second` fields
+fn move_fixed_resources(save: &mut Map<String, Value>) {
+    let Some(state) = save.get_mut("state").and_then(Value::as_object_mut) else {
+        return;
+    };
+    let resources: Vec<Value> = [COINS, WOOD, STONE]
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "name": id,
+                "amount": state.remove(*id).unwrap_or(json!(0.0)),
+                "per_second": state
+                    .remove(&format!("{}_per_second", id))
+                    .unwrap_or(json!(0.0)),
+            })
+        })
+        .collect();
+    state.insert("resources".to_string(), Value::Array(resources));
+}
+
+/// 1 → 2: older builds wrote fewer fields; take the missing ones from a
+/// fresh game, and let content merging fill the empty lists on load
+fn fill_missing_fields(save: &mut Map<String, Value>) {
+    let state = GameState::new(ResourceRegistry::default(), 0.0);
+    fill_object(save, "state", json!(state));
+    fill_object(save, "statistics", json!(Statistics::default()));
+    for list in [
+        "upgrades",
+        "buildings",
+        "workers",
+        "achievements",
+        "crafting_recipes",
+        "unlocked_features",
+    ] {
+        save.entry(list).or_insert_with(|| json!([]));
+    }
+    save.entry("save_timestamp").or_insert(json!(0.0));
+    save.entry("version").or_insert(json!("unknown"));
+}
+
+/// 2 → 3: saves from before prestige start with none
+fn add_prestige(save: &mut Map<String, Value>) {
+    fill_object(save, "prestige", json!(PrestigeState::default()));
+}
+
+/// 3 → 4: content merging fills in the shop with nothing bought
+fn add_prestige_upgrades(save: &mut Map<String, Value>) {
+    save.entry("prestige_upgrades").or_insert_with(|| json!([]));
+}
+
+/// 4 → 5: recipes had a single `input_resource`/`input_amount` and
+/// `output_resource`/`output_amount`
+fn list_recipe_ingredients(save: &mut Map<String, Value>) {
+    let Some(recipes) = save
+        .get_mut("crafting_recipes")
+        .and_then(Value::as_array_mut)
+    else {
+        return;
+    };
+    for recipe in recipes.iter_mut().filter_map(Value::as_object_mut) {
+        for side in ["input", "output"] {
+            let resource = recipe.remove(&format!("{}_resource", side));
+            let amount = recipe.remove(&format!("{}_amount", side));
+            let items = match (resource, amount) {
+                (Some(resource), Some(amount)) => {
+                    json!([{ "resource": resource, "amount": amount }])
+                }
+                _ => json!([]),
+            };
+            recipe.entry(format!("{}s", side)).or_insert(items);
+        }
+    }
+}
+
+/// 5 → 6: crafts completed at once, so nothing is in progress
+fn add_crafting_queue(save: &mut Map<String, Value>) {
+    save.entry("crafting_queue").or_insert_with(|| json!([]));
+}
+
+/// Ensure `save[key]` is an object holding at least every field of `defaults`
+fn fill_object(save: &mut Map<String, Value>, key: &str, defaults: Value) {
+    let Value::Object(defaults) = defaults else {
+        return;
+    };
+    let target = save.entry(key).or_insert_with(|| json!({}));
+    if let Some(target) = target.as_object_mut() {
+        for (field, value) in defaults {
+            target.entry(field).or_insert(value);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+pub mod compact;
+pub mod export;
+pub mod migration;
+pub mod slots;
+pub mod storage;
+pub mod validation;
+
+pub use export::{ExportSizeReport, ImportReport, Integrity, SaveFormat};
+pub use migration::{MigrationReport, SAVE_VERSION};
+pub use slots::{SlotIndex, SlotKind, SlotMetadata};
+pub use storage::{MemoryStorage, SaveStorage};
+pub use validation::SaveProblem;
+
+use crate::entities::{Building, Upgrade, Worker};
+use crate::state::{GameState, Statistics};
+use crate::systems::{Achievement, CraftingJob, CraftingRecipe, PrestigeState, UnlockedFeature};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Complete game save data structure for persistence
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedGame {
+    /// Schema version of this save, see `SAVE_VERSION`
+    pub save_version: u32,
+    pub state: GameState,
+    pub statistics: Statistics,
+    pub upgrades: Vec<Upgrade>,
+    pub buildings: Vec<Building>,
+    pub workers: Vec<Worker>,
+    pub achievements: Vec<Achievement>,
+    pub crafting_recipes: Vec<CraftingRecipe>,
+    /// Crafts in progress, their inputs already spent
+    pub crafting_queue: Vec<CraftingJob>,
+    pub unlocked_features: Vec<UnlockedFeature>,
+    pub prestige: PrestigeState,
+    pub prestige_upgrades: Vec<Upgrade>,
+    pub save_timestamp: f64,
+    /// Game version that wrote the save, for information only
+    pub version: String,
+}
+
+/// Why a save could not be read
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
+pub enum SaveError {
+    Parse(String),
+    /// Written by a newer game whose schema this build does not know
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+    /// An export code whose structure or encoding is broken
+    Corrupted(String),
+    /// An export code changed since it was exported, rejected in
+    /// `LoadMode::Strict`; only catches edits made without the signing key
+    Modified,
+    /// An unsigned (legacy) export code, rejected in `LoadMode::Strict` since
+    /// nothing vouches for its contents
+    Unsigned,
+    /// Failed validation while loading in `LoadMode::Strict`
+    Invalid {
+        problems: Vec<SaveProblem>,
+    },
+    /// The storage backend failed, or holds nothing under the requested slot
+    Storage(String),
+}
+
+/// How `GameCore::load_json` treats a save that fails validation
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LoadMode {
+    /// Fix what can be fixed and report it
+    #[default]
+    Repair,
+    /// Reject the save with `SaveError::Invalid`
+    Strict,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Parse(message) => write!(f, "Deserialization error: {}", message),
+            SaveError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Save version {} is newer than the supported version {}",
+                found, supported
+            ),
+            SaveError::Corrupted(message) => write!(f, "Corrupted save: {}", message),
+            SaveError::Modified => write!(f, "Save was modified after export"),
+            SaveError::Unsigned => write!(f, "Save code is not signed"),
+            SaveError::Invalid { problems } => {
+                write!(
+                    f,
+                    "Save failed validation with {} problem(s)",
+                    problems.len()
+                )
+            }
+            SaveError::Storage(message) => write!(f, "Storage error: {}", message),
+        }
+    }
+}
+
+impl SavedGame {
+    /// Parse a save from JSON, migrating it from older schema versions
+    pub fn from_json(json: &str) -> Result<SavedGame, SaveError> {
+        SavedGame::from_json_with_report(json).map(|(saved, _)| saved)
+    }
+
+    /// Like `from_json`, also reporting which migrations were applied
+    pub fn from_json_with_report(json: &str) -> Result<(SavedGame, MigrationReport), SaveError> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| SaveError::Parse(e.to_string()))?;
+        let report = migration::migrate(&mut value)?;
+        let saved = serde_json::from_value(value).map_err(|e| SaveError::Parse(e.to_string()))?;
+        Ok((saved, report))
+    }
+}
@@ -0,0 +1,220 @@
+use super::{SaveError, SaveStorage, SavedGame};
+use crate::state::resources::COINS;
+use crate::state::BigNum;
+use serde::{Deserialize, Serialize};
+
+/// Storage key of the game the player is currently playing
+pub const CURRENT_SAVE_KEY: &str = "idle_game_save";
+/// Storage key of the `SlotIndex`
+pub const SLOT_INDEX_KEY: &str = "idle_game_slots";
+/// Prefix of the storage key of each slot
+const SLOT_KEY_PREFIX: &str = "idle_game_slot:";
+
+/// Autosave backups kept before the oldest is deleted
+pub const MAX_BACKUPS: usize = 5;
+/// Shortest time between two autosave backups, in milliseconds
+pub const BACKUP_INTERVAL_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+/// Storage key holding the save in slot `name`
+pub fn slot_key(name: &str) -> String {
+    format!("{}{}", SLOT_KEY_PREFIX, name)
+}
+
+/// How a slot was written
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotKind {
+    /// Saved by the player under a name of their choosing
+    Manual,
+    /// Written automatically and rotated out after `MAX_BACKUPS`
+    Backup,
+}
+
+/// What the slot list shows about a save without loading it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SlotMetadata {
+    pub name: String,
+    pub kind: SlotKind,
+    /// Clock time the save was written, in milliseconds
+    pub saved_at: f64,
+    pub play_time_seconds: f64,
+    pub coins: BigNum,
+    pub save_version: u32,
+    /// Game version that wrote the save
+    pub game_version: String,
+}
+
+impl SlotMetadata {
+    pub fn describe(name: &str, kind: SlotKind, saved: &SavedGame) -> SlotMetadata {
+        SlotMetadata {
+            name: name.to_string(),
+            kind,
+            saved_at: saved.save_timestamp,
+            play_time_seconds: saved.statistics.play_time_seconds,
+            coins: saved.state.resources.amount(COINS),
+            save_version: saved.save_version,
+            game_version: saved.version.clone(),
+        }
+    }
+}
+
+/// Every slot in storage, manual slots by name and backups oldest first
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SlotIndex {
+    pub slots: Vec<SlotMetadata>,
+}
+
+impl SlotIndex {
+    /// Parse a stored index; a missing or unreadable one starts empty, as the
+    /// slots themselves are still in storage under their own keys
+    pub fn from_json(json: Option<&str>) -> SlotIndex {
+        json.and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("slot index always serializes")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SlotMetadata> {
+        self.slots.iter().find(|slot| slot.name == name)
+    }
+
+    /// Add `metadata`, replacing any slot with the same name
+    pub fn record(&mut self, metadata: SlotMetadata) {
+        match self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.name == metadata.name)
+        {
+            Some(slot) => *slot = metadata,
+            None => self.slots.push(metadata),
+        }
+    }
+
+    /// Forget slot `name`; false if there was none
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.slots.len();
+        self.slots.retain(|slot| slot.name != name);
+        self.slots.len() != before
+    }
+
+    /// Backups, oldest first
+    pub fn backups(&self) -> impl Iterator<Item = &SlotMetadata> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.kind == SlotKind::Backup)
+    }
+
+    /// Whether an autosave at `now` should also be kept as a backup
+    pub fn backup_due(&self, now: f64) -> bool {
+        self.backups()
+            .last()
+            .is_none_or(|latest| now - latest.saved_at >= BACKUP_INTERVAL_MS)
+    }
+
+    /// Record a new backup and drop the oldest beyond `MAX_BACKUPS`; returns
+    /// the names of the dropped backups so their saves can be deleted
+    pub fn push_backup(&mut self, metadata: SlotMetadata) -> Vec<String> {
+        self.record(SlotMetadata {
+            kind: SlotKind::Backup,
+            ..metadata
+        });
+        let excess = self.backups().count().saturating_sub(MAX_BACKUPS);
+        let dropped: Vec<String> = self
+            .backups()
+            .take(excess)
+            .map(|slot| slot.name.clone())
+            .collect();
+        for name in &dropped {
+            self.remove(name);
+        }
+        dropped
+    }
+}
+
+/// Name of the backup taken at `now`
+pub fn backup_name(now: f64) -> String {
+    format!("backup-{}", now.max(0.0) as u64)
+}
+
+fn to_json(saved: &SavedGame) -> String {
+    serde_json::to_string(saved).expect("saves always serialize")
+}
+
+fn read_index(storage: &dyn SaveStorage) -> Result<SlotIndex, SaveError> {
+    Ok(SlotIndex::from_json(
+        storage.read(SLOT_INDEX_KEY)?.as_deref(),
+    ))
+}
+
+/// Metadata of every slot and backup in `storage`
+pub fn list(storage: &dyn SaveStorage) -> Result<Vec<SlotMetadata>, SaveError> {
+    Ok(read_index(storage)?.slots)
+}
+
+/// JSON of the current save, if there is one
+pub fn read_current(storage: &dyn SaveStorage) -> Result<Option<String>, SaveError> {
+    storage.read(CURRENT_SAVE_KEY)
+}
+
+/// Write `saved` as the current save, also keeping it as a backup when the
+/// last one is older than `BACKUP_INTERVAL_MS`
+pub fn write_current(storage: &mut dyn SaveStorage, saved: &SavedGame) -> Result<(), SaveError> {
+    storage.write(CURRENT_SAVE_KEY, &to_json(saved))?;
+    if read_index(storage)?.backup_due(saved.save_timestamp) {
+        write_backup(storage, saved)?;
+    }
+    Ok(())
+}
+
+/// Store `saved` as a new backup, deleting the backups rotated out
+pub fn write_backup(
+    storage: &mut dyn SaveStorage,
+    saved: &SavedGame,
+) -> Result<SlotMetadata, SaveError> {
+    let name = backup_name(saved.save_timestamp);
+    storage.write(&slot_key(&name), &to_json(saved))?;
+    let metadata = SlotMetadata::describe(&name, SlotKind::Backup, saved);
+    let mut index = read_index(storage)?;
+    for dropped in index.push_backup(metadata.clone()) {
+        storage.remove(&slot_key(&dropped))?;
+    }
+    storage.write(SLOT_INDEX_KEY, &index.to_json())?;
+    Ok(metadata)
+}
+
+/// Save `saved` to the manual slot `name`, replacing what it held
+pub fn write_slot(
+    storage: &mut dyn SaveStorage,
+    name: &str,
+    saved: &SavedGame,
+) -> Result<SlotMetadata, SaveError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(SaveError::Storage("slot name is empty".to_string()));
+    }
+    storage.write(&slot_key(name), &to_json(saved))?;
+    let metadata = SlotMetadata::describe(name, SlotKind::Manual, saved);
+    let mut index = read_index(storage)?;
+    index.record(metadata.clone());
+    storage.write(SLOT_INDEX_KEY, &index.to_json())?;
+    Ok(metadata)
+}
+
+/// JSON of the save in slot or backup `name`
+pub fn read_slot(storage: &dyn SaveStorage, name: &str) -> Result<String, SaveError> {
+    storage
+        .read(&slot_key(name))?
+        .ok_or_else(|| SaveError::Storage(format!("no save in slot {}", name)))
+}
+
+/// Delete slot or backup `name`; false if there was no such slot
+pub fn delete_slot(storage: &mut dyn SaveStorage, name: &str) -> Result<bool, SaveError> {
+    let existed = storage.read(&slot_key(name))?.is_some();
+    storage.remove(&slot_key(name))?;
+    let mut index = read_index(storage)?;
+    let listed = index.remove(name);
+    storage.write(SLOT_INDEX_KEY, &index.to_json())?;
+    Ok(existed || listed)
+}
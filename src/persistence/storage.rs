@@ -0,0 +1,175 @@
+use super::SaveError;
+use std::collections::HashMap;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Key-value store that saves, slots and backups are written to
+pub trait SaveStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, SaveError>;
+    fn write(&mut self, key: &str, value: &str) -> Result<(), SaveError>;
+    /// Removing a missing key is not an error
+    fn remove(&mut self, key: &str) -> Result<(), SaveError>;
+}
+
+/// Storage that lives as long as the value, for tests and native tools
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    items: HashMap<String, String>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.items.keys()
+    }
+}
+
+impl SaveStorage for MemoryStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, SaveError> {
+        Ok(self.items.get(key).cloned())
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), SaveError> {
+        self.items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), SaveError> {
+        self.items.remove(key);
+        Ok(())
+    }
+}
+
+/// The browser's `window.localStorage`
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    pub fn open() -> Result<LocalStorage, SaveError> {
+        let window = web_sys::window()
+            .ok_or_else(|| SaveError::Storage("Window not available".to_string()))?;
+        let storage = window
+            .local_storage()
+            .map_err(|e| SaveError::Storage(format!("localStorage access error: {:?}", e)))?
+            .ok_or_else(|| SaveError::Storage("localStorage not available".to_string()))?;
+        Ok(LocalStorage { storage })
+    }
+}
+
+impl SaveStorage for LocalStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, SaveError> {
+        self.storage
+            .get_item(key)
+            .map_err(|e| SaveError::Storage(format!("localStorage get error: {:?}", e)))
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), SaveError> {
+        self.storage
+            .set_item(key, value)
+            .map_err(|e| SaveError::Storage(format!("localStorage set error: {:?}", e)))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), SaveError> {
+        self.storage
+            .remove_item(key)
+            .map_err(|e| SaveError::Storage(format!("localStorage remove error: {:?}", e)))
+    }
+}
+
+/// Any JS object with synchronous `getItem`, `setItem` and `removeItem`
+/// methods, e.g. a cache in front of IndexedDB or a server
+pub struct JsObjectStorage {
+    backend: JsValue,
+}
+
+impl JsObjectStorage {
+    pub fn new(backend: JsValue) -> JsObjectStorage {
+        JsObjectStorage { backend }
+    }
+
+    fn call(&self, method: &str, args: &[&str]) -> Result<JsValue, SaveError> {
+        let function = js_sys::Reflect::get(&self.backend, &JsValue::from_str(method))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+            .ok_or_else(|| SaveError::Storage(format!("storage backend has no {}", method)))?;
+        let args: js_sys::Array = args.iter().map(|arg| JsValue::from_str(arg)).collect();
+        function
+            .apply(&self.backend, &args)
+            .map_err(|e| SaveError::Storage(format!("storage backend {} error: {:?}", method, e)))
+    }
+}
+
+impl SaveStorage for JsObjectStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, SaveError> {
+        Ok(self.call("getItem", &[key])?.as_string())
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), SaveError> {
+        self.call("setItem", &[key, value]).map(|_| ())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), SaveError> {
+        self.call("removeItem", &[key]).map(|_| ())
+    }
+}
+
+/// One file per key in a directory, for native builds
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    /// Store files in `dir`, creating it if needed
+    pub fn open(dir: impl Into<std::path::PathBuf>) -> Result<FileStorage, SaveError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| SaveError::Storage(format!("cannot create {}: {}", dir.display(), e)))?;
+        Ok(FileStorage { dir })
+    }
+
+    /// File name for `key`, with anything but letters, digits, `-` and `_`
+    /// escaped so every key maps to a distinct, portable name
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        let mut name = String::new();
+        for byte in key.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => name.push(byte as char),
+                _ => name.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, SaveError> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SaveError::Storage(format!("cannot read {}: {}", key, e))),
+        }
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), SaveError> {
+        // Write beside the target and rename, so a crash never leaves half a save
+        let path = self.path(key);
+        let partial = path.with_extension("json.tmp");
+        std::fs::write(&partial, value)
+            .and_then(|_| std::fs::rename(&partial, &path))
+            .map_err(|e| SaveError::Storage(format!("cannot write {}: {}", key, e)))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), SaveError> {
+        match std::fs::remove_file(self.path(key)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(SaveError::Storage(format!("cannot remove {}: {}", key, e))),
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use super::SavedGame;
+use crate::content::GameContent;
+use crate::state::BigNum;
+use crate::systems::effects::Modifiers;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Relative difference tolerated between a saved cost and the one implied
+/// by the owned count, to absorb rounding from repeated multiplication
+const COST_TOLERANCE: f64 = 1e-6;
+
+/// Something in a save that a game could not have produced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum SaveProblem {
+    /// A resource amount or earned total below zero; repaired to zero
+    NegativeAmount { field: String, amount: BigNum },
+    /// A number that is not finite or out of range; repaired to its default
+    InvalidValue { field: String, value: f64 },
+    /// A worker assigned to a building that does not exist; unassigned
+    DanglingAssignment { worker: String, building: String },
+    /// A cost that does not follow from the number owned; recomputed
+    CostMismatch {
+        kind: String,
+        id: String,
+        expected: BigNum,
+        found: BigNum,
+    },
+    /// The same id twice in one list; later copies are dropped
+    DuplicateId { kind: String, id: String },
+    /// More levels of an upgrade than it has; capped and repriced
+    AboveMaxLevel {
+        kind: String,
+        id: String,
+        owned: u32,
+        max_level: u32,
+    },
+    /// A worker assigned beyond the worker slots owned; unassigned
+    ExcessAssignment { worker: String, building: String },
+}
+
+/// Every problem in `saved`, leaving it untouched
+pub fn validate(saved: &SavedGame, content: &GameContent) -> Vec<SaveProblem> {
+    inspect(&mut saved.clone(), content)
+}
+
+/// Fix every problem in `saved` and return what was fixed; rates are left
+/// for `update_production` to recompute
+pub fn repair(saved: &mut SavedGame, content: &GameContent) -> Vec<SaveProblem> {
+    inspect(saved, content)
+}
+
+fn inspect(saved: &mut SavedGame, content: &GameContent) -> Vec<SaveProblem> {
+    let mut problems = Vec::new();
+
+    let mut seen = HashSet::new();
+    saved.state.resources.retain(|resource| {
+        if seen.insert(resource.id.clone()) {
+            return true;
+        }
+        problems.push(SaveProblem::DuplicateId {
+            kind: "resource".to_string(),
+            id: resource.id.clone(),
+        });
+        false
+    });
+    dedup(&mut saved.buildings, "building", |b| &b.id, &mut problems);
+    dedup(&mut saved.upgrades, "upgrade", |u| &u.id, &mut problems);
+    dedup(&mut saved.workers, "worker", |w| &w.id, &mut problems);
+    dedup(
+        &mut saved.achievements,
+        "achievement",
+        |a| &a.id,
+        &mut problems,
+    );
+    dedup(
+        &mut saved.crafting_recipes,
+        "recipe",
+        |r| &r.id,
+        &mut problems,
+    );
+    dedup(
+        &mut saved.unlocked_features,
+        "unlock",
+        |u| &u.id,
+        &mut problems,
+    );
+    dedup(
+        &mut saved.prestige_upgrades,
+        "prestige_upgrade",
+        |u| &u.id,
+        &mut problems,
+    );
+
+    for resource in saved.state.resources.iter_mut() {
+        let field = format!("state.resources.{}", resource.id);
+        clamp_amount(
+            &mut resource.amount,
+            &format!("{}.amount", field),
+            &mut problems,
+        );
+        clamp_amount(
+            &mut resource.lifetime_earned,
+            &format!("{}.lifetime_earned", field),
+            &mut problems,
+        );
+        // Net rates may be negative when buildings consume more than is made
+        if !resource.per_second.is_finite() {
+            problems.push(SaveProblem::InvalidValue {
+                field: format!("{}.per_second", field),
+                value: resource.per_second,
+            });
+            resource.per_second = 0.0;
+        }
+    }
+    let state = &mut saved.state;
+    check_number(
+        &mut state.coins_per_click,
+        "state.coins_per_click",
+        1.0,
+        &mut problems,
+    );
+    check_number(
+        &mut state.last_update_time,
+        "state.last_update_time",
+        0.0,
+        &mut problems,
+    );
+
+    let statistics = &mut saved.statistics;
+    clamp_amount(
+        &mut statistics.total_coins_earned,
+        "statistics.total_coins_earned",
+        &mut problems,
+    );
+    clamp_amount(
+        &mut statistics.total_wood_earned,
+        "statistics.total_wood_earned",
+        &mut problems,
+    );
+    clamp_amount(
+        &mut statistics.total_stone_earned,
+        "statistics.total_stone_earned",
+        &mut problems,
+    );
+    for (resource, amount) in statistics.total_earned.iter_mut() {
+        clamp_amount(
+            amount,
+            &format!("statistics.total_earned.{}", resource),
+            &mut problems,
+        );
+    }
+    check_number(
+        &mut statistics.play_time_seconds,
+        "statistics.play_time_seconds",
+        0.0,
+        &mut problems,
+    );
+
+    clamp_amount(&mut saved.prestige.points, "prestige.points", &mut problems);
+    clamp_amount(
+        &mut saved.prestige.total_points_earned,
+        "prestige.total_points_earned",
+        &mut problems,
+    );
+
+    for building in &mut saved.buildings {
+        let Some(def) = content.building(&building.id) else {
+            continue;
+        };
+        let expected = BigNum::from_f64(def.base_cost)
+            * BigNum::from_f64(def.cost_multiplier).powi(building.count);
+        check_cost(
+            &mut building.cost,
+            expected,
+            "building",
+            &building.id,
+            &mut problems,
+        );
+    }
+    for upgrade in &mut saved.upgrades {
+        let Some(def) = content.upgrade(&upgrade.id) else {
+            continue;
+        };
+        check_max_level(
+            &mut upgrade.owned,
+            def.max_level,
+            "upgrade",
+            &upgrade.id,
+            &mut problems,
+        );
+        let expected = BigNum::from_f64(def.base_cost)
+            * BigNum::from_f64(def.cost_multiplier).powi(upgrade.owned);
+        check_cost(
+            &mut upgrade.cost,
+            expected,
+            "upgrade",
+            &upgrade.id,
+            &mut problems,
+        );
+    }
+    for upgrade in &mut saved.prestige_upgrades {
+        let Some(def) = content.prestige_upgrade(&upgrade.id) else {
+            continue;
+        };
+        check_max_level(
+            &mut upgrade.owned,
+            def.max_level,
+            "prestige_upgrade",
+            &upgrade.id,
+            &mut problems,
+        );
+        let expected = BigNum::from_f64(def.base_cost)
+            * BigNum::from_f64(def.cost_multiplier).powi(upgrade.owned);
+        check_cost(
+            &mut upgrade.cost,
+            expected,
+            "prestige_upgrade",
+            &upgrade.id,
+            &mut problems,
+        );
+    }
+
+    // Assignments may still use building names from older saves
+    let known_buildings: HashSet<&str> = saved
+        .buildings
+        .iter()
+        .flat_map(|b| [b.id.as_str(), b.name.as_str()])
+        .chain(content.buildings.iter().map(|b| b.id.as_str()))
+        .collect();
+    for worker in &mut saved.workers {
+        let field = format!("workers.{}", worker.id);
+        check_number(&mut worker.xp, &format!("{}.xp", field), 0.0, &mut problems);
+        check_number(
+            &mut worker.xp_to_next_level,
+            &format!("{}.xp_to_next_level", field),
+            100.0,
+            &mut problems,
+        );
+        check_number(
+            &mut worker.efficiency_multiplier,
+            &format!("{}.efficiency_multiplier", field),
+            1.0,
+            &mut problems,
+        );
+        if let Some(building) = &worker.assigned_building {
+            if !known_buildings.contains(building.as_str()) {
+                problems.push(SaveProblem::DanglingAssignment {
+                    worker: worker.id.clone(),
+                    building: building.clone(),
+                });
+                worker.assigned_building = None;
+            }
+        }
+    }
+
+    // Slots follow from the levels checked above, first assigned first kept
+    let slots = worker_slots(saved, content) as usize;
+    for worker in saved
+        .workers
+        .iter_mut()
+        .filter(|w| w.assigned_building.is_some())
+        .skip(slots)
+    {
+        problems.push(SaveProblem::ExcessAssignment {
+            worker: worker.id.clone(),
+            building: worker.assigned_building.take().unwrap_or_default(),
+        });
+    }
+
+    for building in &mut saved.buildings {
+        if let Some(converter) = &mut building.converter {
+            let field = format!("buildings.{}.converter.progress", building.id);
+            check_fraction(&mut converter.progress, &field, &mut problems);
+        }
+    }
+
+    for (index, job) in saved.crafting_queue.iter_mut().enumerate() {
+        let field = format!("crafting_queue.{}", index);
+        check_number(
+            &mut job.duration,
+            &format!("{}.duration", field),
+            0.0,
+            &mut problems,
+        );
+        check_number(
+            &mut job.progress,
+            &format!("{}.progress", field),
+            0.0,
+            &mut problems,
+        );
+    }
+
+    problems
+}
+
+/// Negative amounts become zero
+fn clamp_amount(amount: &mut BigNum, field: &str, problems: &mut Vec<SaveProblem>) {
+    if amount.is_negative() {
+        problems.push(SaveProblem::NegativeAmount {
+            field: field.to_string(),
+            amount: *amount,
+        });
+        *amount = BigNum::ZERO;
+    }
+}
+
+/// Non-finite or negative values go back to `default`
+fn check_number(value: &mut f64, field: &str, default: f64, problems: &mut Vec<SaveProblem>) {
+    if !value.is_finite() || *value < 0.0 {
+        problems.push(SaveProblem::InvalidValue {
+            field: field.to_string(),
+            value: *value,
+        });
+        *value = default;
+    }
+}
+
+/// Progress towards the next whole craft must lie in `[0, 1)`; anything else
+/// starts over from zero
+fn check_fraction(value: &mut f64, field: &str, problems: &mut Vec<SaveProblem>) {
+    if !(0.0..1.0).contains(value) {
+        problems.push(SaveProblem::InvalidValue {
+            field: field.to_string(),
+            value: *value,
+        });
+        *value = 0.0;
+    }
+}
+
+/// Levels beyond `max_level` go back to the cap
+fn check_max_level(
+    owned: &mut u32,
+    max_level: Option<u32>,
+    kind: &str,
+    id: &str,
+    problems: &mut Vec<SaveProblem>,
+) {
+    if let Some(max_level) = max_level.filter(|max| *owned > *max) {
+        problems.push(SaveProblem::AboveMaxLevel {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            owned: *owned,
+            max_level,
+        });
+        *owned = max_level;
+    }
+}
+
+/// Worker slots the saved upgrades and achievements grant, with effects
+/// taken from content rather than the save
+fn worker_slots(saved: &SavedGame, content: &GameContent) -> u32 {
+    let mut modifiers = Modifiers::default();
+    for upgrade in &saved.upgrades {
+        if let Some(def) = content.upgrade(&upgrade.id) {
+            for effect in &def.effects {
+                modifiers.apply(effect, upgrade.owned);
+            }
+        }
+    }
+    for upgrade in &saved.prestige_upgrades {
+        if let Some(def) = content.prestige_upgrade(&upgrade.id) {
+            for effect in &def.effects {
+                modifiers.apply(effect, upgrade.owned);
+            }
+        }
+    }
+    for achievement in saved.achievements.iter().filter(|a| a.unlocked) {
+        if let Some(def) = content.achievements.iter().find(|d| d.id == achievement.id) {
+            for effect in &def.effects {
+                modifiers.apply(effect, 1);
+            }
+        }
+    }
+    modifiers.worker_slots()
+}
+
+fn check_cost(
+    cost: &mut BigNum,
+    expected: BigNum,
+    kind: &str,
+    id: &str,
+    problems: &mut Vec<SaveProblem>,
+) {
+    if (*cost - expected).abs() > expected * COST_TOLERANCE {
+        problems.push(SaveProblem::CostMismatch {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            expected,
+            found: *cost,
+        });
+        *cost = expected;
+    }
+}
+
+/// Drop every entry whose non-empty id already appeared earlier in `list`
+fn dedup<T>(
+    list: &mut Vec<T>,
+    kind: &str,
+    id: impl Fn(&T) -> &String,
+    problems: &mut Vec<SaveProblem>,
+) {
+    let mut seen = HashSet::new();
+    list.retain(|entry| {
+        let id = id(entry);
+        if id.is_empty() || seen.insert(id.clone()) {
+            return true;
+        }
+        problems.push(SaveProblem::DuplicateId {
+            kind: kind.to_string(),
+            id: id.clone(),
+        });
+        false
+    });
+}
@@ -0,0 +1,524 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Magnitudes below this are held as a plain `f64`
+const PLAIN_LIMIT: f64 = 1e100;
+const PLAIN_LIMIT_EXPONENT: i64 = 100;
+/// Beyond this many orders of magnitude the smaller addend cannot change the sum
+const SIGNIFICANT_DIGITS: i64 = 17;
+
+const SUFFIXES: [&str; 12] = [
+    "", "K", "M", "B", "T", "Qa", "Qi", "Sx", "Sp", "Oc", "No", "Dc",
+];
+
+/// Number that keeps growing where `f64` would overflow to infinity.
+///
+/// Values below 1e100 are stored as a plain `f64` (exponent 0), so everyday
+/// arithmetic gives exactly the same results as `f64`; larger values are a
+/// mantissa in [1, 10) times a power of ten.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BigNum {
+    mantissa: f64,
+    exponent: i64,
+}
+
+/// How `BigNum::format` writes large values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Notation {
+    /// 1.23K, 45.6M, 789B, ...; scientific past the last suffix
+    Short,
+    /// 1.23e45
+    Scientific,
+    /// 123e42, exponent a multiple of three
+    Engineering,
+}
+
+impl Notation {
+    /// Parse a notation name from the frontend; anything unknown is `Short`
+    pub fn parse(name: &str) -> Notation {
+        match name {
+            "scientific" => Notation::Scientific,
+            "engineering" => Notation::Engineering,
+            _ => Notation::Short,
+        }
+    }
+}
+
+fn pow10(exponent: i64) -> f64 {
+    10f64.powi(exponent.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+}
+
+impl BigNum {
+    pub const ZERO: BigNum = BigNum {
+        mantissa: 0.0,
+        exponent: 0,
+    };
+
+    /// `mantissa` × 10^`exponent`, in canonical form
+    pub fn new(mantissa: f64, exponent: i64) -> BigNum {
+        if mantissa.is_nan() || mantissa == 0.0 {
+            return BigNum::ZERO;
+        }
+        if mantissa.is_infinite() {
+            return BigNum::new(f64::MAX.copysign(mantissa), exponent);
+        }
+
+        let digits = mantissa.abs().log10().floor() as i64;
+        let total = exponent.saturating_add(digits);
+        if total < PLAIN_LIMIT_EXPONENT {
+            let plain = if exponent == 0 {
+                mantissa
+            } else {
+                mantissa * pow10(exponent)
+            };
+            if plain.abs() < PLAIN_LIMIT {
+                return BigNum {
+                    mantissa: plain,
+                    exponent: 0,
+                };
+            }
+        }
+
+        // Two steps so neither power of ten over- or underflows
+        let half = digits / 2;
+        let mut mantissa = mantissa / pow10(half) / pow10(digits - half);
+        let mut exponent = total;
+        if mantissa.abs() >= 10.0 {
+            mantissa /= 10.0;
+            exponent += 1;
+        } else if mantissa.abs() < 1.0 {
+            mantissa *= 10.0;
+            exponent -= 1;
+        }
+        if exponent < PLAIN_LIMIT_EXPONENT {
+            return BigNum {
+                mantissa: mantissa * pow10(exponent),
+                exponent: 0,
+            };
+        }
+        BigNum { mantissa, exponent }
+    }
+
+    pub fn from_f64(value: f64) -> BigNum {
+        BigNum::new(value, 0)
+    }
+
+    /// Closest `f64`, saturating at `f64::MAX` instead of overflowing
+    pub fn to_f64(self) -> f64 {
+        if self.exponent == 0 {
+            return self.mantissa;
+        }
+        let value = self.mantissa * pow10(self.exponent);
+        if value.is_finite() {
+            value
+        } else {
+            f64::MAX.copysign(self.mantissa)
+        }
+    }
+
+    /// Stored mantissa and exponent; `BigNum::new` turns them back into the
+    /// same value
+    pub fn parts(self) -> (f64, i64) {
+        (self.mantissa, self.exponent)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.mantissa == 0.0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.mantissa < 0.0
+    }
+
+    pub fn abs(self) -> BigNum {
+        BigNum {
+            mantissa: self.mantissa.abs(),
+            exponent: self.exponent,
+        }
+    }
+
+    /// Round down to a whole number; large values already are whole
+    pub fn floor(self) -> BigNum {
+        if self.exponent == 0 {
+            BigNum::from_f64(self.mantissa.floor())
+        } else {
+            self
+        }
+    }
+
+    pub fn max(self, other: BigNum) -> BigNum {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    pub fn min(self, other: BigNum) -> BigNum {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// `self` raised to a whole power, e.g. a base cost after `n` price increases
+    pub fn powi(self, n: u32) -> BigNum {
+        if self.exponent == 0 {
+            let plain = self.mantissa.powi(n as i32);
+            if plain.is_finite() && plain.abs() < PLAIN_LIMIT {
+                return BigNum::from_f64(plain);
+            }
+        }
+        if self.is_zero() {
+            return BigNum::ZERO;
+        }
+
+        let (mantissa, exponent) = self.scientific();
+        let log10 = n as f64 * (mantissa.abs().log10() + exponent as f64);
+        let sign = if mantissa < 0.0 && n % 2 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        BigNum::new(sign * 10f64.powf(log10.fract()), log10.trunc() as i64)
+    }
+
+    /// Square root; negative values have none and give zero
+    pub fn sqrt(self) -> BigNum {
+        if self.is_zero() || self.is_negative() {
+            return BigNum::ZERO;
+        }
+        if self.exponent == 0 {
+            return BigNum::from_f64(self.mantissa.sqrt());
+        }
+        if self.exponent % 2 == 0 {
+            BigNum::new(self.mantissa.sqrt(), self.exponent / 2)
+        } else {
+            BigNum::new((self.mantissa * 10.0).sqrt(), (self.exponent - 1) / 2)
+        }
+    }
+
+    /// Mantissa in [1, 10) and exponent, whatever the magnitude
+    fn scientific(self) -> (f64, i64) {
+        if self.exponent != 0 || self.mantissa == 0.0 {
+            return (self.mantissa, self.exponent);
+        }
+        let mut exponent = self.mantissa.abs().log10().floor() as i64;
+        let mut mantissa = self.mantissa / pow10(exponent);
+        if mantissa.abs() >= 10.0 {
+            mantissa /= 10.0;
+            exponent += 1;
+        } else if mantissa.abs() < 1.0 {
+            mantissa *= 10.0;
+            exponent -= 1;
+        }
+        (mantissa, exponent)
+    }
+
+    fn sign(self) -> i8 {
+        if self.mantissa > 0.0 {
+            1
+        } else if self.mantissa < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// Human-readable form; values under 1000 are written out with at most
+    /// two decimals in every notation
+    pub fn format(self, notation: Notation) -> String {
+        if self.is_negative() {
+            return format!("-{}", self.abs().format(notation));
+        }
+
+        let (mantissa, exponent) = self.scientific();
+        if exponent < 3 {
+            let text = format!("{:.2}", self.to_f64());
+            return text.trim_end_matches('0').trim_end_matches('.').to_string();
+        }
+
+        // Round to three significant digits first so 9.999e5 becomes 1.00e6
+        let mut mantissa = (mantissa * 100.0).round() / 100.0;
+        let mut exponent = exponent;
+        if mantissa >= 10.0 {
+            mantissa /= 10.0;
+            exponent += 1;
+        }
+
+        let grouped = |exponent: i64| {
+            let group = exponent - exponent.rem_euclid(3);
+            let scaled = mantissa * pow10(exponent - group);
+            let decimals = (2 - exponent.rem_euclid(3)) as usize;
+            (format!("{:.*}", decimals, scaled), group)
+        };
+
+        match notation {
+            Notation::Short if ((exponent / 3) as usize) < SUFFIXES.len() => {
+                let (scaled, group) = grouped(exponent);
+                format!("{}{}", scaled, SUFFIXES[(group / 3) as usize])
+            }
+            Notation::Short | Notation::Scientific => format!("{:.2}e{}", mantissa, exponent),
+            Notation::Engineering => {
+                let (scaled, group) = grouped(exponent);
+                format!("{}e{}", scaled, group)
+            }
+        }
+    }
+
+    /// Parse "123.4", "1.5e300" or anything else `f64` accepts, without
+    /// overflowing on exponents `f64` cannot represent
+    pub fn parse(text: &str) -> Option<BigNum> {
+        let text = text.trim();
+        match text.find(['e', 'E']) {
+            Some(split) => {
+                let mantissa: f64 = text[..split].parse().ok()?;
+                let exponent: i64 = text[split + 1..].parse().ok()?;
+                Some(BigNum::new(mantissa, exponent))
+            }
+            None => text.parse().ok().map(BigNum::from_f64),
+        }
+    }
+}
+
+impl From<f64> for BigNum {
+    fn from(value: f64) -> BigNum {
+        BigNum::from_f64(value)
+    }
+}
+
+impl From<u32> for BigNum {
+    fn from(value: u32) -> BigNum {
+        BigNum::from_f64(value as f64)
+    }
+}
+
+impl fmt::Display for BigNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(Notation::Short))
+    }
+}
+
+impl PartialOrd for BigNum {
+    fn partial_cmp(&self, other: &BigNum) -> Option<Ordering> {
+        if self.exponent == 0 && other.exponent == 0 {
+            return self.mantissa.partial_cmp(&other.mantissa);
+        }
+
+        let sign = self.sign().cmp(&other.sign());
+        if sign != Ordering::Equal || self.sign() == 0 {
+            return Some(sign);
+        }
+
+        let (a_mantissa, a_exponent) = self.scientific();
+        let (b_mantissa, b_exponent) = other.scientific();
+        let magnitude = a_exponent
+            .cmp(&b_exponent)
+            .then(a_mantissa.abs().partial_cmp(&b_mantissa.abs())?);
+        Some(if self.sign() < 0 {
+            magnitude.reverse()
+        } else {
+            magnitude
+        })
+    }
+}
+
+impl PartialEq<f64> for BigNum {
+    fn eq(&self, other: &f64) -> bool {
+        *self == BigNum::from_f64(*other)
+    }
+}
+
+impl PartialOrd<f64> for BigNum {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.partial_cmp(&BigNum::from_f64(*other))
+    }
+}
+
+impl Add for BigNum {
+    type Output = BigNum;
+
+    fn add(self, rhs: BigNum) -> BigNum {
+        if self.exponent == 0 && rhs.exponent == 0 {
+            return BigNum::new(self.mantissa + rhs.mantissa, 0);
+        }
+        if rhs.is_zero() {
+            return self;
+        }
+        if self.is_zero() {
+            return rhs;
+        }
+
+        let (a_mantissa, a_exponent) = self.scientific();
+        let (b_mantissa, b_exponent) = rhs.scientific();
+        let (high, low) = if a_exponent >= b_exponent {
+            ((a_mantissa, a_exponent), (b_mantissa, b_exponent))
+        } else {
+            ((b_mantissa, b_exponent), (a_mantissa, a_exponent))
+        };
+        let gap = high.1 - low.1;
+        if gap > SIGNIFICANT_DIGITS {
+            return BigNum::new(high.0, high.1);
+        }
+        BigNum::new(high.0 + low.0 * pow10(-gap), high.1)
+    }
+}
+
+impl Add<f64> for BigNum {
+    type Output = BigNum;
+
+    fn add(self, rhs: f64) -> BigNum {
+        self + BigNum::from_f64(rhs)
+    }
+}
+
+impl Neg for BigNum {
+    type Output = BigNum;
+
+    fn neg(self) -> BigNum {
+        BigNum {
+            mantissa: -self.mantissa,
+            exponent: self.exponent,
+        }
+    }
+}
+
+impl Sub for BigNum {
+    type Output = BigNum;
+
+    fn sub(self, rhs: BigNum) -> BigNum {
+        self + -rhs
+    }
+}
+
+impl Sub<f64> for BigNum {
+    type Output = BigNum;
+
+    fn sub(self, rhs: f64) -> BigNum {
+        self - BigNum::from_f64(rhs)
+    }
+}
+
+impl Mul for BigNum {
+    type Output = BigNum;
+
+    fn mul(self, rhs: BigNum) -> BigNum {
+        if self.exponent == 0 && rhs.exponent == 0 {
+            return BigNum::new(self.mantissa * rhs.mantissa, 0);
+        }
+        let (a_mantissa, a_exponent) = self.scientific();
+        let (b_mantissa, b_exponent) = rhs.scientific();
+        BigNum::new(a_mantissa * b_mantissa, a_exponent + b_exponent)
+    }
+}
+
+impl Mul<f64> for BigNum {
+    type Output = BigNum;
+
+    fn mul(self, rhs: f64) -> BigNum {
+        self * BigNum::from_f64(rhs)
+    }
+}
+
+/// Division by zero gives zero rather than infinity
+impl Div for BigNum {
+    type Output = BigNum;
+
+    fn div(self, rhs: BigNum) -> BigNum {
+        if rhs.is_zero() {
+            return BigNum::ZERO;
+        }
+        if self.exponent == 0 && rhs.exponent == 0 {
+            return BigNum::new(self.mantissa / rhs.mantissa, 0);
+        }
+        let (a_mantissa, a_exponent) = self.scientific();
+        let (b_mantissa, b_exponent) = rhs.scientific();
+        BigNum::new(a_mantissa / b_mantissa, a_exponent - b_exponent)
+    }
+}
+
+impl Div<f64> for BigNum {
+    type Output = BigNum;
+
+    fn div(self, rhs: f64) -> BigNum {
+        self / BigNum::from_f64(rhs)
+    }
+}
+
+impl AddAssign for BigNum {
+    fn add_assign(&mut self, rhs: BigNum) {
+        *self = *self + rhs;
+    }
+}
+
+impl AddAssign<f64> for BigNum {
+    fn add_assign(&mut self, rhs: f64) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for BigNum {
+    fn sub_assign(&mut self, rhs: BigNum) {
+        *self = *self - rhs;
+    }
+}
+
+impl SubAssign<f64> for BigNum {
+    fn sub_assign(&mut self, rhs: f64) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f64> for BigNum {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+/// Plain values are written as JSON numbers, so saves from before `BigNum`
+/// still load; larger ones as a "1.5e300" string
+impl Serialize for BigNum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.exponent == 0 {
+            serializer.serialize_f64(self.mantissa)
+        } else {
+            serializer.serialize_str(&format!("{}e{}", self.mantissa, self.exponent))
+        }
+    }
+}
+
+struct BigNumVisitor;
+
+impl<'de> Visitor<'de> for BigNumVisitor {
+    type Value = BigNum;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or a numeric string such as \"1.5e300\"")
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<BigNum, E> {
+        Ok(BigNum::from_f64(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<BigNum, E> {
+        Ok(BigNum::from_f64(value as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<BigNum, E> {
+        Ok(BigNum::from_f64(value as f64))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<BigNum, E> {
+        BigNum::parse(value).ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for BigNum {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BigNum, D::Error> {
+        deserializer.deserialize_any(BigNumVisitor)
+    }
+}
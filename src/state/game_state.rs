@@ -1,15 +1,24 @@
+use crate::state::resources::ResourceRegistry;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameState {
-    pub coins: f64,
-    pub wood: f64,
-    pub stone: f64,
+    pub resources: ResourceRegistry,
     pub coins_per_click: f64,
-    pub coins_per_second: f64,
-    pub wood_per_second: f64,
-    pub stone_per_second: f64,
     pub autoclick_count: u32,
     pub total_clicks: u32,
     pub last_update_time: f64,
 }
+
+impl GameState {
+    /// Fresh state for a new game started at `now`
+    pub fn new(resources: ResourceRegistry, now: f64) -> GameState {
+        GameState {
+            resources,
+            coins_per_click: 1.0,
+            autoclick_count: 0,
+            total_clicks: 0,
+            last_update_time: now,
+        }
+    }
+}
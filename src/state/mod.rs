@@ -1,5 +1,9 @@
+pub mod big_num;
 pub mod game_state;
+pub mod resources;
 pub mod statistics;
 
+pub use big_num::{BigNum, Notation};
 pub use game_state::GameState;
+pub use resources::{Resource, ResourceId, ResourceRate, ResourceRegistry};
 pub use statistics::Statistics;
@@ -0,0 +1,164 @@
+use super::BigNum;
+use serde::{Deserialize, Serialize};
+
+/// Stable identifier of a resource, as used in content definitions
+pub type ResourceId = String;
+
+pub const COINS: &str = "coins";
+pub const WOOD: &str = "wood";
+pub const STONE: &str = "stone";
+
+/// A per-second flow of one resource
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResourceRate {
+    pub resource: ResourceId,
+    pub rate: f64,
+}
+
+/// One stockpile tracked by the game, with the metadata needed to display it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Resource {
+    pub id: ResourceId,
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    pub amount: BigNum,
+    pub per_second: f64,
+    /// Total gained in the current run, never reduced by spending; prestige
+    /// resets it. All-time totals, which prestige points and `earned:`
+    /// conditions read, live in `Statistics`
+    #[serde(default)]
+    pub lifetime_earned: BigNum,
+}
+
+impl Resource {
+    pub fn new(id: &str, name: &str, icon: &str) -> Resource {
+        Resource {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon: icon.to_string(),
+            amount: BigNum::ZERO,
+            per_second: 0.0,
+            lifetime_earned: BigNum::ZERO,
+        }
+    }
+}
+
+/// Every resource of the game keyed by id, in display order
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct ResourceRegistry {
+    resources: Vec<Resource>,
+}
+
+impl ResourceRegistry {
+    pub fn new(resources: Vec<Resource>) -> ResourceRegistry {
+        ResourceRegistry { resources }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Resource> {
+        self.resources.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Resource> {
+        self.resources.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Resource> {
+        self.resources.iter_mut().find(|r| r.id == id)
+    }
+
+    /// Current amount, or 0 for a resource that does not exist
+    pub fn amount(&self, id: &str) -> BigNum {
+        self.get(id).map_or(BigNum::ZERO, |r| r.amount)
+    }
+
+    /// Current production per second, or 0 for a resource that does not exist
+    pub fn rate(&self, id: &str) -> f64 {
+        self.get(id).map_or(0.0, |r| r.per_second)
+    }
+
+    /// Overwrite an amount without counting it as earned
+    pub fn set_amount(&mut self, id: &str, amount: impl Into<BigNum>) -> bool {
+        let amount = amount.into();
+        match self.get_mut(id) {
+            Some(resource) => {
+                resource.amount = amount;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add a gain, which also counts towards the lifetime total
+    pub fn earn(&mut self, id: &str, amount: impl Into<BigNum>) -> bool {
+        let amount = amount.into();
+        match self.get_mut(id) {
+            Some(resource) => {
+                resource.amount += amount;
+                if amount > BigNum::ZERO {
+                    resource.lifetime_earned += amount;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Give back an amount that was spent, without counting it as earned
+    pub fn refund(&mut self, id: &str, amount: impl Into<BigNum>) -> bool {
+        let amount = amount.into();
+        match self.get_mut(id) {
+            Some(resource) => {
+                resource.amount += amount;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove `amount` if at least that much is available
+    pub fn spend(&mut self, id: &str, amount: impl Into<BigNum>) -> bool {
+        let amount = amount.into();
+        match self.get_mut(id) {
+            Some(resource) if resource.amount + 1e-10 >= amount => {
+                resource.amount -= amount;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Keep only the resources for which `keep` returns true
+    pub fn retain(&mut self, keep: impl FnMut(&Resource) -> bool) {
+        self.resources.retain(keep);
+    }
+
+    /// Add a resource, or refresh the display metadata of an existing one
+    /// while keeping its amount
+    pub fn register(&mut self, resource: Resource) {
+        match self.get_mut(&resource.id) {
+            Some(existing) => {
+                existing.name = resource.name;
+                existing.icon = resource.icon;
+            }
+            None => self.resources.push(resource),
+        }
+    }
+}
@@ -1,14 +1,46 @@
+use super::resources::{ResourceId, COINS, STONE, WOOD};
+use super::BigNum;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Serialize, Deserialize, Clone)]
+/// Totals kept across prestiges. The `total_*_earned` figures are the single
+/// source for all-time earnings: prestige points and `earned:` conditions read
+/// them, while `Resource::lifetime_earned` only covers the current run
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Statistics {
     pub total_clicks: u32,
-    pub total_coins_earned: f64,
-    pub total_wood_earned: f64,
-    pub total_stone_earned: f64,
+    pub total_coins_earned: BigNum,
+    pub total_wood_earned: BigNum,
+    pub total_stone_earned: BigNum,
     pub total_resources_crafted: u32,
     pub achievements_unlocked_count: u32,
     pub play_time_seconds: f64,
     pub buildings_purchased: u32,
     pub upgrades_purchased: u32,
+    /// Lifetime totals of resources without a field of their own
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub total_earned: BTreeMap<ResourceId, BigNum>,
+}
+
+impl Statistics {
+    /// Count a gain of `resource` towards its lifetime total
+    pub fn record_earned(&mut self, resource: &str, amount: impl Into<BigNum>) {
+        let total = match resource {
+            COINS => &mut self.total_coins_earned,
+            WOOD => &mut self.total_wood_earned,
+            STONE => &mut self.total_stone_earned,
+            _ => self.total_earned.entry(resource.to_string()).or_default(),
+        };
+        *total += amount.into();
+    }
+
+    /// Lifetime total of `resource`; zero if none was ever earned
+    pub fn earned(&self, resource: &str) -> BigNum {
+        match resource {
+            COINS => self.total_coins_earned,
+            WOOD => self.total_wood_earned,
+            STONE => self.total_stone_earned,
+            _ => self.total_earned.get(resource).copied().unwrap_or_default(),
+        }
+    }
 }
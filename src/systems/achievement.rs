@@ -1,5 +1,20 @@
+use crate::state::ResourceId;
+use crate::systems::condition::{Condition, StatKey};
+use crate::systems::effects::{Modifiers, UpgradeEffect};
 use serde::{Deserialize, Serialize};
 
+/// Something granted once, at the moment an achievement unlocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AchievementReward {
+    /// Amount of a resource added to the stockpile
+    Grant { resource: ResourceId, amount: f64 },
+    /// Recipe made craftable
+    UnlockRecipe { recipe: String },
+    /// Feature unlocked, e.g. one that gates a building
+    UnlockFeature { feature: String },
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Achievement {
     pub id: String,
@@ -10,52 +25,42 @@ pub struct Achievement {
     pub progress: f64,
     pub requirement: f64,
     pub category: String,
+    /// What earns the achievement; refreshed from content when a save loads
+    #[serde(default)]
+    pub condition: Condition,
+    /// Permanent bonuses while unlocked, like one level of an upgrade
+    #[serde(default)]
+    pub effects: Vec<UpgradeEffect>,
+    /// One-time rewards handed out on unlock
+    #[serde(default)]
+    pub rewards: Vec<AchievementReward>,
 }
 
 impl Achievement {
-    /// Check and update achievement progress
-    /// Returns true if achievement was unlocked this call
-    pub fn check_progress(
-        &mut self,
-        total_clicks: f64,
-        coins: f64,
-        wood: f64,
-        stone: f64,
-        buildings_purchased: f64,
-        total_resources_crafted: f64,
-        achievements_unlocked_count: f64,
-    ) -> bool {
+    /// Check and update achievement progress, stamping `now` on unlock
+    /// Returns true if achievement is unlocked after this call
+    pub fn check_progress(&mut self, stat: &impl Fn(&StatKey) -> f64, now: f64) -> bool {
         if self.unlocked {
             return true;
         }
 
-        let current_value = match self.category.as_str() {
-            "clicks" => total_clicks,
-            "resources" => {
-                if self.id == "first_coins_100" {
-                    coins
-                } else if self.id == "wood_collector_1000" {
-                    wood
-                } else if self.id == "stone_hoarder_5000" {
-                    stone
-                } else {
-                    0.0
-                }
-            }
-            "buildings" => buildings_purchased,
-            "crafting" => total_resources_crafted,
-            "unlocks" => achievements_unlocked_count,
-            _ => 0.0,
-        };
+        self.progress = self.condition.progress(stat);
 
-        self.progress = current_value;
-
-        if self.progress >= self.requirement {
+        if self.condition.is_met(stat) {
             self.unlocked = true;
-            self.unlock_timestamp = Some(js_sys::Date::now());
+            self.unlock_timestamp = Some(now);
             return true;
         }
 
         false
     }
+
+    /// Fold the bonuses of an unlocked achievement into `modifiers`
+    pub fn apply_to(&self, modifiers: &mut Modifiers) {
+        if self.unlocked {
+            for effect in &self.effects {
+                modifiers.apply(effect, 1);
+            }
+        }
+    }
 }
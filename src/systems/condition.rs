@@ -0,0 +1,201 @@
+use crate::state::ResourceId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A value a condition can be measured against, written in content as a
+/// string such as `"total_clicks"` or `"resource:coins"`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum StatKey {
+    /// Clicks in the current run
+    Clicks,
+    /// Clicks across every run
+    TotalClicks,
+    BuildingsPurchased,
+    UpgradesPurchased,
+    ResourcesCrafted,
+    AchievementsUnlocked,
+    PlayTimeSeconds,
+    PrestigeCount,
+    WorkersAssigned,
+    /// Current amount of a resource
+    Resource(ResourceId),
+    /// Amount of a resource earned across every run
+    Earned(ResourceId),
+    /// Buildings of one kind owned
+    Building(String),
+    /// Levels of one upgrade owned
+    Upgrade(String),
+}
+
+impl fmt::Display for StatKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatKey::Clicks => write!(f, "clicks"),
+            StatKey::TotalClicks => write!(f, "total_clicks"),
+            StatKey::BuildingsPurchased => write!(f, "buildings_purchased"),
+            StatKey::UpgradesPurchased => write!(f, "upgrades_purchased"),
+            StatKey::ResourcesCrafted => write!(f, "resources_crafted"),
+            StatKey::AchievementsUnlocked => write!(f, "achievements_unlocked"),
+            StatKey::PlayTimeSeconds => write!(f, "play_time_seconds"),
+            StatKey::PrestigeCount => write!(f, "prestige_count"),
+            StatKey::WorkersAssigned => write!(f, "workers_assigned"),
+            StatKey::Resource(id) => write!(f, "resource:{}", id),
+            StatKey::Earned(id) => write!(f, "earned:{}", id),
+            StatKey::Building(id) => write!(f, "building:{}", id),
+            StatKey::Upgrade(id) => write!(f, "upgrade:{}", id),
+        }
+    }
+}
+
+impl FromStr for StatKey {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<StatKey, String> {
+        let stat = match key {
+            "clicks" => StatKey::Clicks,
+            "total_clicks" => StatKey::TotalClicks,
+            "buildings_purchased" => StatKey::BuildingsPurchased,
+            "upgrades_purchased" => StatKey::UpgradesPurchased,
+            "resources_crafted" => StatKey::ResourcesCrafted,
+            "achievements_unlocked" => StatKey::AchievementsUnlocked,
+            "play_time_seconds" => StatKey::PlayTimeSeconds,
+            "prestige_count" => StatKey::PrestigeCount,
+            "workers_assigned" => StatKey::WorkersAssigned,
+            _ => match key.split_once(':') {
+                Some(("resource", id)) if !id.is_empty() => StatKey::Resource(id.to_string()),
+                Some(("earned", id)) if !id.is_empty() => StatKey::Earned(id.to_string()),
+                Some(("building", id)) if !id.is_empty() => StatKey::Building(id.to_string()),
+                Some(("upgrade", id)) if !id.is_empty() => StatKey::Upgrade(id.to_string()),
+                _ => return Err(format!("unknown stat {}", key)),
+            },
+        };
+        Ok(stat)
+    }
+}
+
+impl TryFrom<String> for StatKey {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<StatKey, String> {
+        key.parse()
+    }
+}
+
+impl From<StatKey> for String {
+    fn from(stat: StatKey) -> String {
+        stat.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Comparator {
+    #[default]
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = "<=")]
+    AtMost,
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "==")]
+    Equal,
+}
+
+impl Comparator {
+    pub fn compare(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparator::AtLeast => value >= threshold,
+            Comparator::Above => value > threshold,
+            Comparator::AtMost => value <= threshold,
+            Comparator::Below => value < threshold,
+            Comparator::Equal => value == threshold,
+        }
+    }
+}
+
+/// When an achievement or feature is earned, declared in content so new
+/// ones need no code. The default, an empty `any`, is never met
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// A stat compared with a threshold, `>=` unless stated
+    Stat {
+        stat: StatKey,
+        #[serde(default)]
+        comparator: Comparator,
+        value: f64,
+    },
+    /// Every condition holds
+    All { conditions: Vec<Condition> },
+    /// At least one condition holds
+    Any { conditions: Vec<Condition> },
+}
+
+impl Default for Condition {
+    fn default() -> Condition {
+        Condition::Any {
+            conditions: Vec::new(),
+        }
+    }
+}
+
+impl Condition {
+    pub fn stat(stat: StatKey, value: f64) -> Condition {
+        Condition::Stat {
+            stat,
+            comparator: Comparator::AtLeast,
+            value,
+        }
+    }
+
+    pub fn is_met(&self, stat: &impl Fn(&StatKey) -> f64) -> bool {
+        match self {
+            Condition::Stat {
+                stat: key,
+                comparator,
+                value,
+            } => comparator.compare(stat(key), *value),
+            Condition::All { conditions } => conditions.iter().all(|c| c.is_met(stat)),
+            Condition::Any { conditions } => conditions.iter().any(|c| c.is_met(stat)),
+        }
+    }
+
+    /// How far along the condition is, out of `requirement()`: the stat
+    /// itself for a single comparison, else the number of parts met
+    pub fn progress(&self, stat: &impl Fn(&StatKey) -> f64) -> f64 {
+        match self {
+            Condition::Stat { stat: key, .. } => stat(key),
+            Condition::All { conditions } => {
+                conditions.iter().filter(|c| c.is_met(stat)).count() as f64
+            }
+            Condition::Any { .. } => {
+                if self.is_met(stat) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    pub fn requirement(&self) -> f64 {
+        match self {
+            Condition::Stat { value, .. } => *value,
+            Condition::All { conditions } => conditions.len() as f64,
+            Condition::Any { .. } => 1.0,
+        }
+    }
+
+    /// Every stat the condition reads
+    pub fn stats(&self) -> Vec<&StatKey> {
+        match self {
+            Condition::Stat { stat, .. } => vec![stat],
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                conditions.iter().flat_map(Condition::stats).collect()
+            }
+        }
+    }
+}
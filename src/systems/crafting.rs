@@ -1,74 +1,124 @@
+use crate::state::{BigNum, ResourceId, ResourceRegistry};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone)]
+/// Crafts that can wait in the queue, running ones included
+pub const MAX_QUEUED_CRAFTS: usize = 10;
+
+/// Most crafts `craft_max` makes in one batch
+pub const MAX_CRAFT_BATCH: u32 = 10_000;
+
+fn default_count() -> u32 {
+    1
+}
+
+/// An amount of one resource a recipe consumes or produces
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecipeItem {
+    pub resource: ResourceId,
+    pub amount: f64,
+}
+
+impl RecipeItem {
+    pub fn new(resource: &str, amount: f64) -> RecipeItem {
+        RecipeItem {
+            resource: resource.to_string(),
+            amount,
+        }
+    }
+}
+
+/// An output a craft only sometimes produces, on top of the regular ones
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BonusOutput {
+    pub resource: ResourceId,
+    pub amount: f64,
+    /// Chance per craft, from 0 to 1
+    pub chance: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CraftingRecipe {
     pub id: String,
     pub name: String,
-    pub input_resource: String,
-    pub input_amount: f64,
-    pub output_resource: String,
-    pub output_amount: f64,
+    /// Resources spent by each craft
+    pub inputs: Vec<RecipeItem>,
+    /// Resources produced by each craft
+    pub outputs: Vec<RecipeItem>,
+    #[serde(default)]
+    pub bonus_outputs: Vec<BonusOutput>,
+    /// Seconds each craft takes in the queue; 0 completes at once
+    #[serde(default)]
+    pub duration: f64,
     pub unlocked: bool,
+    /// Feature that must be unlocked before the recipe can be crafted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    /// Building of which at least one must be owned to craft the recipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_building: Option<String>,
 }
 
 impl CraftingRecipe {
-    /// Get all default crafting recipes
-    pub fn get_default_recipes() -> Vec<CraftingRecipe> {
-        vec![
-            CraftingRecipe {
-                id: "coins_to_wood".to_string(),
-                name: "金币换木材".to_string(),
-                input_resource: "coins".to_string(),
-                input_amount: 100.0,
-                output_resource: "wood".to_string(),
-                output_amount: 10.0,
-                unlocked: true,
-            },
-            CraftingRecipe {
-                id: "wood_to_coins".to_string(),
-                name: "木材换金币".to_string(),
-                input_resource: "wood".to_string(),
-                input_amount: 10.0,
-                output_resource: "coins".to_string(),
-                output_amount: 100.0,
-                unlocked: true,
-            },
-            CraftingRecipe {
-                id: "coins_to_stone".to_string(),
-                name: "金币换石头".to_string(),
-                input_resource: "coins".to_string(),
-                input_amount: 100.0,
-                output_resource: "stone".to_string(),
-                output_amount: 1.0,
-                unlocked: true,
-            },
-            CraftingRecipe {
-                id: "stone_to_coins".to_string(),
-                name: "石头换金币".to_string(),
-                input_resource: "stone".to_string(),
-                input_amount: 1.0,
-                output_resource: "coins".to_string(),
-                output_amount: 100.0,
-                unlocked: true,
-            },
-            CraftingRecipe {
-                id: "wood_to_stone".to_string(),
-                name: "木材换石头".to_string(),
-                input_resource: "wood".to_string(),
-                input_amount: 10.0,
-                output_resource: "stone".to_string(),
-                output_amount: 1.0,
-                unlocked: true,
-            },
-            CraftingRecipe {
-                id: "stone_to_wood".to_string(),
-                name: "石头换木材".to_string(),
-                input_resource: "stone".to_string(),
-                input_amount: 1.0,
-                output_resource: "wood".to_string(),
-                output_amount: 10.0,
-                unlocked: true,
-            },
-        ]
+    /// The first input `resources` cannot pay for, with the amount available
+    pub fn missing_input(&self, resources: &ResourceRegistry) -> Option<(&RecipeItem, BigNum)> {
+        self.inputs.iter().find_map(|input| {
+            let available = resources.amount(&input.resource);
+            (available < input.amount).then_some((input, available))
+        })
+    }
+
+    /// How many crafts `resources` can pay for; recipes without inputs are
+    /// limited only by `u32::MAX`
+    pub fn affordable(&self, resources: &ResourceRegistry) -> u32 {
+        self.inputs
+            .iter()
+            .filter(|input| input.amount > 0.0)
+            // Float to integer casts saturate, so huge amounts give u32::MAX
+            .map(|input| {
+                (resources.amount(&input.resource) / input.amount)
+                    .floor()
+                    .to_f64() as u32
+            })
+            .min()
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Every resource the recipe reads or writes
+    pub fn resources(&self) -> impl Iterator<Item = &ResourceId> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|item| &item.resource)
+            .chain(self.bonus_outputs.iter().map(|bonus| &bonus.resource))
+    }
+}
+
+/// A queued batch of crafts; its inputs were spent when it was queued
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CraftingJob {
+    pub recipe_id: String,
+    /// Crafts in the batch, all completing together
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Seconds the batch takes in total
+    pub duration: f64,
+    /// Seconds spent on it so far
+    pub progress: f64,
+}
+
+impl CraftingJob {
+    /// A batch of `count` crafts of `recipe`, taking `count` times as long
+    pub fn new(recipe: &CraftingRecipe, count: u32) -> CraftingJob {
+        CraftingJob {
+            recipe_id: recipe.id.clone(),
+            count,
+            duration: recipe.duration * count as f64,
+            progress: 0.0,
+        }
+    }
+
+    /// Seconds left until the craft completes
+    pub fn remaining(&self) -> f64 {
+        (self.duration - self.progress).max(0.0)
     }
 }
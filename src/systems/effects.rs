@@ -0,0 +1,150 @@
+use crate::state::ResourceId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Coins per click before any upgrade
+pub const BASE_COINS_PER_CLICK: f64 = 1.0;
+
+/// Building prices never drop below this fraction of their undiscounted cost
+pub const MIN_COST_MULTIPLIER: f64 = 0.1;
+
+/// Workers that can be assigned at once before any extra slots; enough for
+/// every worker the default content ships
+pub const BASE_WORKER_SLOTS: u32 = 5;
+
+/// Crafts that progress at once before any extra slots
+pub const BASE_CRAFTING_SLOTS: u32 = 1;
+
+/// What owning one level of an upgrade does; bonuses are fractions, so 0.5
+/// means +50% per level
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UpgradeEffect {
+    /// Coins added to every click
+    ClickBonus { amount: f64 },
+    /// Coins per click scaled by 1 + bonus
+    ClickMultiplier { bonus: f64 },
+    /// Autoclickers added, each clicking ten times a second
+    Autoclicker { count: u32 },
+    /// Flat per-second production of a resource
+    ResourceRate { resource: ResourceId, rate: f64 },
+    /// Output of one building scaled by 1 + bonus
+    BuildingMultiplier { building: String, bonus: f64 },
+    /// All production, clicks included, scaled by 1 + bonus
+    GlobalMultiplier { bonus: f64 },
+    /// Building prices scaled by 1 - fraction
+    CostReduction { fraction: f64 },
+    /// Worker XP gain scaled by 1 + bonus
+    WorkerXpMultiplier { bonus: f64 },
+    /// Amount of a resource every run after a prestige starts with
+    StartingResource { resource: ResourceId, amount: f64 },
+    /// Autoclicker upgrades are kept through a prestige
+    KeepAutoclickers,
+    /// More workers can be assigned at once
+    WorkerSlots { count: u32 },
+    /// More queued crafts progress at once
+    CraftingSlots { count: u32 },
+}
+
+/// Combined effect of everything the player owns, applied uniformly by the
+/// production pipeline
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Modifiers {
+    pub click_bonus: f64,
+    pub click_multiplier: f64,
+    pub autoclickers: u32,
+    pub resource_rates: BTreeMap<ResourceId, f64>,
+    pub building_multipliers: BTreeMap<String, f64>,
+    pub global_multiplier: f64,
+    pub cost_multiplier: f64,
+    pub worker_xp_multiplier: f64,
+    pub starting_resources: BTreeMap<ResourceId, f64>,
+    pub keep_autoclickers: bool,
+    pub extra_worker_slots: u32,
+    pub extra_crafting_slots: u32,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Modifiers {
+            click_bonus: 0.0,
+            click_multiplier: 1.0,
+            autoclickers: 0,
+            resource_rates: BTreeMap::new(),
+            building_multipliers: BTreeMap::new(),
+            global_multiplier: 1.0,
+            cost_multiplier: 1.0,
+            worker_xp_multiplier: 1.0,
+            starting_resources: BTreeMap::new(),
+            keep_autoclickers: false,
+            extra_worker_slots: 0,
+            extra_crafting_slots: 0,
+        }
+    }
+}
+
+impl Modifiers {
+    /// Fold in `levels` levels of `effect`
+    pub fn apply(&mut self, effect: &UpgradeEffect, levels: u32) {
+        if levels == 0 {
+            return;
+        }
+        let n = levels as f64;
+
+        match effect {
+            UpgradeEffect::ClickBonus { amount } => self.click_bonus += amount * n,
+            UpgradeEffect::ClickMultiplier { bonus } => self.click_multiplier *= 1.0 + bonus * n,
+            UpgradeEffect::Autoclicker { count } => self.autoclickers += count * levels,
+            UpgradeEffect::ResourceRate { resource, rate } => {
+                *self.resource_rates.entry(resource.clone()).or_insert(0.0) += rate * n;
+            }
+            UpgradeEffect::BuildingMultiplier { building, bonus } => {
+                *self
+                    .building_multipliers
+                    .entry(building.clone())
+                    .or_insert(1.0) *= 1.0 + bonus * n;
+            }
+            UpgradeEffect::GlobalMultiplier { bonus } => self.global_multiplier *= 1.0 + bonus * n,
+            UpgradeEffect::CostReduction { fraction } => {
+                self.cost_multiplier = (self.cost_multiplier
+                    * (1.0 - fraction).clamp(0.0, 1.0).powi(levels as i32))
+                .max(MIN_COST_MULTIPLIER);
+            }
+            UpgradeEffect::WorkerXpMultiplier { bonus } => {
+                self.worker_xp_multiplier *= 1.0 + bonus * n;
+            }
+            UpgradeEffect::StartingResource { resource, amount } => {
+                *self
+                    .starting_resources
+                    .entry(resource.clone())
+                    .or_insert(0.0) += amount * n;
+            }
+            UpgradeEffect::KeepAutoclickers => self.keep_autoclickers = true,
+            UpgradeEffect::WorkerSlots { count } => self.extra_worker_slots += count * levels,
+            UpgradeEffect::CraftingSlots { count } => self.extra_crafting_slots += count * levels,
+        }
+    }
+
+    pub fn coins_per_click(&self) -> f64 {
+        (BASE_COINS_PER_CLICK + self.click_bonus) * self.click_multiplier * self.global_multiplier
+    }
+
+    /// Workers that can be assigned at once
+    pub fn worker_slots(&self) -> u32 {
+        BASE_WORKER_SLOTS + self.extra_worker_slots
+    }
+
+    /// Queued crafts that progress at once
+    pub fn crafting_slots(&self) -> u32 {
+        BASE_CRAFTING_SLOTS + self.extra_crafting_slots
+    }
+
+    /// Scale applied to the output of the building with `building_id`
+    pub fn building_multiplier(&self, building_id: &str) -> f64 {
+        self.building_multipliers
+            .get(building_id)
+            .copied()
+            .unwrap_or(1.0)
+            * self.global_multiplier
+    }
+}
@@ -1,8 +1,16 @@
 pub mod achievement;
+pub mod condition;
 pub mod crafting;
+pub mod effects;
+pub mod offline;
+pub mod prestige;
 pub mod production;
 pub mod unlock;
 
-pub use achievement::Achievement;
-pub use crafting::CraftingRecipe;
+pub use achievement::{Achievement, AchievementReward};
+pub use condition::{Comparator, Condition, StatKey};
+pub use crafting::{BonusOutput, CraftingJob, CraftingRecipe, RecipeItem};
+pub use effects::{Modifiers, UpgradeEffect};
+pub use offline::{OfflineConfig, OfflineReport};
+pub use prestige::PrestigeState;
 pub use unlock::UnlockedFeature;
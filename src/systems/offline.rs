@@ -0,0 +1,95 @@
+use crate::state::BigNum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Tuning for progress earned while the game was not running
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OfflineConfig {
+    /// Gaps between updates longer than this many seconds count as offline
+    /// time; the first `threshold_seconds` of an absence are still credited at
+    /// full rate, so a briefly backgrounded tab loses nothing
+    pub threshold_seconds: f64,
+    /// Longest absence, in seconds, that is credited
+    pub max_seconds: f64,
+    /// Fraction of normal production earned for offline time beyond the
+    /// threshold
+    pub efficiency: f64,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        OfflineConfig {
+            threshold_seconds: 300.0,
+            max_seconds: 12.0 * 3600.0,
+            efficiency: 0.5,
+        }
+    }
+}
+
+impl OfflineConfig {
+    /// Seconds of full-rate production an absence of `elapsed_seconds` is
+    /// worth: full rate up to the threshold, `efficiency` beyond it
+    pub fn effective_seconds(&self, elapsed_seconds: f64) -> f64 {
+        let credited = self.credited_seconds(elapsed_seconds);
+        let full_rate = credited.min(self.threshold_seconds.max(0.0));
+        full_rate + (credited - full_rate) * self.efficiency
+    }
+
+    /// Part of an absence that falls under the cap
+    pub fn credited_seconds(&self, elapsed_seconds: f64) -> f64 {
+        elapsed_seconds.clamp(0.0, self.max_seconds)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkerLevelGain {
+    pub worker: String,
+    pub levels: u32,
+}
+
+/// Summary of a catch-up after an absence, for the frontend to display
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OfflineReport {
+    /// Real time away, in seconds
+    pub duration_seconds: f64,
+    /// Time credited after applying the cap, in seconds
+    pub credited_seconds: f64,
+    pub efficiency: f64,
+    pub resources_gained: BTreeMap<String, BigNum>,
+    pub worker_levels_gained: Vec<WorkerLevelGain>,
+    pub achievements_unlocked: Vec<String>,
+    /// Recipes whose queued crafts completed while away, in order
+    pub crafts_completed: Vec<String>,
+}
+
+impl OfflineReport {
+    /// Fold a later catch-up into this one, so nothing is lost if the
+    /// frontend has not collected the previous report yet
+    pub fn merge(&mut self, other: OfflineReport) {
+        self.duration_seconds += other.duration_seconds;
+        self.credited_seconds += other.credited_seconds;
+        self.efficiency = other.efficiency;
+
+        for (resource, amount) in other.resources_gained {
+            *self
+                .resources_gained
+                .entry(resource)
+                .or_insert(BigNum::ZERO) += amount;
+        }
+
+        for gain in other.worker_levels_gained {
+            match self
+                .worker_levels_gained
+                .iter_mut()
+                .find(|g| g.worker == gain.worker)
+            {
+                Some(existing) => existing.levels += gain.levels,
+                None => self.worker_levels_gained.push(gain),
+            }
+        }
+
+        self.achievements_unlocked
+            .extend(other.achievements_unlocked);
+        self.crafts_completed.extend(other.crafts_completed);
+    }
+}
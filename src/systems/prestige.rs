@@ -0,0 +1,85 @@
+use crate::state::BigNum;
+use crate::systems::effects::Modifiers;
+use serde::{Deserialize, Serialize};
+
+/// Name of the prestige currency where a resource id is expected, e.g. in
+/// a failed purchase
+pub const PRESTIGE_POINTS: &str = "prestige_points";
+
+/// Lifetime coins worth the first prestige point; points grow with the
+/// square root of lifetime coins beyond that
+pub const PRESTIGE_BASE_COINS: f64 = 10_000.0;
+
+/// Production bonus per prestige point ever earned, as a fraction
+pub const PRESTIGE_BONUS_PER_POINT: f64 = 0.02;
+
+/// Progress that survives a prestige
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PrestigeState {
+    /// Prestige currency available to spend
+    pub points: BigNum,
+    /// Every point earned so far, spent or not; drives the multiplier
+    pub total_points_earned: BigNum,
+    pub prestige_count: u32,
+}
+
+/// Prestige progress as the frontend shows it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrestigeSummary {
+    pub points: BigNum,
+    pub total_points_earned: BigNum,
+    pub prestige_count: u32,
+    /// Points prestiging now would add
+    pub pending_points: BigNum,
+    pub multiplier: f64,
+    pub lifetime_coins: BigNum,
+    pub coins_for_next_point: BigNum,
+}
+
+impl PrestigeState {
+    /// Points that prestiging now would add, given lifetime coins earned
+    /// across every run; points already claimed are not counted again
+    pub fn pending_points(&self, lifetime_coins: BigNum) -> BigNum {
+        let claimable = (lifetime_coins / PRESTIGE_BASE_COINS).sqrt().floor();
+        (claimable - self.total_points_earned).max(BigNum::ZERO)
+    }
+
+    /// Lifetime coins at which the next point becomes claimable
+    pub fn coins_for_next_point(&self) -> BigNum {
+        (self.total_points_earned + 1.0).powi(2) * PRESTIGE_BASE_COINS
+    }
+
+    /// Permanent production multiplier from every point ever earned
+    pub fn multiplier(&self) -> f64 {
+        let bonus = self.total_points_earned.to_f64() * PRESTIGE_BONUS_PER_POINT;
+        if bonus.is_finite() {
+            1.0 + bonus
+        } else {
+            f64::MAX
+        }
+    }
+
+    /// Fold the prestige bonus into `modifiers`
+    pub fn apply_to(&self, modifiers: &mut Modifiers) {
+        modifiers.global_multiplier *= self.multiplier();
+    }
+
+    pub fn summary(&self, lifetime_coins: BigNum) -> PrestigeSummary {
+        PrestigeSummary {
+            points: self.points,
+            total_points_earned: self.total_points_earned,
+            prestige_count: self.prestige_count,
+            pending_points: self.pending_points(lifetime_coins),
+            multiplier: self.multiplier(),
+            lifetime_coins,
+            coins_for_next_point: self.coins_for_next_point(),
+        }
+    }
+
+    /// Claim `points`
+    pub fn claim(&mut self, points: BigNum) {
+        self.points += points;
+        self.total_points_earned += points;
+        self.prestige_count += 1;
+    }
+}
@@ -1,12 +1,16 @@
 use crate::entities::{Building, Upgrade, Worker};
+use crate::state::resources::ResourceId;
+use crate::state::ResourceRegistry;
+use crate::systems::effects::Modifiers;
+use std::collections::BTreeMap;
 
 /// Calculate worker bonus for a specific building
-pub fn get_worker_bonus_for_building(workers: &[Worker], building_name: &str) -> f64 {
+pub fn get_worker_bonus_for_building(workers: &[Worker], building_id: &str) -> f64 {
     let mut total_bonus = 1.0;
 
     for worker in workers {
         if let Some(ref assigned) = worker.assigned_building {
-            if assigned == building_name {
+            if assigned == building_id {
                 total_bonus += worker.efficiency_multiplier - 1.0;
             }
         }
@@ -15,84 +19,168 @@ pub fn get_worker_bonus_for_building(workers: &[Worker], building_name: &str) ->
     total_bonus
 }
 
-/// Update production rates based on buildings and worker bonuses
+/// Whether every input of `building` is in stock; a building that consumes
+/// nothing is always supplied
+pub fn is_supplied(building: &Building, resources: &ResourceRegistry) -> bool {
+    building
+        .inputs
+        .iter()
+        .all(|input| resources.amount(&input.resource) > 0.0)
+}
+
+/// Combined effects of every owned upgrade level
+pub fn collect_modifiers<'a>(upgrades: impl IntoIterator<Item = &'a Upgrade>) -> Modifiers {
+    let mut modifiers = Modifiers::default();
+    for upgrade in upgrades {
+        for effect in &upgrade.effects {
+            modifiers.apply(effect, upgrade.owned);
+        }
+    }
+    modifiers
+}
+
+/// Net per-second flow of each resource from buildings, worker bonuses and
+/// modifiers; buildings with an input out of stock neither consume nor produce
 pub fn update_production(
     buildings: &[Building],
-    upgrades: &[Upgrade],
     workers: &[Worker],
-) -> (f64, f64, f64) {
-    let mut total_cps = 0.0;
-    let mut total_wps = 0.0;
-    let mut total_sps = 0.0;
+    resources: &ResourceRegistry,
+    modifiers: &Modifiers,
+) -> BTreeMap<ResourceId, f64> {
+    let mut rates: BTreeMap<ResourceId, f64> = BTreeMap::new();
 
     for building in buildings {
-        let base_production = building.production_rate * building.count as f64;
-        let worker_bonus = get_worker_bonus_for_building(workers, &building.name);
-        let boosted_production = base_production * worker_bonus;
+        if building.count == 0 || !is_supplied(building, resources) {
+            continue;
+        }
 
-        match building.name.as_str() {
-            "Coin Mine" | "Coin Factory" | "Coin Corporation" => {
-                total_cps += boosted_production;
-            }
-            "Woodcutter" | "Lumber Mill" | "Forest Workshop" => {
-                total_wps += boosted_production;
-            }
-            "Stone Quarry" | "Rock Crusher" | "Mason Workshop" => {
-                total_sps += boosted_production;
+        let count = building.count as f64;
+        let worker_bonus = get_worker_bonus_for_building(workers, &building.id);
+        let multiplier = modifiers.building_multiplier(&building.id);
+        for output in &building.outputs {
+            *rates.entry(output.resource.clone()).or_insert(0.0) +=
+                output.rate * count * worker_bonus * multiplier;
+        }
+        for input in &building.inputs {
+            *rates.entry(input.resource.clone()).or_insert(0.0) -= input.rate * count;
+        }
+    }
+
+    for (resource, rate) in &modifiers.resource_rates {
+        *rates.entry(resource.clone()).or_insert(0.0) += rate * modifiers.global_multiplier;
+    }
+
+    for rate in rates.values_mut() {
+        if !rate.is_finite() {
+            *rate = 0.0;
+        }
+    }
+
+    rates
+}
+
+/// Resources gained (negative when spent) by buildings and modifier rates
+/// over `seconds`. Each consumer runs only for the part of the step its inputs
+/// can pay for, from stock plus what input-free sources add meanwhile;
+/// consumers of the same resource share it in proportion to their demand
+pub fn step_production(
+    buildings: &[Building],
+    workers: &[Worker],
+    resources: &ResourceRegistry,
+    modifiers: &Modifiers,
+    seconds: f64,
+) -> BTreeMap<ResourceId, f64> {
+    let mut gained: BTreeMap<ResourceId, f64> = BTreeMap::new();
+    let mut available: BTreeMap<ResourceId, f64> = BTreeMap::new();
+    let mut demand: BTreeMap<ResourceId, f64> = BTreeMap::new();
+
+    for (resource, rate) in &modifiers.resource_rates {
+        *gained.entry(resource.clone()).or_insert(0.0) +=
+            rate * modifiers.global_multiplier * seconds;
+    }
+    for building in buildings.iter().filter(|b| b.count > 0) {
+        let count = building.count as f64;
+        if building.inputs.is_empty() {
+            let worker_bonus = get_worker_bonus_for_building(workers, &building.id);
+            let multiplier = modifiers.building_multiplier(&building.id);
+            for output in &building.outputs {
+                *gained.entry(output.resource.clone()).or_insert(0.0) +=
+                    output.rate * count * worker_bonus * multiplier * seconds;
             }
-            _ => {}
+        }
+        for input in &building.inputs {
+            *demand.entry(input.resource.clone()).or_insert(0.0) += input.rate * count * seconds;
         }
     }
+    for resource in demand.keys() {
+        let inflow = gained.get(resource).copied().unwrap_or(0.0).max(0.0);
+        available.insert(
+            resource.clone(),
+            resources.amount(resource).to_f64() + inflow,
+        );
+    }
 
-    for upgrade in upgrades {
-        if upgrade.name == "Lumberjack Efficiency" {
-            total_wps += upgrade.production_increase * upgrade.owned as f64;
+    for building in buildings
+        .iter()
+        .filter(|b| b.count > 0 && !b.inputs.is_empty())
+    {
+        let supplied = building
+            .inputs
+            .iter()
+            .map(|input| {
+                let wanted = demand[&input.resource];
+                if wanted > 0.0 {
+                    (available[&input.resource] / wanted).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                }
+            })
+            .fold(1.0, f64::min);
+        let running = seconds * supplied;
+        if running <= 0.0 {
+            continue;
+        }
+
+        let count = building.count as f64;
+        let worker_bonus = get_worker_bonus_for_building(workers, &building.id);
+        let multiplier = modifiers.building_multiplier(&building.id);
+        for output in &building.outputs {
+            *gained.entry(output.resource.clone()).or_insert(0.0) +=
+                output.rate * count * worker_bonus * multiplier * running;
+        }
+        for input in &building.inputs {
+            *gained.entry(input.resource.clone()).or_insert(0.0) -= input.rate * count * running;
         }
-        if upgrade.name == "Stone Mason Skill" {
-            total_sps += upgrade.production_increase * upgrade.owned as f64;
+    }
+
+    for amount in gained.values_mut() {
+        if !amount.is_finite() {
+            *amount = 0.0;
         }
     }
 
-    total_cps = if total_cps.is_finite() && total_cps >= 0.0 {
-        total_cps
-    } else {
-        0.0
-    };
-    total_wps = if total_wps.is_finite() && total_wps >= 0.0 {
-        total_wps
-    } else {
-        0.0
-    };
-    total_sps = if total_sps.is_finite() && total_sps >= 0.0 {
-        total_sps
-    } else {
-        0.0
-    };
-
-    (total_cps, total_wps, total_sps)
+    gained
 }
 
-/// Grant XP to workers assigned to buildings
-pub fn grant_worker_xp(workers: &mut [Worker], elapsed: f64) {
-    for i in 0..workers.len() {
-        if workers[i].assigned_building.is_some() {
-            let xp_gain = 10.0 * elapsed;
-            workers[i].xp += xp_gain;
+/// Grant XP to workers assigned to buildings, scaled by `xp_multiplier`
+pub fn grant_worker_xp(workers: &mut [Worker], elapsed: f64, xp_multiplier: f64) {
+    for worker in workers.iter_mut() {
+        if let Some(assigned) = &worker.assigned_building {
+            let xp_gain = 10.0 * elapsed * xp_multiplier;
+            worker.xp += xp_gain;
 
-            while workers[i].xp >= workers[i].xp_to_next_level {
-                workers[i].xp -= workers[i].xp_to_next_level;
-                workers[i].level += 1;
-                workers[i].xp_to_next_level = (workers[i].xp_to_next_level * 1.5).ceil();
+            while worker.xp >= worker.xp_to_next_level {
+                worker.xp -= worker.xp_to_next_level;
+                worker.level += 1;
+                worker.xp_to_next_level = (worker.xp_to_next_level * 1.5).ceil();
 
-                let preference = &workers[i].preferences;
-                let assigned = workers[i].assigned_building.as_ref().unwrap();
                 let mut efficiency = 1.0;
 
-                if preference == assigned {
+                if &worker.preferences == assigned {
                     efficiency += 0.2;
                 }
-                efficiency += (workers[i].level as f64) * 0.05;
-                workers[i].efficiency_multiplier = efficiency;
+                efficiency += (worker.level as f64) * 0.05;
+                worker.efficiency_multiplier = efficiency;
             }
         }
     }
@@ -1,3 +1,4 @@
+use crate::systems::condition::{Condition, StatKey};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -7,59 +8,23 @@ pub struct UnlockedFeature {
     pub feature_type: String,
     pub unlocked: bool,
     pub unlock_timestamp: Option<f64>,
-    pub requirement_type: String,
-    pub requirement_value: f64,
+    #[serde(default)]
+    pub progress: f64,
+    #[serde(default)]
+    pub requirement: f64,
+    /// What unlocks the feature; refreshed from content when a save loads
+    #[serde(default)]
+    pub condition: Condition,
+    /// Features that must be unlocked before this one can be
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
 }
 
 impl UnlockedFeature {
-    /// Get all default unlocked features
-    pub fn get_default_features() -> Vec<UnlockedFeature> {
-        vec![
-            UnlockedFeature {
-                id: "first_unlock".to_string(),
-                name: "首次解锁".to_string(),
-                feature_type: "achievement".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                requirement_type: "total_clicks".to_string(),
-                requirement_value: 10.0,
-            },
-            UnlockedFeature {
-                id: "progress_master_5".to_string(),
-                name: "进度大师".to_string(),
-                feature_type: "achievement".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                requirement_type: "total_clicks".to_string(),
-                requirement_value: 50.0,
-            },
-            UnlockedFeature {
-                id: "unlock_building_1".to_string(),
-                name: "建筑解锁 1".to_string(),
-                feature_type: "building".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                requirement_type: "buildings_owned".to_string(),
-                requirement_value: 5.0,
-            },
-            UnlockedFeature {
-                id: "unlock_building_2".to_string(),
-                name: "建筑解锁 2".to_string(),
-                feature_type: "building".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                requirement_type: "buildings_owned".to_string(),
-                requirement_value: 15.0,
-            },
-            UnlockedFeature {
-                id: "unlock_crafting".to_string(),
-                name: "制作系统".to_string(),
-                feature_type: "system".to_string(),
-                unlocked: false,
-                unlock_timestamp: None,
-                requirement_type: "total_coins".to_string(),
-                requirement_value: 500.0,
-            },
-        ]
+    /// Update `progress` and report whether the condition is met;
+    /// prerequisites are the caller's concern
+    pub fn check_progress(&mut self, stat: &impl Fn(&StatKey) -> f64) -> bool {
+        self.progress = self.condition.progress(stat);
+        self.condition.is_met(stat)
     }
 }
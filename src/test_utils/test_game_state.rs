@@ -1,757 +1,107 @@
-use crate::entities::{Building, Upgrade, Worker};
+use crate::content::GameContent;
+use crate::core::{GameCore, ManualClock};
+use crate::entities::Worker;
 use crate::state::Statistics;
-use crate::systems::{Achievement, CraftingRecipe, UnlockedFeature};
+use crate::systems::{Achievement, CraftingJob, CraftingRecipe, UnlockedFeature};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
 
+/// Test fixture: the real `GameCore` driven by a manual clock the test controls
 #[cfg(test)]
 pub struct TestGameState {
-    coins: f64,
-    wood: f64,
-    stone: f64,
-    coins_per_click: f64,
-    coins_per_second: f64,
-    wood_per_second: f64,
-    stone_per_second: f64,
-    autoclick_count: u32,
-    total_clicks: u32,
-    upgrades: Vec<Upgrade>,
-    buildings: Vec<Building>,
-    achievements: Vec<Achievement>,
-    crafting_recipes: Vec<CraftingRecipe>,
-    unlocked_features: Vec<UnlockedFeature>,
-    statistics: Statistics,
-    workers: Vec<Worker>,
+    core: GameCore,
+    pub clock: ManualClock,
+}
+
+#[cfg(test)]
+impl Default for TestGameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
 impl TestGameState {
     pub fn new() -> Self {
+        TestGameState::with_content(GameContent::embedded())
+    }
+
+    pub fn with_content(content: GameContent) -> Self {
+        let clock = ManualClock::new(0.0);
         TestGameState {
-            coins: 0.0,
-            wood: 0.0,
-            stone: 0.0,
-            coins_per_click: 1.0,
-            coins_per_second: 0.0,
-            wood_per_second: 0.0,
-            stone_per_second: 0.0,
-            autoclick_count: 0,
-            total_clicks: 0,
-            upgrades: vec![
-                Upgrade {
-                    name: "Better Click".to_string(),
-                    cost: 10.0,
-                    production_increase: 1.0,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Autoclicker Lv1".to_string(),
-                    cost: 50.0,
-                    production_increase: 1.0,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Lumberjack Efficiency".to_string(),
-                    cost: 20.0,
-                    production_increase: 0.2,
-                    owned: 0,
-                    unlocked: true,
-                },
-                Upgrade {
-                    name: "Stone Mason Skill".to_string(),
-                    cost: 25.0,
-                    production_increase: 0.3,
-                    owned: 0,
-                    unlocked: true,
-                },
-            ],
-            buildings: vec![
-                Building {
-                    name: "Coin Mine".to_string(),
-                    cost: 15.0,
-                    production_rate: 0.1,
-                    count: 0,
-                },
-                Building {
-                    name: "Coin Factory".to_string(),
-                    cost: 100.0,
-                    production_rate: 1.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Coin Corporation".to_string(),
-                    cost: 500.0,
-                    production_rate: 5.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Woodcutter".to_string(),
-                    cost: 20.0,
-                    production_rate: 0.2,
-                    count: 0,
-                },
-                Building {
-                    name: "Lumber Mill".to_string(),
-                    cost: 80.0,
-                    production_rate: 1.5,
-                    count: 0,
-                },
-                Building {
-                    name: "Forest Workshop".to_string(),
-                    cost: 400.0,
-                    production_rate: 4.0,
-                    count: 0,
-                },
-                Building {
-                    name: "Stone Quarry".to_string(),
-                    cost: 25.0,
-                    production_rate: 0.15,
-                    count: 0,
-                },
-                Building {
-                    name: "Rock Crusher".to_string(),
-                    cost: 120.0,
-                    production_rate: 1.2,
-                    count: 0,
-                },
-                Building {
-                    name: "Mason Workshop".to_string(),
-                    cost: 450.0,
-                    production_rate: 4.5,
-                    count: 0,
-                },
-            ],
-            statistics: Statistics {
-                total_clicks: 0,
-                total_coins_earned: 0.0,
-                total_wood_earned: 0.0,
-                total_stone_earned: 0.0,
-                total_resources_crafted: 0,
-                achievements_unlocked_count: 0,
-                play_time_seconds: 0.0,
-                buildings_purchased: 0,
-                upgrades_purchased: 0,
-            },
-            achievements: vec![
-                Achievement {
-                    id: "click_novice_10".to_string(),
-                    name: "点击新手".to_string(),
-                    description: "点击 10 次".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 10.0,
-                    category: "clicks".to_string(),
-                },
-                Achievement {
-                    id: "click_master_100".to_string(),
-                    name: "点击大师".to_string(),
-                    description: "点击 100 次".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 100.0,
-                    category: "clicks".to_string(),
-                },
-                Achievement {
-                    id: "click_legend_1000".to_string(),
-                    name: "点击传奇".to_string(),
-                    description: "点击 1000 次".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 1000.0,
-                    category: "clicks".to_string(),
-                },
-                Achievement {
-                    id: "first_coins_100".to_string(),
-                    name: "第一桶金".to_string(),
-                    description: "获得 100 金币".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 100.0,
-                    category: "resources".to_string(),
-                },
-                Achievement {
-                    id: "wood_collector_1000".to_string(),
-                    name: "木材收集者".to_string(),
-                    description: "获得 1000 木头".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 1000.0,
-                    category: "resources".to_string(),
-                },
-                Achievement {
-                    id: "stone_hoarder_5000".to_string(),
-                    name: "石头囤积者".to_string(),
-                    description: "获得 5000 石头".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 5000.0,
-                    category: "resources".to_string(),
-                },
-                Achievement {
-                    id: "first_building".to_string(),
-                    name: "第一座建筑".to_string(),
-                    description: "购买第一座建筑".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 1.0,
-                    category: "buildings".to_string(),
-                },
-                Achievement {
-                    id: "building_enthusiast_10".to_string(),
-                    name: "建筑爱好者".to_string(),
-                    description: "购买 10 座建筑".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 10.0,
-                    category: "buildings".to_string(),
-                },
-                Achievement {
-                    id: "building_tycoon_50".to_string(),
-                    name: "建筑大亨".to_string(),
-                    description: "购买 50 座建筑".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 50.0,
-                    category: "buildings".to_string(),
-                },
-                Achievement {
-                    id: "first_craft".to_string(),
-                    name: "第一次制作".to_string(),
-                    description: "制作第一个物品".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 1.0,
-                    category: "crafting".to_string(),
-                },
-                Achievement {
-                    id: "craft_master_100".to_string(),
-                    name: "制作大师".to_string(),
-                    description: "制作 100 个物品".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 100.0,
-                    category: "crafting".to_string(),
-                },
-                Achievement {
-                    id: "first_unlock".to_string(),
-                    name: "首次解锁".to_string(),
-                    description: "解锁第一个成就".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 1.0,
-                    category: "unlocks".to_string(),
-                },
-                Achievement {
-                    id: "progress_master_5".to_string(),
-                    name: "进度大师".to_string(),
-                    description: "解锁 5 个成就".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    progress: 0.0,
-                    requirement: 5.0,
-                    category: "unlocks".to_string(),
-                },
-            ],
-            crafting_recipes: vec![
-                CraftingRecipe {
-                    id: "coins_to_wood".to_string(),
-                    name: "金币换木材".to_string(),
-                    input_resource: "coins".to_string(),
-                    input_amount: 100.0,
-                    output_resource: "wood".to_string(),
-                    output_amount: 10.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "wood_to_coins".to_string(),
-                    name: "木材换金币".to_string(),
-                    input_resource: "wood".to_string(),
-                    input_amount: 10.0,
-                    output_resource: "coins".to_string(),
-                    output_amount: 100.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "coins_to_stone".to_string(),
-                    name: "金币换石头".to_string(),
-                    input_resource: "coins".to_string(),
-                    input_amount: 100.0,
-                    output_resource: "stone".to_string(),
-                    output_amount: 1.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "stone_to_coins".to_string(),
-                    name: "石头换金币".to_string(),
-                    input_resource: "stone".to_string(),
-                    input_amount: 1.0,
-                    output_resource: "coins".to_string(),
-                    output_amount: 100.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "wood_to_stone".to_string(),
-                    name: "木材换石头".to_string(),
-                    input_resource: "wood".to_string(),
-                    input_amount: 10.0,
-                    output_resource: "stone".to_string(),
-                    output_amount: 1.0,
-                    unlocked: true,
-                },
-                CraftingRecipe {
-                    id: "stone_to_wood".to_string(),
-                    name: "石头换木材".to_string(),
-                    input_resource: "stone".to_string(),
-                    input_amount: 1.0,
-                    output_resource: "wood".to_string(),
-                    output_amount: 10.0,
-                    unlocked: true,
-                },
-            ],
-            unlocked_features: vec![
-                UnlockedFeature {
-                    id: "workers_tab".to_string(),
-                    name: "工人面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 50.0,
-                },
-                UnlockedFeature {
-                    id: "advanced_buildings".to_string(),
-                    name: "高级建筑".to_string(),
-                    feature_type: "building".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "buildings_owned".to_string(),
-                    requirement_value: 20.0,
-                },
-                UnlockedFeature {
-                    id: "prestige_system".to_string(),
-                    name: "转生系统".to_string(),
-                    feature_type: "mechanic".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_coins".to_string(),
-                    requirement_value: 10000.0,
-                },
-                UnlockedFeature {
-                    id: "statistics_panel".to_string(),
-                    name: "统计面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 10.0,
-                },
-                UnlockedFeature {
-                    id: "achievements_panel".to_string(),
-                    name: "成就面板".to_string(),
-                    feature_type: "area".to_string(),
-                    unlocked: false,
-                    unlock_timestamp: None,
-                    requirement_type: "total_clicks".to_string(),
-                    requirement_value: 25.0,
-                },
-            ],
-            workers: vec![
-                Worker {
-                    name: "矿工".to_string(),
-                    skills: "mining".to_string(),
-                    background: "擅长挖矿的工人".to_string(),
-                    preferences: "Coin Mine".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "伐木工".to_string(),
-                    skills: "logging".to_string(),
-                    background: "擅长伐木的工人".to_string(),
-                    preferences: "Woodcutter".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-                Worker {
-                    name: "石匠".to_string(),
-                    skills: "masonry".to_string(),
-                    background: "擅长采石的工人".to_string(),
-                    preferences: "Stone Quarry".to_string(),
-                    assigned_building: None,
-                    level: 1,
-                    efficiency_multiplier: 1.0,
-                    xp: 0.0,
-                    xp_to_next_level: 100.0,
-                },
-            ],
+            core: GameCore::with_content(Rc::new(clock.clone()), content),
+            clock,
         }
     }
 
-    #[cfg(test)]
     pub fn get_statistics(&self) -> Statistics {
         self.statistics.clone()
     }
 
-    #[cfg(test)]
     pub fn get_achievements(&self) -> Vec<Achievement> {
         self.achievements.clone()
     }
 
-    #[cfg(test)]
     pub fn get_crafting_recipes(&self) -> Vec<CraftingRecipe> {
         self.crafting_recipes.clone()
     }
 
-    #[cfg(test)]
     pub fn get_unlocks(&self) -> Vec<UnlockedFeature> {
         self.unlocked_features.clone()
     }
 
-    #[cfg(test)]
     pub(crate) fn get_workers(&self) -> Vec<Worker> {
         self.workers.clone()
     }
 
-    #[cfg(test)]
-    pub fn assign_worker(&mut self, worker_index: usize, building_id: &str) -> bool {
-        if worker_index >= self.workers.len() {
-            return false;
-        }
-
-        let building_exists = self.buildings.iter().any(|b| b.name == building_id);
-        if !building_exists {
-            return false;
-        }
-
-        self.workers[worker_index].assigned_building = Some(building_id.to_string());
-
-        let preference = &self.workers[worker_index].preferences;
-
-        let mut efficiency = 1.0;
-
-        if preference == building_id {
-            efficiency += 0.2;
-        }
-
-        efficiency += (self.workers[worker_index].level as f64) * 0.05;
-
-        self.workers[worker_index].efficiency_multiplier = efficiency;
-
-        true
-    }
-
-    #[cfg(test)]
-    pub fn check_unlock(&mut self, feature_id: &str) -> bool {
-        let feature = match self
-            .unlocked_features
-            .iter_mut()
-            .find(|f| f.id == feature_id)
-        {
-            Some(f) => f,
-            None => return false,
-        };
-
-        if feature.unlocked {
-            return true;
-        }
-
-        let current_value = match feature.requirement_type.as_str() {
-            "total_clicks" => self.total_clicks as f64,
-            "total_coins" => self.coins,
-            "buildings_owned" => self.statistics.buildings_purchased as f64,
-            _ => 0.0,
-        };
-
-        current_value >= feature.requirement_value
-    }
-
-    #[cfg(test)]
-    pub fn unlock_feature(&mut self, feature_id: &str) -> bool {
-        if !self.check_unlock(feature_id) {
-            return false;
-        }
-
-        let feature = match self
-            .unlocked_features
-            .iter_mut()
-            .find(|f| f.id == feature_id)
-        {
-            Some(f) => f,
-            None => return false,
-        };
-
-        if feature.unlocked {
-            return true;
-        }
-
-        feature.unlocked = true;
-        feature.unlock_timestamp = Some(0.0); // Use 0.0 for tests instead of Date::now()
-        true
-    }
-
-    pub fn craft_resource(&mut self, recipe_id: &str) -> bool {
-        let recipe = match self.crafting_recipes.iter().find(|r| r.id == recipe_id) {
-            Some(r) => r.clone(),
-            None => return false,
-        };
-
-        let input_amount = match recipe.input_resource.as_str() {
-            "coins" => self.coins,
-            "wood" => self.wood,
-            "stone" => self.stone,
-            _ => return false,
-        };
-
-        if input_amount + 1e-10 >= recipe.input_amount {
-            match recipe.input_resource.as_str() {
-                "coins" => self.coins -= recipe.input_amount,
-                "wood" => self.wood -= recipe.input_amount,
-                "stone" => self.stone -= recipe.input_amount,
-                _ => return false,
-            }
-
-            match recipe.output_resource.as_str() {
-                "coins" => self.coins += recipe.output_amount,
-                "wood" => self.wood += recipe.output_amount,
-                "stone" => self.stone += recipe.output_amount,
-                _ => return false,
-            }
-
-            self.statistics.total_resources_crafted += 1;
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn click_action(&mut self) {
-        let earned = self.coins_per_click;
-        self.coins += earned;
-        self.total_clicks += 1;
-        self.statistics.total_clicks += 1;
-        self.statistics.total_coins_earned += earned;
-    }
-
-    pub fn buy_upgrade(&mut self, index: usize) -> bool {
-        if index >= self.upgrades.len() {
-            return false;
-        }
-
-        let upgrade_cost = self.upgrades[index].cost;
-
-        if self.coins >= upgrade_cost {
-            self.coins -= upgrade_cost;
-
-            if self.upgrades[index].name == "Better Click" {
-                self.coins_per_click += self.upgrades[index].production_increase;
-            } else if self.upgrades[index].name.starts_with("Autoclicker") {
-                self.autoclick_count += 1;
-            }
-
-            self.upgrades[index].owned += 1;
-            self.upgrades[index].cost *= 1.5;
-            self.statistics.upgrades_purchased += 1;
-
-            self.update_production();
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn buy_building(&mut self, index: usize) -> bool {
-        if index >= self.buildings.len() {
-            return false;
-        }
-
-        let building_cost = self.buildings[index].cost;
-
-        if self.coins >= building_cost {
-            self.coins -= building_cost;
-            self.buildings[index].count += 1;
-            self.buildings[index].cost *= 1.15;
-            self.statistics.buildings_purchased += 1;
-            self.update_production();
-            true
-        } else {
-            false
-        }
-    }
-
-    fn update_production(&mut self) {
-        let mut total_cps = 0.0;
-        let mut total_wps = 0.0;
-        let mut total_sps = 0.0;
-
-        for building in &self.buildings {
-            match building.name.as_str() {
-                "Coin Mine" | "Coin Factory" | "Coin Corporation" => {
-                    total_cps += building.production_rate * building.count as f64;
-                }
-                "Woodcutter" | "Lumber Mill" | "Forest Workshop" => {
-                    total_wps += building.production_rate * building.count as f64;
-                }
-                "Stone Quarry" | "Rock Crusher" | "Mason Workshop" => {
-                    total_sps += building.production_rate * building.count as f64;
-                }
-                _ => {} // Unknown building type
-            }
-        }
-
-        for upgrade in &self.upgrades {
-            if upgrade.name == "Lumberjack Efficiency" {
-                total_wps += upgrade.production_increase * upgrade.owned as f64;
-            }
-            if upgrade.name == "Stone Mason Skill" {
-                total_sps += upgrade.production_increase * upgrade.owned as f64;
-            }
-        }
-
-        self.coins_per_second = total_cps;
-        self.wood_per_second = total_wps;
-        self.stone_per_second = total_sps;
-    }
-
-    pub fn get_coins(&self) -> f64 {
-        self.coins
-    }
-
-    pub fn get_wood(&self) -> f64 {
-        self.wood
-    }
-
-    pub fn get_stone(&self) -> f64 {
-        self.stone
-    }
-
-    pub fn get_coins_per_second(&self) -> f64 {
-        self.coins_per_second
-    }
-
-    pub fn get_wood_per_second(&self) -> f64 {
-        self.wood_per_second
-    }
-
-    pub fn get_stone_per_second(&self) -> f64 {
-        self.stone_per_second
-    }
-
-    pub fn get_coins_per_click(&self) -> f64 {
-        self.coins_per_click
+    /// Let time pass until every queued craft has completed
+    pub fn finish_crafts(&mut self) {
+        let seconds: f64 = self.crafting_queue.iter().map(CraftingJob::remaining).sum();
+        self.clock.advance_secs(seconds);
+        self.game_loop();
     }
 
-    pub fn check_achievement(&mut self, achievement_id: &str) -> bool {
-        let achievement = match self
-            .achievements
-            .iter_mut()
-            .find(|a| a.id == achievement_id)
-        {
-            Some(a) => a,
-            None => return false,
-        };
-
-        if achievement.unlocked {
-            return true;
-        }
-
-        let current_value = match achievement.category.as_str() {
-            "clicks" => self.total_clicks as f64,
-            "resources" => {
-                if achievement.id == "first_coins_100" {
-                    self.coins
-                } else if achievement.id == "wood_collector_1000" {
-                    self.wood
-                } else if achievement.id == "stone_hoarder_5000" {
-                    self.stone
-                } else {
-                    0.0
-                }
-            }
-            "buildings" => self.statistics.buildings_purchased as f64,
-            "crafting" => self.statistics.total_resources_crafted as f64,
-            "unlocks" => self.statistics.achievements_unlocked_count as f64,
-            _ => 0.0,
-        };
-
-        achievement.progress = current_value;
-
-        if achievement.progress >= achievement.requirement {
-            achievement.unlocked = true;
-            achievement.unlock_timestamp = Some(0.0);
-
-            self.statistics.achievements_unlocked_count += 1;
-
-            self.check_achievement("first_unlock");
-            self.check_achievement("progress_master_5");
-
-            return true;
+    /// Open up everything gated behind a feature
+    pub fn unlock_all_features(&mut self) {
+        for feature in &mut self.unlocked_features {
+            feature.unlocked = true;
         }
-
-        false
     }
+}
 
-    pub fn grant_worker_xp(&mut self, elapsed: f64) {
-        for i in 0..self.workers.len() {
-            if self.workers[i].assigned_building.is_some() {
-                let xp_gain = 10.0 * elapsed;
-                self.workers[i].xp += xp_gain;
-
-                while self.workers[i].xp >= self.workers[i].xp_to_next_level {
-                    self.workers[i].xp -= self.workers[i].xp_to_next_level;
-                    self.workers[i].level += 1;
-                    self.workers[i].xp_to_next_level =
-                        (self.workers[i].xp_to_next_level * 1.5).ceil();
-
-                    let preference = &self.workers[i].preferences;
-                    let assigned = self.workers[i].assigned_building.as_ref().unwrap();
-                    let mut efficiency = 1.0;
+#[cfg(test)]
+impl Deref for TestGameState {
+    type Target = GameCore;
 
-                    if preference == assigned {
-                        efficiency += 0.2;
-                    }
-                    efficiency += (self.workers[i].level as f64) * 0.05;
-                    self.workers[i].efficiency_multiplier = efficiency;
-                }
-            }
-        }
+    fn deref(&self) -> &GameCore {
+        &self.core
     }
+}
 
-    pub fn get_worker_production_bonus(&self, worker_index: usize) -> f64 {
-        if worker_index >= self.workers.len() {
-            return 0.0;
-        }
-
-        let worker = &self.workers[worker_index];
-        if worker.assigned_building.is_none() {
-            return 0.0;
-        }
-
-        worker.efficiency_multiplier - 1.0
+#[cfg(test)]
+impl DerefMut for TestGameState {
+    fn deref_mut(&mut self) -> &mut GameCore {
+        &mut self.core
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::content::ContentError;
+    use crate::core::{
+        Clock, FailureReason, GameEvent, IdleGame, ManualClock, MonotonicClock, PurchaseTarget,
+        SavedGame,
+    };
+    use crate::persistence::export::{self, EXPORT_HEADER};
+    use crate::persistence::{
+        slots, storage::FileStorage, validation, Integrity, LoadMode, MemoryStorage, SaveError,
+        SaveFormat, SaveProblem, SaveStorage, SlotIndex, SlotKind, SlotMetadata, SAVE_VERSION,
+    };
+    use crate::state::{BigNum, Notation};
+    use crate::systems::{Condition, RecipeItem, StatKey};
+    use base64::{engine::general_purpose, Engine as _};
+    use std::rc::Rc;
 
     #[test]
     fn test_initial_state() {
@@ -780,12 +130,12 @@ mod tests {
     fn test_buy_upgrade() {
         let mut game = TestGameState::new();
 
-        assert_eq!(game.buy_upgrade(0), false);
+        assert!(!game.buy_upgrade(0));
         assert_eq!(game.get_coins(), 0.0);
 
-        game.coins = 15.0;
+        game.state.resources.set_amount("coins", 15.0);
 
-        assert_eq!(game.buy_upgrade(0), true);
+        assert!(game.buy_upgrade(0));
         assert_eq!(game.get_coins(), 5.0);
         assert_eq!(game.get_coins_per_click(), 2.0);
     }
@@ -794,12 +144,12 @@ mod tests {
     fn test_buy_building() {
         let mut game = TestGameState::new();
 
-        assert_eq!(game.buy_building(0), false);
+        assert!(!game.buy_building(0));
         assert_eq!(game.get_coins(), 0.0);
 
-        game.coins = 20.0;
+        game.state.resources.set_amount("coins", 20.0);
 
-        assert_eq!(game.buy_building(0), true);
+        assert!(game.buy_building(0));
         assert_eq!(game.get_coins(), 5.0);
         assert_eq!(game.buildings[0].count, 1);
         assert_eq!(game.get_coins_per_second(), 0.1);
@@ -808,22 +158,23 @@ mod tests {
     #[test]
     fn test_update_production() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
-        game.coins = 700.0;
+        game.state.resources.set_amount("coins", 700.0);
         game.buy_building(0);
         game.buy_building(1);
         game.buy_building(2);
 
         assert_eq!(game.get_coins_per_second(), 6.1);
 
-        game.coins = 500.0;
+        game.state.resources.set_amount("coins", 500.0);
         game.buy_building(3);
         game.buy_building(4);
         game.buy_building(5);
 
         assert_eq!(game.get_wood_per_second(), 5.7);
 
-        game.coins = 600.0;
+        game.state.resources.set_amount("coins", 600.0);
         game.buy_building(6);
         game.buy_building(7);
         game.buy_building(8);
@@ -863,7 +214,7 @@ mod tests {
     #[test]
     fn test_statistics_building_tracking() {
         let mut game = TestGameState::new();
-        game.coins = 1000.0;
+        game.state.resources.set_amount("coins", 1000.0);
 
         game.buy_building(0);
         game.buy_building(0);
@@ -876,7 +227,7 @@ mod tests {
     #[test]
     fn test_statistics_upgrade_tracking() {
         let mut game = TestGameState::new();
-        game.coins = 100.0;
+        game.state.resources.set_amount("coins", 100.0);
 
         game.buy_upgrade(0);
         game.buy_upgrade(0);
@@ -892,7 +243,7 @@ mod tests {
 
         assert_eq!(achievements.len(), 13);
         for achievement in &achievements {
-            assert_eq!(achievement.unlocked, false);
+            assert!(!achievement.unlocked);
             assert_eq!(achievement.progress, 0.0);
         }
     }
@@ -906,14 +257,14 @@ mod tests {
         }
 
         let unlocked = game.check_achievement("click_novice_10");
-        assert_eq!(unlocked, true);
+        assert!(unlocked);
 
         let achievements = game.get_achievements();
         let click_novice = achievements
             .iter()
             .find(|a| a.id == "click_novice_10")
             .unwrap();
-        assert_eq!(click_novice.unlocked, true);
+        assert!(click_novice.unlocked);
         assert_eq!(click_novice.progress, 10.0);
     }
 
@@ -926,50 +277,50 @@ mod tests {
         }
 
         let unlocked = game.check_achievement("click_novice_10");
-        assert_eq!(unlocked, false);
+        assert!(!unlocked);
 
         let achievements = game.get_achievements();
         let click_novice = achievements
             .iter()
             .find(|a| a.id == "click_novice_10")
             .unwrap();
-        assert_eq!(click_novice.unlocked, false);
+        assert!(!click_novice.unlocked);
         assert_eq!(click_novice.progress, 5.0);
     }
 
     #[test]
     fn test_check_achievement_first_building() {
         let mut game = TestGameState::new();
-        game.coins = 20.0;
+        game.state.resources.set_amount("coins", 20.0);
 
         game.buy_building(0);
 
         let unlocked = game.check_achievement("first_building");
-        assert_eq!(unlocked, true);
+        assert!(unlocked);
 
         let achievements = game.get_achievements();
         let first_building = achievements
             .iter()
             .find(|a| a.id == "first_building")
             .unwrap();
-        assert_eq!(first_building.unlocked, true);
+        assert!(first_building.unlocked);
         assert_eq!(first_building.progress, 1.0);
     }
 
     #[test]
     fn test_check_achievement_first_coins() {
         let mut game = TestGameState::new();
-        game.coins = 150.0;
+        game.state.resources.set_amount("coins", 150.0);
 
         let unlocked = game.check_achievement("first_coins_100");
-        assert_eq!(unlocked, true);
+        assert!(unlocked);
 
         let achievements = game.get_achievements();
         let first_coins = achievements
             .iter()
             .find(|a| a.id == "first_coins_100")
             .unwrap();
-        assert_eq!(first_coins.unlocked, true);
+        assert!(first_coins.unlocked);
         assert_eq!(first_coins.progress, 150.0);
     }
 
@@ -978,7 +329,7 @@ mod tests {
         let mut game = TestGameState::new();
 
         let unlocked = game.check_achievement("invalid_achievement_id");
-        assert_eq!(unlocked, false);
+        assert!(!unlocked);
     }
 
     #[test]
@@ -990,10 +341,10 @@ mod tests {
         }
 
         let unlocked1 = game.check_achievement("click_novice_10");
-        assert_eq!(unlocked1, true);
+        assert!(unlocked1);
 
         let unlocked2 = game.check_achievement("click_novice_10");
-        assert_eq!(unlocked2, true);
+        assert!(unlocked2);
     }
 
     #[test]
@@ -1001,56 +352,46 @@ mod tests {
         let game = TestGameState::new();
         let recipes = game.get_crafting_recipes();
 
-        assert_eq!(recipes.len(), 6);
+        assert_eq!(recipes.len(), 8);
 
         assert_eq!(recipes[0].id, "coins_to_wood");
-        assert_eq!(recipes[0].input_resource, "coins");
-        assert_eq!(recipes[0].input_amount, 100.0);
-        assert_eq!(recipes[0].output_resource, "wood");
-        assert_eq!(recipes[0].output_amount, 10.0);
+        assert_eq!(recipes[0].inputs, vec![RecipeItem::new("coins", 100.0)]);
+        assert_eq!(recipes[0].outputs, vec![RecipeItem::new("wood", 10.0)]);
 
         assert_eq!(recipes[1].id, "wood_to_coins");
-        assert_eq!(recipes[1].input_resource, "wood");
-        assert_eq!(recipes[1].input_amount, 10.0);
-        assert_eq!(recipes[1].output_resource, "coins");
-        assert_eq!(recipes[1].output_amount, 100.0);
+        assert_eq!(recipes[1].inputs, vec![RecipeItem::new("wood", 10.0)]);
+        assert_eq!(recipes[1].outputs, vec![RecipeItem::new("coins", 100.0)]);
 
         assert_eq!(recipes[2].id, "coins_to_stone");
-        assert_eq!(recipes[2].input_resource, "coins");
-        assert_eq!(recipes[2].input_amount, 100.0);
-        assert_eq!(recipes[2].output_resource, "stone");
-        assert_eq!(recipes[2].output_amount, 1.0);
+        assert_eq!(recipes[2].inputs, vec![RecipeItem::new("coins", 100.0)]);
+        assert_eq!(recipes[2].outputs, vec![RecipeItem::new("stone", 1.0)]);
 
         assert_eq!(recipes[3].id, "stone_to_coins");
-        assert_eq!(recipes[3].input_resource, "stone");
-        assert_eq!(recipes[3].input_amount, 1.0);
-        assert_eq!(recipes[3].output_resource, "coins");
-        assert_eq!(recipes[3].output_amount, 100.0);
+        assert_eq!(recipes[3].inputs, vec![RecipeItem::new("stone", 1.0)]);
+        assert_eq!(recipes[3].outputs, vec![RecipeItem::new("coins", 100.0)]);
 
         assert_eq!(recipes[4].id, "wood_to_stone");
-        assert_eq!(recipes[4].input_resource, "wood");
-        assert_eq!(recipes[4].input_amount, 10.0);
-        assert_eq!(recipes[4].output_resource, "stone");
-        assert_eq!(recipes[4].output_amount, 1.0);
+        assert_eq!(recipes[4].inputs, vec![RecipeItem::new("wood", 10.0)]);
+        assert_eq!(recipes[4].outputs, vec![RecipeItem::new("stone", 1.0)]);
 
         assert_eq!(recipes[5].id, "stone_to_wood");
-        assert_eq!(recipes[5].input_resource, "stone");
-        assert_eq!(recipes[5].input_amount, 1.0);
-        assert_eq!(recipes[5].output_resource, "wood");
-        assert_eq!(recipes[5].output_amount, 10.0);
+        assert_eq!(recipes[5].inputs, vec![RecipeItem::new("stone", 1.0)]);
+        assert_eq!(recipes[5].outputs, vec![RecipeItem::new("wood", 10.0)]);
     }
 
     #[test]
     fn test_craft_resource_success() {
         let mut game = TestGameState::new();
-        game.coins = 200.0;
-        game.wood = 0.0;
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 200.0);
+        game.state.resources.set_amount("wood", 0.0);
 
         let wood_before = game.get_wood();
         let result = game.craft_resource("coins_to_wood");
 
-        assert_eq!(result, true);
+        assert!(result);
         assert_eq!(game.get_coins(), 100.0);
+        game.finish_crafts();
         assert_eq!(game.get_wood(), wood_before + 10.0);
 
         let stats = game.get_statistics();
@@ -1060,14 +401,29 @@ mod tests {
     #[test]
     fn test_craft_resource_insufficient() {
         let mut game = TestGameState::new();
-        game.coins = 50.0;
-        game.wood = 0.0;
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 50.0);
+        game.state.resources.set_amount("wood", 0.0);
+        game.drain_events();
 
         let coins_before = game.get_coins();
         let wood_before = game.get_wood();
         let result = game.craft_resource("coins_to_wood");
 
-        assert_eq!(result, false);
+        assert!(!result);
+        assert_eq!(
+            game.drain_events(),
+            vec![GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Recipe {
+                    id: "coins_to_wood".to_string(),
+                },
+                reason: FailureReason::InsufficientResources {
+                    resource: "coins".to_string(),
+                    required: 100.0.into(),
+                    available: 50.0.into(),
+                },
+            }]
+        );
         assert_eq!(game.get_coins(), coins_before);
         assert_eq!(game.get_wood(), wood_before);
 
@@ -1080,14 +436,17 @@ mod tests {
         let game = TestGameState::new();
         let unlocks = game.get_unlocks();
 
-        assert_eq!(unlocks.len(), 5);
+        assert_eq!(unlocks.len(), 6);
 
         let workers_tab = unlocks.iter().find(|u| u.id == "workers_tab").unwrap();
         assert_eq!(workers_tab.name, "工人面板");
         assert_eq!(workers_tab.feature_type, "area");
-        assert_eq!(workers_tab.unlocked, false);
-        assert_eq!(workers_tab.requirement_type, "total_clicks");
-        assert_eq!(workers_tab.requirement_value, 50.0);
+        assert!(!workers_tab.unlocked);
+        assert_eq!(workers_tab.requirement, 50.0);
+        assert_eq!(
+            workers_tab.condition,
+            Condition::stat(StatKey::Clicks, 50.0)
+        );
 
         let advanced_buildings = unlocks
             .iter()
@@ -1095,23 +454,32 @@ mod tests {
             .unwrap();
         assert_eq!(advanced_buildings.name, "高级建筑");
         assert_eq!(advanced_buildings.feature_type, "building");
-        assert_eq!(advanced_buildings.unlocked, false);
-        assert_eq!(advanced_buildings.requirement_type, "buildings_owned");
-        assert_eq!(advanced_buildings.requirement_value, 20.0);
+        assert!(!advanced_buildings.unlocked);
+        assert_eq!(advanced_buildings.requirement, 20.0);
+        assert_eq!(
+            advanced_buildings.condition,
+            Condition::stat(StatKey::BuildingsPurchased, 20.0)
+        );
 
         let prestige_system = unlocks.iter().find(|u| u.id == "prestige_system").unwrap();
         assert_eq!(prestige_system.name, "转生系统");
         assert_eq!(prestige_system.feature_type, "mechanic");
-        assert_eq!(prestige_system.unlocked, false);
-        assert_eq!(prestige_system.requirement_type, "total_coins");
-        assert_eq!(prestige_system.requirement_value, 10000.0);
+        assert!(!prestige_system.unlocked);
+        assert_eq!(prestige_system.requirement, 10000.0);
+        assert_eq!(
+            prestige_system.condition,
+            Condition::stat(StatKey::Resource("coins".to_string()), 10000.0)
+        );
 
         let statistics_panel = unlocks.iter().find(|u| u.id == "statistics_panel").unwrap();
         assert_eq!(statistics_panel.name, "统计面板");
         assert_eq!(statistics_panel.feature_type, "area");
-        assert_eq!(statistics_panel.unlocked, false);
-        assert_eq!(statistics_panel.requirement_type, "total_clicks");
-        assert_eq!(statistics_panel.requirement_value, 10.0);
+        assert!(!statistics_panel.unlocked);
+        assert_eq!(statistics_panel.requirement, 10.0);
+        assert_eq!(
+            statistics_panel.condition,
+            Condition::stat(StatKey::Clicks, 10.0)
+        );
 
         let achievements_panel = unlocks
             .iter()
@@ -1119,64 +487,68 @@ mod tests {
             .unwrap();
         assert_eq!(achievements_panel.name, "成就面板");
         assert_eq!(achievements_panel.feature_type, "area");
-        assert_eq!(achievements_panel.unlocked, false);
-        assert_eq!(achievements_panel.requirement_type, "total_clicks");
-        assert_eq!(achievements_panel.requirement_value, 25.0);
+        assert!(!achievements_panel.unlocked);
+        assert_eq!(achievements_panel.prerequisites, vec!["statistics_panel"]);
+        assert_eq!(achievements_panel.requirement, 25.0);
+        assert_eq!(
+            achievements_panel.condition,
+            Condition::stat(StatKey::Clicks, 25.0)
+        );
     }
 
     #[test]
     fn test_check_unlock_requirement_met() {
         let mut game = TestGameState::new();
-        game.total_clicks = 60;
+        game.state.total_clicks = 60;
 
         let can_unlock = game.check_unlock("workers_tab");
-        assert_eq!(can_unlock, true);
+        assert!(can_unlock);
     }
 
     #[test]
     fn test_check_unlock_requirement_not_met() {
         let mut game = TestGameState::new();
-        game.total_clicks = 5;
+        game.state.total_clicks = 5;
 
         let can_unlock = game.check_unlock("workers_tab");
-        assert_eq!(can_unlock, false);
+        assert!(!can_unlock);
     }
 
     #[test]
     fn test_unlock_feature_success() {
         let mut game = TestGameState::new();
-        game.total_clicks = 60;
+        game.state.total_clicks = 60;
 
         let unlocks_before = game.get_unlocks();
         let workers_before = unlocks_before
             .iter()
             .find(|u| u.id == "workers_tab")
             .unwrap();
-        assert_eq!(workers_before.unlocked, false);
+        assert!(!workers_before.unlocked);
 
         let result = game.unlock_feature("workers_tab");
-        assert_eq!(result, true);
+        assert!(result);
 
         let unlocks_after = game.get_unlocks();
         let workers_after = unlocks_after
             .iter()
             .find(|u| u.id == "workers_tab")
             .unwrap();
-        assert_eq!(workers_after.unlocked, true);
+        assert!(workers_after.unlocked);
         assert!(workers_after.unlock_timestamp.is_some());
     }
 
     #[test]
     fn test_unlock_feature_requirement_not_met() {
         let mut game = TestGameState::new();
-        game.total_clicks = 5;
+        game.state.total_clicks = 5;
 
         let result = game.unlock_feature("workers_tab");
-        assert_eq!(result, false);
+        assert!(!result);
 
         let unlocks = game.get_unlocks();
         let workers = unlocks.iter().find(|u| u.id == "workers_tab").unwrap();
-        assert_eq!(workers.unlocked, false);
+        assert!(!workers.unlocked);
     }
 
     #[test]
@@ -1185,24 +557,24 @@ mod tests {
         game.statistics.buildings_purchased = 25;
 
         let can_unlock = game.check_unlock("advanced_buildings");
-        assert_eq!(can_unlock, true);
+        assert!(can_unlock);
 
         game.statistics.buildings_purchased = 10;
         let can_unlock_again = game.check_unlock("advanced_buildings");
-        assert_eq!(can_unlock_again, false);
+        assert!(!can_unlock_again);
     }
 
     #[test]
     fn test_check_unlock_coins_requirement() {
         let mut game = TestGameState::new();
-        game.coins = 15000.0;
+        game.state.resources.set_amount("coins", 15000.0);
 
         let can_unlock = game.check_unlock("prestige_system");
-        assert_eq!(can_unlock, true);
+        assert!(can_unlock);
 
-        game.coins = 5000.0;
+        game.state.resources.set_amount("coins", 5000.0);
         let can_unlock_again = game.check_unlock("prestige_system");
-        assert_eq!(can_unlock_again, false);
+        assert!(!can_unlock_again);
     }
 
     #[test]
@@ -1210,10 +582,10 @@ mod tests {
         let mut game = TestGameState::new();
 
         let result = game.unlock_feature("invalid_feature_id");
-        assert_eq!(result, false);
+        assert!(!result);
 
         let can_unlock = game.check_unlock("invalid_feature_id");
-        assert_eq!(can_unlock, false);
+        assert!(!can_unlock);
     }
 
     #[test]
@@ -1221,7 +593,7 @@ mod tests {
         let game = TestGameState::new();
         let workers = game.get_workers();
 
-        assert_eq!(workers.len(), 3);
+        assert_eq!(workers.len(), 5);
 
         let miner = &workers[0];
         assert_eq!(miner.name, "矿工");
@@ -1233,32 +605,38 @@ mod tests {
         let lumberjack = &workers[1];
         assert_eq!(lumberjack.name, "伐木工");
         assert_eq!(lumberjack.skills, "logging");
-        assert_eq!(lumberjack.preferences, "Woodcutter");
+        assert_eq!(lumberjack.preferences, "woodcutter");
 
         let mason = &workers[2];
         assert_eq!(mason.name, "石匠");
         assert_eq!(mason.skills, "masonry");
-        assert_eq!(mason.preferences, "Stone Quarry");
+        assert_eq!(mason.preferences, "stone_quarry");
+
+        assert_eq!(workers[3].preferences, "coin_factory");
+        assert_eq!(workers[4].preferences, "mason_workshop");
     }
 
     #[test]
     fn test_assign_worker_success() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         let result = game.assign_worker(0, "Coin Mine");
-        assert_eq!(result, true);
+        assert!(result);
 
         let workers = game.get_workers();
         let worker = &workers[0];
-        assert_eq!(worker.assigned_building, Some("Coin Mine".to_string()));
+        // Buildings are referenced by id whichever way they were named
+        assert_eq!(worker.assigned_building, Some("coin_mine".to_string()));
     }
 
     #[test]
     fn test_assign_worker_with_bonus() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         let result = game.assign_worker(0, "Coin Mine");
-        assert_eq!(result, true);
+        assert!(result);
 
         let workers = game.get_workers();
         let worker = &workers[0];
@@ -1270,24 +648,42 @@ mod tests {
         let mut game = TestGameState::new();
 
         let result = game.assign_worker(99, "Coin Mine");
-        assert_eq!(result, false);
+        assert!(!result);
+        assert_eq!(
+            game.drain_events().last(),
+            Some(&GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Worker { index: 99 },
+                reason: FailureReason::NotFound,
+            })
+        );
     }
 
     #[test]
     fn test_assign_worker_invalid_building() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         let result = game.assign_worker(0, "Invalid Building");
-        assert_eq!(result, false);
+        assert!(!result);
+        assert_eq!(
+            game.drain_events().last(),
+            Some(&GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Worker { index: 0 },
+                reason: FailureReason::UnknownBuilding {
+                    building: "Invalid Building".to_string(),
+                },
+            })
+        );
     }
 
     #[test]
     fn test_worker_level_bonus() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         game.workers[0].level = 5;
         let result = game.assign_worker(0, "Coin Mine");
-        assert_eq!(result, true);
+        assert!(result);
 
         let workers = game.get_workers();
         let worker = &workers[0];
@@ -1297,9 +693,10 @@ mod tests {
     #[test]
     fn test_worker_xp_gain() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         let result = game.assign_worker(0, "Coin Mine");
-        assert_eq!(result, true);
+        assert!(result);
 
         let workers_before = game.get_workers();
         let xp_before = workers_before[0].xp;
@@ -1314,9 +711,10 @@ mod tests {
     #[test]
     fn test_worker_level_up() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         let result = game.assign_worker(0, "Coin Mine");
-        assert_eq!(result, true);
+        assert!(result);
 
         game.grant_worker_xp(10.0);
 
@@ -1330,6 +728,7 @@ mod tests {
     #[test]
     fn test_worker_multiple_level_ups() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         game.assign_worker(0, "Coin Mine");
 
@@ -1357,6 +756,7 @@ mod tests {
     #[test]
     fn test_get_worker_production_bonus() {
         let mut game = TestGameState::new();
+        game.unlock_all_features();
 
         game.assign_worker(0, "Coin Mine");
 
@@ -1366,4 +766,2719 @@ mod tests {
         let unassigned_bonus = game.get_worker_production_bonus(1);
         assert_eq!(unassigned_bonus, 0.0);
     }
+
+    #[test]
+    fn test_manual_clock_advance() {
+        let clock = ManualClock::new(1_000.0);
+        let handle = clock.clone();
+
+        handle.advance(500.0);
+        assert_eq!(clock.now(), 1_500.0);
+
+        handle.advance_secs(2.0);
+        assert_eq!(clock.now(), 3_500.0);
+
+        handle.set(10.0);
+        assert_eq!(clock.now(), 10.0);
+    }
+
+    #[test]
+    fn test_monotonic_clock_never_goes_backwards() {
+        let inner = ManualClock::new(5_000.0);
+        let clock = MonotonicClock::new(inner.clone());
+        assert_eq!(clock.now(), 5_000.0);
+
+        inner.set(1_000.0);
+        assert_eq!(clock.now(), 5_000.0);
+
+        inner.set(6_000.0);
+        assert_eq!(clock.now(), 6_000.0);
+    }
+
+    #[test]
+    fn test_idle_game_loop_uses_injected_clock() {
+        let clock = ManualClock::new(1_000_000.0);
+        let mut game = IdleGame::with_clock(Rc::new(clock.clone()));
+
+        for _ in 0..15 {
+            game.click_action();
+        }
+        assert!(game.buy_building(0));
+        assert_eq!(game.get_coins(), 0.0);
+
+        clock.advance_secs(10.0);
+        game.game_loop();
+        assert!((game.get_coins() - 1.0).abs() < 1e-9);
+        assert_eq!(game.save_game().state.last_update_time, 1_010_000.0);
+
+        // No time passed, nothing produced
+        game.game_loop();
+        assert!((game.get_coins() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_idle_game_timestamps_come_from_clock() {
+        let clock = ManualClock::new(42_000.0);
+        let mut game = IdleGame::with_clock(Rc::new(clock.clone()));
+
+        clock.advance_secs(3.0);
+        for _ in 0..10 {
+            game.click_action();
+        }
+
+        let saved = game.save_game();
+        assert_eq!(saved.save_timestamp, 45_000.0);
+        let click_novice = saved
+            .achievements
+            .iter()
+            .find(|a| a.id == "click_novice_10")
+            .unwrap();
+        assert!(click_novice.unlocked);
+        assert_eq!(click_novice.unlock_timestamp, Some(45_000.0));
+    }
+
+    #[test]
+    fn test_game_loop_produces_resources() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        game.buy_building(0);
+
+        game.clock.advance_secs(10.0);
+        game.game_loop();
+
+        assert!((game.get_coins() - 1.0).abs() < 1e-9);
+        assert_eq!(game.statistics.play_time_seconds, 10.0);
+    }
+
+    #[test]
+    fn test_game_loop_autoclicker() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 50.0);
+        assert!(game.buy_upgrade(1));
+        assert_eq!(game.state.autoclick_count, 1);
+
+        game.clock.advance_secs(2.0);
+        game.game_loop();
+
+        // 1 autoclicker * 10 clicks/s * 1 coin/click * 2s
+        assert_eq!(game.get_coins(), 20.0);
+    }
+
+    #[test]
+    fn test_game_loop_grants_worker_xp() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.assign_worker(0, "Coin Mine");
+
+        game.clock.advance_secs(10.0);
+        game.game_loop();
+
+        assert_eq!(game.workers[0].level, 2);
+    }
+
+    #[test]
+    fn test_reset_game_restores_initial_values() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 1000.0);
+        game.buy_building(0);
+        game.buy_upgrade(0);
+        game.assign_worker(0, "Coin Mine");
+
+        game.reset_game();
+
+        assert_eq!(game.get_coins(), 0.0);
+        assert_eq!(game.get_coins_per_click(), 1.0);
+        assert_eq!(game.buildings[0].count, 0);
+        assert_eq!(game.buildings[0].cost, 15.0);
+        assert_eq!(game.upgrades[0].owned, 0);
+        assert_eq!(game.upgrades[0].cost, 10.0);
+        assert_eq!(game.workers[0].assigned_building, None);
+        assert_eq!(game.statistics.buildings_purchased, 0);
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 200.0);
+        assert!(game.buy_building(3));
+        assert!(game.craft_resource("coins_to_stone"));
+
+        let json = serde_json::to_string(&game.save_game()).unwrap();
+
+        let mut restored = TestGameState::new();
+        restored.load_game(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(restored.get_coins(), game.get_coins());
+        assert_eq!(restored.crafting_queue, game.crafting_queue);
+        restored.finish_crafts();
+        assert_eq!(restored.get_stone(), 1.0);
+        assert_eq!(restored.buildings[3].count, 1);
+        assert_eq!(restored.statistics.total_resources_crafted, 1);
+    }
+
+    #[test]
+    fn test_offline_progress_applies_efficiency() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        game.buy_building(0);
+
+        // One hour away: the first five minutes at full rate, the rest at
+        // the default 50% efficiency
+        game.clock.advance_secs(3600.0);
+        game.game_loop();
+
+        assert!((game.get_coins() - 195.0).abs() < 1e-6);
+        assert_eq!(game.state.last_update_time, 3_600_000.0);
+        assert_eq!(game.statistics.play_time_seconds, 3600.0);
+
+        let report = game.take_offline_report().unwrap();
+        assert_eq!(report.duration_seconds, 3600.0);
+        assert_eq!(report.credited_seconds, 3600.0);
+        assert!((report.resources_gained["coins"] - 195.0).abs() < 1e-6);
+        assert_eq!(report.resources_gained["wood"], 0.0);
+        assert!(report
+            .achievements_unlocked
+            .contains(&"first_coins_100".to_string()));
+
+        assert!(game.take_offline_report().is_none());
+    }
+
+    #[test]
+    fn test_offline_progress_respects_cap() {
+        let mut game = TestGameState::new();
+        game.offline_config.max_seconds = 1000.0;
+        game.offline_config.efficiency = 1.0;
+        game.state.resources.set_amount("coins", 20.0);
+        game.buy_building(3);
+
+        game.clock.advance_secs(100_000.0);
+        game.game_loop();
+
+        let report = game.take_offline_report().unwrap();
+        assert_eq!(report.duration_seconds, 100_000.0);
+        assert_eq!(report.credited_seconds, 1000.0);
+        assert!((game.get_wood() - 200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_offline_progress_levels_workers() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.offline_config.efficiency = 1.0;
+        game.assign_worker(1, "Woodcutter");
+
+        game.clock.advance_secs(600.0);
+        game.game_loop();
+
+        let report = game.take_offline_report().unwrap();
+        assert_eq!(report.worker_levels_gained.len(), 1);
+        assert_eq!(report.worker_levels_gained[0].worker, "伐木工");
+        assert_eq!(
+            report.worker_levels_gained[0].levels,
+            game.workers[1].level - 1
+        );
+        assert!(game.workers[1].level > 1);
+    }
+
+    #[test]
+    fn test_short_gap_is_not_offline() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        game.buy_building(0);
+
+        game.clock.advance_secs(100.0);
+        game.game_loop();
+
+        assert!((game.get_coins() - 10.0).abs() < 1e-6);
+        assert!(game.take_offline_report().is_none());
+    }
+
+    #[test]
+    fn test_offline_reports_merge_until_taken() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        game.buy_building(0);
+
+        game.clock.advance_secs(1000.0);
+        game.game_loop();
+        game.clock.advance_secs(1000.0);
+        game.game_loop();
+
+        let report = game.take_offline_report().unwrap();
+        assert_eq!(report.duration_seconds, 2000.0);
+        assert!((report.resources_gained["coins"] - 130.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_events_click_reports_resources() {
+        let mut game = TestGameState::new();
+        game.click_action();
+
+        let events = game.drain_events();
+        assert!(events.contains(&GameEvent::ResourceChanged {
+            resource: "coins".to_string(),
+            amount: 1.0.into(),
+            per_second: 0.0,
+        }));
+        assert!(game.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_events_resource_changes_coalesce() {
+        let mut game = TestGameState::new();
+        game.drain_events();
+
+        for _ in 0..5 {
+            game.click_action();
+        }
+
+        let coin_events: Vec<GameEvent> = game
+            .drain_events()
+            .into_iter()
+            .filter(
+                |e| matches!(e, GameEvent::ResourceChanged { resource, .. } if resource == "coins"),
+            )
+            .collect();
+        assert_eq!(
+            coin_events,
+            vec![GameEvent::ResourceChanged {
+                resource: "coins".to_string(),
+                amount: 5.0.into(),
+                per_second: 0.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_events_building_bought_and_achievement() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        game.buy_building(0);
+
+        let events = game.drain_events();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            GameEvent::BuildingBought {
+                index: 0,
+                count: 1,
+                ..
+            }
+        )));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            GameEvent::AchievementUnlocked { id, .. } if id == "first_building"
+        )));
+    }
+
+    #[test]
+    fn test_events_purchase_failed_reason() {
+        let mut game = TestGameState::new();
+        assert!(!game.buy_upgrade(0));
+        assert!(!game.buy_building(99));
+
+        let events = game.drain_events();
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Upgrade { index: 0 },
+                    reason: FailureReason::InsufficientResources {
+                        resource: "coins".to_string(),
+                        required: 10.0.into(),
+                        available: 0.0.into(),
+                    },
+                },
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Building { index: 99 },
+                    reason: FailureReason::NotFound,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_crafted_feature_and_worker_level() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.unlock_feature("unlock_crafting"));
+        assert!(game.craft_resource("coins_to_wood"));
+        game.finish_crafts();
+
+        game.state.total_clicks = 60;
+        assert!(game.unlock_feature("workers_tab"));
+
+        game.assign_worker(0, "Coin Mine");
+        game.grant_worker_xp(10.0);
+
+        let events = game.drain_events();
+        assert!(events.contains(&GameEvent::Crafted {
+            recipe_id: "coins_to_wood".to_string(),
+            count: 1,
+            bonus: Vec::new(),
+        }));
+        assert!(events.contains(&GameEvent::FeatureUnlocked {
+            id: "workers_tab".to_string(),
+            name: "工人面板".to_string(),
+        }));
+        assert!(events.contains(&GameEvent::WorkerLeveledUp {
+            worker_index: 0,
+            name: "矿工".to_string(),
+            level: 2,
+        }));
+    }
+
+    #[test]
+    fn test_embedded_content_matches_game() {
+        let content = GameContent::embedded();
+        assert_eq!(content.buildings.len(), 10);
+        assert_eq!(content.upgrades.len(), 4);
+        assert_eq!(content.achievements.len(), 13);
+        assert_eq!(content.recipes.len(), 8);
+        assert_eq!(content.workers.len(), 5);
+        assert_eq!(content.unlocks.len(), 6);
+
+        let game = TestGameState::new();
+        assert_eq!(game.buildings[8].id, "mason_workshop");
+        assert_eq!(game.buildings[8].cost, 450.0);
+        assert_eq!(game.upgrades[0].id, "better_click");
+        assert_eq!(game.workers[0].id, "miner");
+        assert_eq!(
+            game.content()
+                .building("coin_mine")
+                .unwrap()
+                .cost_multiplier,
+            1.15
+        );
+    }
+
+    #[test]
+    fn test_content_override_replaces_and_appends_by_id() {
+        let json = r#"{
+            "buildings": [
+                {"id": "coin_mine", "name": "Coin Mine", "base_cost": 5.0, "cost_multiplier": 2.0,
+                 "outputs": [{"resource": "coins", "rate": 0.5}]},
+                {"id": "gold_vault", "name": "Gold Vault", "base_cost": 1000.0,
+                 "outputs": [{"resource": "coins", "rate": 20.0}]}
+            ]
+        }"#;
+        let content = GameContent::embedded_with_override(json).unwrap();
+        assert_eq!(content.buildings.len(), 11);
+        assert_eq!(content.buildings[0].base_cost, 5.0);
+        assert_eq!(content.buildings[10].id, "gold_vault");
+        assert_eq!(content.buildings[10].cost_multiplier, 1.15);
+        // Kinds absent from the override are left alone
+        assert_eq!(content.upgrades.len(), 4);
+
+        let mut game = TestGameState::with_content(content);
+        game.state.resources.set_amount("coins", 5.0);
+        assert!(game.buy_building(0));
+        assert_eq!(game.buildings[0].cost, 10.0);
+        assert_eq!(game.buildings[10].name, "Gold Vault");
+    }
+
+    #[test]
+    fn test_content_rejects_bad_definitions() {
+        assert!(matches!(
+            GameContent::from_json("not json"),
+            Err(ContentError::Parse(_))
+        ));
+
+        let duplicate = r#"{"workers": [
+            {"id": "miner", "name": "a", "skills": "", "background": "", "preferences": ""},
+            {"id": "miner", "name": "b", "skills": "", "background": "", "preferences": ""}
+        ]}"#;
+        assert_eq!(
+            GameContent::from_json(duplicate),
+            Err(ContentError::DuplicateId {
+                kind: "worker",
+                id: "miner".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_reset_derives_costs_from_content() {
+        let json = r#"{"upgrades": [
+            {"id": "better_click", "name": "Better Click", "base_cost": 3.0,
+             "effects": [{"type": "click_bonus", "amount": 1.0}]}
+        ]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 10_000.0);
+        assert!(game.buy_upgrade(0));
+        assert!(game.buy_building(2));
+        assert!(game.buy_building(8));
+
+        game.reset_game();
+
+        assert_eq!(game.upgrades[0].cost, 3.0);
+        assert_eq!(game.upgrades[0].owned, 0);
+        assert_eq!(game.buildings[2].cost, 500.0);
+        assert_eq!(game.buildings[8].cost, 450.0);
+        assert_eq!(game.buildings[8].count, 0);
+        assert_eq!(game.state.coins_per_click, 1.0);
+    }
+
+    #[test]
+    fn test_load_fills_ids_missing_from_old_saves() {
+        let mut game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        for kind in ["buildings", "upgrades", "workers"] {
+            for entry in json[kind].as_array_mut().unwrap() {
+                entry.as_object_mut().unwrap().remove("id");
+            }
+        }
+        let saved: SavedGame = serde_json::from_value(json).unwrap();
+        assert_eq!(saved.buildings[3].id, "");
+
+        game.load_game(saved);
+        assert_eq!(game.buildings[3].id, "woodcutter");
+        assert_eq!(game.upgrades[1].id, "autoclicker_lv1");
+        assert_eq!(game.workers[4].id, "artisan");
+    }
+
+    #[test]
+    fn test_resource_registry_tracks_lifetime_earned() {
+        let mut game = TestGameState::new();
+        for _ in 0..3 {
+            game.click_action();
+        }
+        game.state.resources.set_amount("coins", 15.0);
+        assert!(game.buy_building(0));
+
+        let coins = game.state.resources.get("coins").unwrap();
+        assert_eq!(coins.name, "金币");
+        assert_eq!(coins.lifetime_earned, 3.0);
+        assert!(coins.amount.abs() < 1e-9);
+        assert_eq!(game.get_resource_rate("coins"), 0.1);
+
+        game.clock.advance_secs(10.0);
+        game.game_loop();
+        let coins = game.state.resources.get("coins").unwrap();
+        assert!((coins.lifetime_earned - 4.0).abs() < 1e-9);
+        assert_eq!(game.get_resource("gems"), 0.0);
+        assert_eq!(game.get_resource_rate("gems"), 0.0);
+    }
+
+    #[test]
+    fn test_content_adds_new_resource() {
+        let json = r#"{
+            "resources": [{"id": "iron", "name": "铁", "icon": "⛓️"}],
+            "recipes": [{
+                "id": "stone_to_iron", "name": "炼铁",
+                "inputs": [{"resource": "stone", "amount": 5.0}],
+                "outputs": [{"resource": "iron", "amount": 1.0}]
+            }]
+        }"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        assert_eq!(game.state.resources.len(), 5);
+        game.state.resources.set_amount("stone", 12.0);
+
+        assert!(game.craft_resource("stone_to_iron"));
+        assert!(game.craft_resource("stone_to_iron"));
+        assert!(!game.craft_resource("stone_to_iron"));
+        assert_eq!(game.get_resource("iron"), 2.0);
+        assert_eq!(game.get_stone(), 2.0);
+
+        let events = game.drain_events();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            GameEvent::ResourceChanged { resource, amount, .. } if resource == "iron" && *amount == 2.0
+        )));
+    }
+
+    #[test]
+    fn test_load_legacy_save_with_fixed_resource_fields() {
+        let mut game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        json.as_object_mut().unwrap().remove("save_version");
+        let state = json["state"].as_object_mut().unwrap();
+        state.remove("resources");
+        state.insert("coins".to_string(), serde_json::json!(123.0));
+        state.insert("wood".to_string(), serde_json::json!(45.0));
+        state.insert("stone".to_string(), serde_json::json!(6.0));
+        state.insert("coins_per_second".to_string(), serde_json::json!(0.0));
+
+        let saved = SavedGame::from_json(&json.to_string()).unwrap();
+        game.load_game(saved);
+
+        assert_eq!(game.get_coins(), 123.0);
+        assert_eq!(game.get_wood(), 45.0);
+        assert_eq!(game.get_stone(), 6.0);
+        assert_eq!(game.state.resources.get("wood").unwrap().name, "木头");
+    }
+
+    #[test]
+    fn test_renamed_building_keeps_producing() {
+        let json = r#"{"buildings": [
+            {"id": "woodcutter", "name": "伐木场", "base_cost": 20.0,
+             "outputs": [{"resource": "wood", "rate": 0.2}, {"resource": "coins", "rate": 0.05}]}
+        ]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 20.0);
+        assert!(game.buy_building(3));
+        assert!(game.assign_worker(1, "woodcutter"));
+
+        // Preferred building: 1 + 0.2 + 0.05 per level
+        assert!((game.get_wood_per_second() - 0.2 * 1.25).abs() < 1e-9);
+        assert!((game.get_coins_per_second() - 0.05 * 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_building_inputs_are_consumed_and_gate_output() {
+        let json = r#"{
+            "resources": [{"id": "planks", "name": "木板"}],
+            "buildings": [{"id": "sawmill", "name": "Sawmill", "base_cost": 10.0,
+                "inputs": [{"resource": "wood", "rate": 2.0}],
+                "outputs": [{"resource": "planks", "rate": 1.0}]}]
+        }"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        let sawmill = game
+            .buildings
+            .iter()
+            .position(|b| b.id == "sawmill")
+            .unwrap();
+        game.state.resources.set_amount("coins", 10.0);
+        assert!(game.buy_building(sawmill));
+
+        // No wood in stock, so the sawmill is idle
+        assert_eq!(game.get_resource_rate("planks"), 0.0);
+
+        game.state.resources.set_amount("wood", 10.0);
+        game.update_production();
+        assert_eq!(game.get_resource_rate("planks"), 1.0);
+        assert_eq!(game.get_wood_per_second(), -2.0);
+
+        game.clock.advance_secs(3.0);
+        game.game_loop();
+        assert_eq!(game.get_resource("planks"), 3.0);
+        assert_eq!(game.get_wood(), 4.0);
+    }
+
+    #[test]
+    fn test_content_rejects_unknown_resource() {
+        let json = r#"{"buildings": [{"id": "mine", "name": "Mine", "base_cost": 1.0,
+            "outputs": [{"resource": "mithril", "rate": 1.0}]}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownResource {
+                kind: "building",
+                id: "mine".to_string(),
+                resource: "mithril".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_converts_worker_building_names_to_ids() {
+        let mut game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        let worker = json["workers"][0].as_object_mut().unwrap();
+        worker.insert("preferences".to_string(), serde_json::json!("Coin Mine"));
+        worker.insert(
+            "assigned_building".to_string(),
+            serde_json::json!("Coin Mine"),
+        );
+        let building = json["buildings"][0].as_object_mut().unwrap();
+        building.remove("outputs");
+        building.insert("production_rate".to_string(), serde_json::json!(0.1));
+        building.insert("count".to_string(), serde_json::json!(2));
+
+        game.load_game(SavedGame::from_json(&json.to_string()).unwrap());
+        game.update_production();
+
+        assert_eq!(game.workers[0].preferences, "coin_mine");
+        assert_eq!(
+            game.workers[0].assigned_building,
+            Some("coin_mine".to_string())
+        );
+        assert!((game.get_coins_per_second() - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_upgrade_effects_apply_through_modifiers() {
+        let json = r#"{"upgrades": [
+            {"id": "double_click", "name": "Double Click", "base_cost": 1.0,
+             "effects": [{"type": "click_multiplier", "bonus": 1.0}]},
+            {"id": "mine_boost", "name": "Mine Boost", "base_cost": 1.0,
+             "effects": [{"type": "building_multiplier", "building": "coin_mine", "bonus": 0.5}]},
+            {"id": "global_boost", "name": "Global Boost", "base_cost": 1.0,
+             "effects": [{"type": "global_multiplier", "bonus": 1.0}]},
+            {"id": "discount", "name": "Discount", "base_cost": 1.0,
+             "effects": [{"type": "cost_reduction", "fraction": 0.2}]},
+            {"id": "mentoring", "name": "Mentoring", "base_cost": 1.0,
+             "effects": [{"type": "worker_xp_multiplier", "bonus": 1.0}]}
+        ]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.unlock_all_features();
+        // Overrides are appended after the four built-in upgrades
+        assert_eq!(game.upgrades[8].id, "mentoring");
+        game.state.resources.set_amount("coins", 1000.0);
+
+        assert!(game.buy_upgrade(4));
+        assert_eq!(game.get_coins_per_click(), 2.0);
+        assert!(game.buy_upgrade(0)); // Better Click: (1 + 1) * 2
+        assert_eq!(game.get_coins_per_click(), 4.0);
+
+        assert!(game.buy_building(0));
+        assert!(game.buy_upgrade(5));
+        assert!((game.get_coins_per_second() - 0.15).abs() < 1e-9);
+
+        assert!(game.buy_upgrade(6));
+        assert!((game.get_coins_per_second() - 0.3).abs() < 1e-9);
+        assert_eq!(game.get_coins_per_click(), 8.0);
+
+        let full_price = game.buildings[1].cost;
+        assert!(game.buy_upgrade(7));
+        assert!((game.building_price(1) - full_price * 0.8).abs() < 1e-9);
+        assert_eq!(game.priced_buildings()[1].cost, game.building_price(1));
+        let coins_before = game.get_coins();
+        assert!(game.buy_building(1));
+        assert!((coins_before - game.get_coins() - (full_price * 0.8).to_f64()).abs() < 1e-9);
+
+        assert!(game.buy_upgrade(8));
+        game.assign_worker(0, "coin_mine");
+        game.grant_worker_xp(1.0);
+        assert_eq!(game.workers[0].xp, 20.0);
+    }
+
+    #[test]
+    fn test_autoclicker_and_flat_rate_effects() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.buy_upgrade(1));
+        assert!(game.buy_upgrade(2));
+        assert_eq!(game.state.autoclick_count, 1);
+        assert_eq!(game.modifiers().resource_rates.get("wood"), Some(&0.2));
+        assert_eq!(game.get_wood_per_second(), 0.2);
+
+        // Click power and autoclickers are derived from owned upgrades on load
+        let saved = game.save_game();
+        let mut loaded = TestGameState::new();
+        loaded.load_game(saved);
+        assert_eq!(loaded.state.autoclick_count, 1);
+        assert_eq!(loaded.get_wood_per_second(), 0.2);
+    }
+
+    #[test]
+    fn test_content_rejects_unknown_building_in_effect() {
+        let json = r#"{"upgrades": [{"id": "x", "name": "X", "base_cost": 1.0,
+            "effects": [{"type": "building_multiplier", "building": "castle", "bonus": 1.0}]}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownBuilding {
+                kind: "upgrade",
+                id: "x".to_string(),
+                building: "castle".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_big_num_grows_past_f64() {
+        let huge = BigNum::from_f64(f64::MAX);
+        let doubled = huge + huge;
+        assert!(doubled > huge);
+        assert_eq!(doubled.to_f64(), f64::MAX);
+
+        let googol_squared = BigNum::new(1.0, 100) * BigNum::new(1.0, 100);
+        assert_eq!(googol_squared, BigNum::new(1.0, 200));
+        let tiny_gain = BigNum::new(1.0, 400) + 1.0;
+        assert_eq!(tiny_gain, BigNum::new(1.0, 400));
+        assert_eq!(
+            BigNum::new(3.0, 500) - BigNum::new(2.0, 500),
+            BigNum::new(1.0, 500)
+        );
+        assert_eq!(
+            BigNum::new(1.0, 500) / BigNum::new(1.0, 450),
+            BigNum::new(1.0, 50)
+        );
+    }
+
+    #[test]
+    fn test_big_num_small_values_match_f64() {
+        let sum = BigNum::from_f64(0.1) + 0.2;
+        assert_eq!(sum.to_f64(), 0.1 + 0.2);
+        assert_eq!((BigNum::from_f64(15.0) * 1.15).to_f64(), 15.0 * 1.15);
+        assert_eq!(BigNum::from_f64(f64::NAN), BigNum::ZERO);
+    }
+
+    #[test]
+    fn test_big_num_ordering() {
+        let mut values = vec![
+            BigNum::new(-2.0, 300),
+            BigNum::new(5.0, 150),
+            BigNum::from_f64(-3.0),
+            BigNum::ZERO,
+            BigNum::new(1.0, 400),
+            BigNum::from_f64(42.0),
+        ];
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            values,
+            vec![
+                BigNum::new(-2.0, 300),
+                BigNum::from_f64(-3.0),
+                BigNum::ZERO,
+                BigNum::from_f64(42.0),
+                BigNum::new(5.0, 150),
+                BigNum::new(1.0, 400),
+            ]
+        );
+        assert!(BigNum::new(1.0, 400) > 1e300);
+    }
+
+    #[test]
+    fn test_big_num_serde() {
+        // Plain values stay JSON numbers so older saves remain readable
+        assert_eq!(
+            serde_json::to_string(&BigNum::from_f64(12.5)).unwrap(),
+            "12.5"
+        );
+        assert_eq!(
+            serde_json::to_string(&BigNum::new(1.5, 300)).unwrap(),
+            "\"1.5e300\""
+        );
+
+        let parsed: BigNum = serde_json::from_str("\"2.5e1000\"").unwrap();
+        assert_eq!(parsed, BigNum::new(2.5, 1000));
+        let legacy: BigNum = serde_json::from_str("42").unwrap();
+        assert_eq!(legacy, 42.0);
+        assert!(serde_json::from_str::<BigNum>("\"lots\"").is_err());
+    }
+
+    #[test]
+    fn test_big_num_formatting() {
+        assert_eq!(BigNum::from_f64(0.0).format(Notation::Short), "0");
+        assert_eq!(BigNum::from_f64(12.345).format(Notation::Short), "12.35");
+        assert_eq!(BigNum::from_f64(999.0).format(Notation::Short), "999");
+        assert_eq!(BigNum::from_f64(1234.0).format(Notation::Short), "1.23K");
+        assert_eq!(
+            BigNum::from_f64(45_600_000.0).format(Notation::Short),
+            "45.6M"
+        );
+        assert_eq!(BigNum::from_f64(999_999.0).format(Notation::Short), "1.00M");
+        assert_eq!(BigNum::from_f64(-2500.0).format(Notation::Short), "-2.50K");
+        assert_eq!(BigNum::new(1.5, 300).format(Notation::Short), "1.50e300");
+
+        assert_eq!(
+            BigNum::from_f64(1234.0).format(Notation::Scientific),
+            "1.23e3"
+        );
+        assert_eq!(
+            BigNum::new(1.234, 1000).format(Notation::Scientific),
+            "1.23e1000"
+        );
+        assert_eq!(
+            BigNum::from_f64(123_456.0).format(Notation::Engineering),
+            "123e3"
+        );
+        assert_eq!(
+            BigNum::new(4.56, 301).format(Notation::Engineering),
+            "45.6e300"
+        );
+
+        assert_eq!(Notation::parse("scientific"), Notation::Scientific);
+        assert_eq!(Notation::parse("unknown"), Notation::Short);
+    }
+
+    #[test]
+    fn test_big_num_amounts_survive_save_roundtrip() {
+        let mut game = TestGameState::new();
+        game.state
+            .resources
+            .set_amount("coins", BigNum::new(7.0, 500));
+        // Consistent with the count, so validation on load keeps it
+        game.buildings[0].count = 7400;
+        game.buildings[0].cost = BigNum::from_f64(15.0) * BigNum::from_f64(1.15).powi(7400);
+        assert!(game.buildings[0].cost > BigNum::new(1.0, 400));
+        assert!(game.buy_building(0));
+        assert_eq!(game.resource_amount("coins"), BigNum::new(7.0, 500));
+        assert_eq!(game.get_coins(), f64::MAX);
+
+        let json = serde_json::to_string(&game.save_game()).unwrap();
+        let mut restored = TestGameState::new();
+        restored.load_game(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.resource_amount("coins"), BigNum::new(7.0, 500));
+        assert_eq!(restored.buildings[0].cost, game.buildings[0].cost);
+        assert_eq!(restored.buildings[0].count, 7401);
+    }
+
+    #[test]
+    fn test_save_records_schema_version() {
+        let game = TestGameState::new();
+        let saved = game.save_game();
+        assert_eq!(saved.save_version, SAVE_VERSION);
+        assert_eq!(saved.version, env!("CARGO_PKG_VERSION"));
+
+        let mut restored = TestGameState::new();
+        let json = serde_json::to_string(&saved).unwrap();
+        let report = restored.load_json(&json).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(report.from_version, SAVE_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_pre_registry_save() {
+        // Shape written by 0.2.x: fixed resource fields, string version only
+        let json = r#"{
+            "state": {"coins": 50.0, "wood": 3.0, "stone": 1.0,
+                      "coins_per_second": 0.0, "wood_per_second": 0.0, "stone_per_second": 0.0,
+                      "coins_per_click": 1.0, "autoclick_count": 0, "total_clicks": 7,
+                      "last_update_time": 0.0},
+            "statistics": {"total_clicks": 7},
+            "upgrades": [], "buildings": [], "workers": [],
+            "achievements": [], "crafting_recipes": [], "unlocked_features": [],
+            "save_timestamp": 0.0,
+            "version": "0.2.6"
+        }"#;
+        let mut game = TestGameState::new();
+        let report = game.load_json(json).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, SAVE_VERSION);
+        assert_eq!(report.steps.len(), 6);
+        assert_eq!(game.get_coins(), 50.0);
+        assert_eq!(game.state.total_clicks, 7);
+        assert_eq!(game.statistics.total_clicks, 7);
+        assert_eq!(game.statistics.buildings_purchased, 0);
+        assert_eq!(
+            game.buildings.len(),
+            GameContent::embedded().buildings.len()
+        );
+        assert_eq!(game.last_migration(), Some(&report));
+    }
+
+    #[test]
+    fn test_load_adds_content_missing_from_save() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 15.0);
+        assert!(game.buy_building(0));
+
+        let mut saved = game.save_game();
+        saved.buildings.retain(|b| b.id != "coin_corporation");
+        saved.buildings.swap(0, 1);
+        saved.achievements.clear();
+
+        let mut restored = TestGameState::new();
+        let added = restored.load_game(saved);
+
+        assert!(added.contains(&"building:coin_corporation".to_string()));
+        assert!(added.iter().any(|entry| entry.starts_with("achievement:")));
+        assert_eq!(restored.buildings.len(), game.buildings.len());
+        assert_eq!(restored.buildings[0].id, game.buildings[0].id);
+        assert_eq!(restored.buildings[0].count, 1);
+        assert_eq!(restored.achievements.len(), game.achievements.len());
+    }
+
+    #[test]
+    fn test_load_keeps_entries_removed_from_content() {
+        let mut game = TestGameState::new();
+        let mut saved = game.save_game();
+        saved.buildings[0].id = "retired_building".to_string();
+        saved.buildings[0].name = "Retired".to_string();
+
+        game.load_game(saved);
+        assert_eq!(game.buildings.last().unwrap().id, "retired_building");
+        assert_eq!(game.buildings[0].id, "coin_mine");
+    }
+
+    #[test]
+    fn test_reject_save_from_newer_version() {
+        let game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        json["save_version"] = serde_json::json!(SAVE_VERSION + 1);
+
+        assert_eq!(
+            SavedGame::from_json(&json.to_string()).err(),
+            Some(SaveError::UnsupportedVersion {
+                found: SAVE_VERSION + 1,
+                supported: SAVE_VERSION,
+            })
+        );
+    }
+
+    /// A save with one of every kind of problem validation knows about
+    fn tampered_save_json() -> String {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.buy_building(0));
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+
+        json["state"]["resources"][0]["amount"] = serde_json::json!(-5.0);
+        json["state"]["coins_per_click"] = serde_json::json!(-1.0);
+        json["buildings"][0]["cost"] = serde_json::json!(1.0);
+        json["workers"][0]["assigned_building"] = serde_json::json!("castle");
+        let duplicate = json["achievements"][0].clone();
+        json["achievements"].as_array_mut().unwrap().push(duplicate);
+        json.to_string()
+    }
+
+    #[test]
+    fn test_valid_save_has_no_problems() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 500.0);
+        assert!(game.buy_building(0));
+        assert!(game.buy_building(0));
+        assert!(game.buy_upgrade(0));
+        game.assign_worker(0, "coin_mine");
+
+        assert_eq!(
+            validation::validate(&game.save_game(), game.content()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_load_repairs_invalid_save() {
+        let mut game = TestGameState::new();
+        let report = game.load_json(&tampered_save_json()).unwrap();
+
+        assert!(report.repaired.contains(&SaveProblem::NegativeAmount {
+            field: "state.resources.coins.amount".to_string(),
+            amount: (-5.0).into(),
+        }));
+        assert!(report.repaired.contains(&SaveProblem::InvalidValue {
+            field: "state.coins_per_click".to_string(),
+            value: -1.0,
+        }));
+        assert!(report.repaired.contains(&SaveProblem::CostMismatch {
+            kind: "building".to_string(),
+            id: "coin_mine".to_string(),
+            expected: (15.0 * 1.15).into(),
+            found: 1.0.into(),
+        }));
+        assert!(report.repaired.contains(&SaveProblem::DanglingAssignment {
+            worker: "miner".to_string(),
+            building: "castle".to_string(),
+        }));
+        assert!(report.repaired.iter().any(|p| matches!(
+            p,
+            SaveProblem::DuplicateId { kind, .. } if kind == "achievement"
+        )));
+
+        assert_eq!(game.get_coins(), 0.0);
+        assert_eq!(game.buildings[0].cost, 15.0 * 1.15);
+        assert_eq!(game.workers[0].assigned_building, None);
+        assert_eq!(
+            game.achievements.len(),
+            GameContent::embedded().achievements.len()
+        );
+        // Derived values come from what is owned, not from the save
+        assert_eq!(game.state.coins_per_click, 1.0);
+        assert!((game.get_coins_per_second() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strict_load_rejects_invalid_save() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 42.0);
+
+        let result = game.load_json_with_mode(&tampered_save_json(), LoadMode::Strict);
+        match result {
+            Err(SaveError::Invalid { problems }) => assert_eq!(problems.len(), 5),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(game.get_coins(), 42.0);
+        assert_eq!(game.last_migration(), None);
+    }
+
+    #[test]
+    fn test_big_num_powi() {
+        assert_eq!(BigNum::from_f64(1.15).powi(3), 1.15f64.powi(3));
+        assert_eq!(BigNum::from_f64(10.0).powi(0), 1.0);
+        let huge = BigNum::from_f64(10.0).powi(400);
+        assert!((huge / BigNum::new(1.0, 400) - 1.0).abs() < 1e-9);
+        assert_eq!(BigNum::from_f64(-2.0).powi(3), -8.0);
+    }
+
+    #[test]
+    fn test_big_num_sqrt() {
+        assert_eq!(BigNum::from_f64(16.0).sqrt(), 4.0);
+        assert_eq!(BigNum::from_f64(-4.0).sqrt(), 0.0);
+        assert_eq!(BigNum::new(4.0, 400).sqrt(), BigNum::new(2.0, 200));
+        let odd = BigNum::new(1.0, 401).sqrt();
+        assert!((odd / BigNum::new(10f64.sqrt(), 200) - 1.0).abs() < 1e-12);
+    }
+
+    /// The JSON payload of an export code
+    fn export_payload(code: &str) -> String {
+        String::from_utf8(export::decode(code, b"unused").unwrap().1).unwrap()
+    }
+
+    #[test]
+    fn test_export_roundtrip_is_verified() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 77.0);
+        let code = game.export_code();
+        assert!(code.starts_with(&format!("{}.", EXPORT_HEADER)));
+
+        let mut restored = TestGameState::new();
+        let report = restored.import_code(&code, LoadMode::Strict).unwrap();
+        assert_eq!(report.integrity, Integrity::Verified);
+        assert_eq!(restored.get_coins(), 77.0);
+    }
+
+    #[test]
+    fn test_import_detects_modified_payload() {
+        let game = TestGameState::new();
+        let code = game.export_code();
+        let mac = code.rsplit('.').next().unwrap();
+        let edited = export_payload(&code).replacen("\"amount\":0.0", "\"amount\":99999.0", 1);
+        let forged = format!(
+            "{}.{}.{}",
+            EXPORT_HEADER,
+            general_purpose::STANDARD.encode(edited),
+            mac
+        );
+
+        let mut restored = TestGameState::new();
+        assert_eq!(
+            restored.import_code(&forged, LoadMode::Strict).err(),
+            Some(SaveError::Modified)
+        );
+        assert_eq!(restored.get_coins(), 0.0);
+
+        let report = restored.import_code(&forged, LoadMode::Repair).unwrap();
+        assert_eq!(report.integrity, Integrity::Modified);
+        assert_eq!(restored.get_coins(), 99999.0);
+    }
+
+    #[test]
+    fn test_export_key_must_match() {
+        let mut game = TestGameState::new();
+        game.set_export_key(b"team secret");
+        let code = game.export_code();
+
+        let mut same_key = TestGameState::new();
+        same_key.set_export_key(b"team secret");
+        let report = same_key.import_code(&code, LoadMode::Repair).unwrap();
+        assert_eq!(report.integrity, Integrity::Verified);
+
+        let mut other_key = TestGameState::new();
+        let report = other_key.import_code(&code, LoadMode::Repair).unwrap();
+        assert_eq!(report.integrity, Integrity::Modified);
+    }
+
+    #[test]
+    fn test_import_legacy_unsigned_code() {
+        let game = TestGameState::new();
+        let json = serde_json::to_string(&game.save_game()).unwrap();
+        let legacy = general_purpose::STANDARD.encode(json);
+
+        let mut restored = TestGameState::new();
+        let report = restored.import_code(&legacy, LoadMode::Repair).unwrap();
+        assert_eq!(report.integrity, Integrity::Unsigned);
+    }
+
+    #[test]
+    fn test_strict_import_rejects_edited_unsigned_code() {
+        let game = TestGameState::new();
+        let edited = export_payload(&game.export_code()).replacen(
+            "\"amount\":0.0",
+            "\"amount\":1000000000000.0",
+            1,
+        );
+        let unsigned = general_purpose::STANDARD.encode(edited);
+
+        let mut restored = TestGameState::new();
+        assert_eq!(
+            restored.import_code(&unsigned, LoadMode::Strict).err(),
+            Some(SaveError::Unsigned)
+        );
+        assert_eq!(restored.get_coins(), 0.0);
+    }
+
+    #[test]
+    fn test_import_reports_corrupted_codes() {
+        let mut game = TestGameState::new();
+        let code = game.export_code();
+        for broken in [
+            String::from("not a save!"),
+            format!("{}.{}", EXPORT_HEADER, "abc"),
+            format!("IG9.{}", code.split_once('.').unwrap().1),
+            code.replacen(
+                &format!("{}.", EXPORT_HEADER),
+                &format!("{}.!!", EXPORT_HEADER),
+                1,
+            ),
+            general_purpose::STANDARD.encode("{\"state\": 1"),
+        ] {
+            assert!(
+                matches!(
+                    game.import_code(&broken, LoadMode::Repair),
+                    Err(SaveError::Corrupted(_))
+                ),
+                "{} should be corrupted",
+                broken
+            );
+        }
+    }
+
+    /// A game with something in every part of the save
+    fn played_game() -> TestGameState {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 5000.0);
+        for _ in 0..12 {
+            game.click_action();
+        }
+        assert!(game.buy_building(0));
+        assert!(game.buy_building(1));
+        assert!(game.buy_upgrade(0));
+        let sawmill = game.buildings.iter().position(|b| b.id == "sawmill");
+        assert!(game.buy_building(sawmill.unwrap()));
+        assert!(game.craft_resource("coins_to_wood"));
+        game.assign_worker(0, "coin_mine");
+        game.clock.advance_secs(30.0);
+        game.game_loop();
+        game.state.resources.earn("stone", BigNum::new(3.5, 720));
+        game
+    }
+
+    #[test]
+    fn test_compact_export_roundtrip() {
+        let mut game = played_game();
+        // Leave the sawmill halfway through its next craft
+        game.clock.advance_secs(2.5);
+        game.game_loop();
+        let sawmill = game.buildings.iter().find(|b| b.id == "sawmill").unwrap();
+        let progress = sawmill.converter.as_ref().unwrap().progress;
+        assert!((progress - 0.5).abs() < 1e-9);
+
+        let code = game.export_code_as(SaveFormat::Compact);
+        assert!(code.starts_with("IGZ1."));
+
+        let mut restored = TestGameState::new();
+        restored.clock.advance_secs(32.5);
+        let report = restored.import_code(&code, LoadMode::Strict).unwrap();
+        assert_eq!(report.integrity, Integrity::Verified);
+        assert!(report.load.is_empty());
+        assert_eq!(
+            serde_json::to_value(restored.save_game()).unwrap(),
+            serde_json::to_value(game.save_game()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compact_export_is_much_smaller() {
+        let game = played_game();
+        let sizes = game.export_size_report();
+
+        assert_eq!(
+            sizes.compact_code_length,
+            game.export_code_as(SaveFormat::Compact).len()
+        );
+        assert_eq!(sizes.json_code_length, game.export_code().len());
+        assert!(sizes.compressed_bytes < sizes.packed_bytes);
+        assert!(sizes.packed_bytes * 4 < sizes.json_bytes);
+        assert!(sizes.compact_code_length * 5 < sizes.json_code_length);
+    }
+
+    #[test]
+    fn test_compact_import_detects_damage() {
+        let game = played_game();
+        let code = game.export_code_as(SaveFormat::Compact);
+        let (header, rest) = code.split_once('.').unwrap();
+        let (payload, mac) = rest.split_once('.').unwrap();
+
+        // A payload from another save: still decodes, but the checksum fails
+        let other = TestGameState::new().export_code_as(SaveFormat::Compact);
+        let other_payload = other.split('.').nth(1).unwrap();
+        let swapped = format!("{}.{}.{}", header, other_payload, mac);
+        let mut restored = TestGameState::new();
+        let report = restored.import_code(&swapped, LoadMode::Repair).unwrap();
+        assert_eq!(report.integrity, Integrity::Modified);
+
+        // Bytes that do not inflate are corrupted
+        let garbage = general_purpose::STANDARD.encode(b"definitely not deflate");
+        assert_ne!(garbage, payload);
+        let broken = format!("{}.{}.{}", header, garbage, mac);
+        assert!(matches!(
+            restored.import_code(&broken, LoadMode::Repair),
+            Err(SaveError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn test_slot_metadata_describes_save() {
+        let game = played_game();
+        game.clock.advance_secs(60.0);
+        let metadata = SlotMetadata::describe("before boss", SlotKind::Manual, &game.save_game());
+
+        assert_eq!(metadata.name, "before boss");
+        assert_eq!(metadata.saved_at, 90_000.0);
+        assert_eq!(metadata.play_time_seconds, 30.0);
+        assert_eq!(metadata.coins, game.resource_amount("coins"));
+        assert_eq!(metadata.save_version, SAVE_VERSION);
+
+        let mut index = SlotIndex::default();
+        index.record(metadata.clone());
+        index.record(SlotMetadata {
+            play_time_seconds: 45.0,
+            ..metadata
+        });
+        assert_eq!(index.slots.len(), 1);
+        assert_eq!(index.get("before boss").unwrap().play_time_seconds, 45.0);
+
+        let stored = SlotIndex::from_json(Some(&index.to_json()));
+        assert_eq!(stored, index);
+        assert!(SlotIndex::from_json(Some("not json")).slots.is_empty());
+        assert!(index.remove("before boss"));
+        assert!(!index.remove("before boss"));
+    }
+
+    #[test]
+    fn test_backups_rotate_and_keep_manual_slots() {
+        let game = TestGameState::new();
+        let mut index = SlotIndex::default();
+        index.record(SlotMetadata::describe(
+            "main",
+            SlotKind::Manual,
+            &game.save_game(),
+        ));
+
+        let mut dropped = Vec::new();
+        for _ in 0..slots::MAX_BACKUPS + 2 {
+            let saved = game.save_game();
+            assert!(index.backup_due(saved.save_timestamp));
+            let name = slots::backup_name(saved.save_timestamp);
+            dropped.extend(index.push_backup(SlotMetadata::describe(
+                &name,
+                SlotKind::Backup,
+                &saved,
+            )));
+            game.clock.advance_secs(60.0);
+            assert!(!index.backup_due(game.clock.now()));
+            game.clock.advance_secs(240.0);
+        }
+
+        assert_eq!(dropped, vec!["backup-0", "backup-300000"]);
+        assert_eq!(index.backups().count(), slots::MAX_BACKUPS);
+        assert_eq!(index.backups().next().unwrap().name, "backup-600000");
+        assert!(index.get("main").is_some());
+    }
+
+    #[test]
+    fn test_storage_save_and_load_with_backups() {
+        let mut game = played_game();
+        let mut storage = MemoryStorage::new();
+        assert_eq!(game.load_from(&storage).unwrap(), None);
+
+        game.save_to(&mut storage).unwrap();
+        game.clock.advance_secs(60.0);
+        game.save_to(&mut storage).unwrap();
+        assert_eq!(slots::list(&storage).unwrap().len(), 1);
+        game.clock.advance_secs(300.0);
+        game.save_to(&mut storage).unwrap();
+        assert_eq!(slots::list(&storage).unwrap().len(), 2);
+
+        let mut restored = TestGameState::new();
+        let report = restored.load_from(&storage).unwrap().unwrap();
+        assert!(report.is_empty());
+        assert_eq!(restored.resource_amount("stone"), BigNum::new(3.5, 720));
+        assert_eq!(restored.buildings[1].count, 1);
+
+        // Older saves in storage are migrated like any other
+        let mut json = serde_json::to_value(TestGameState::new().save_game()).unwrap();
+        json.as_object_mut().unwrap().remove("save_version");
+        storage
+            .write(slots::CURRENT_SAVE_KEY, &json.to_string())
+            .unwrap();
+        let report = restored.load_from(&storage).unwrap().unwrap();
+        assert_eq!(report.from_version, 1);
+    }
+
+    #[test]
+    fn test_storage_slots_load_restore_and_delete() {
+        let mut game = played_game();
+        let mut storage = MemoryStorage::new();
+        let metadata = game.save_to_slot(&mut storage, " early ").unwrap();
+        assert_eq!(metadata.name, "early");
+        assert!(matches!(
+            game.save_to_slot(&mut storage, "  "),
+            Err(SaveError::Storage(_))
+        ));
+
+        game.reset_game();
+        game.clock.advance_secs(10.0);
+        game.save_to(&mut storage).unwrap();
+        game.load_slot(&storage, "early").unwrap();
+        assert_eq!(game.buildings[0].count, 1);
+        // Loading leaves the current save alone
+        assert_eq!(
+            SavedGame::from_json(&slots::read_current(&storage).unwrap().unwrap())
+                .unwrap()
+                .buildings[0]
+                .count,
+            0
+        );
+
+        game.reset_game();
+        game.clock.advance_secs(10.0);
+        game.restore_slot(&mut storage, "early").unwrap();
+        assert_eq!(game.buildings[0].count, 1);
+        let current =
+            SavedGame::from_json(&slots::read_current(&storage).unwrap().unwrap()).unwrap();
+        assert_eq!(current.buildings[0].count, 1);
+        // The reset game that was replaced is kept as a backup
+        let backups: Vec<SlotMetadata> = slots::list(&storage)
+            .unwrap()
+            .into_iter()
+            .filter(|slot| slot.kind == SlotKind::Backup)
+            .collect();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[1].coins, BigNum::ZERO);
+
+        assert!(slots::delete_slot(&mut storage, "early").unwrap());
+        assert!(!slots::delete_slot(&mut storage, "early").unwrap());
+        assert!(matches!(
+            game.load_slot(&storage, "early"),
+            Err(SaveError::Storage(_))
+        ));
+        assert!(storage.keys().all(|key| key != &slots::slot_key("early")));
+    }
+
+    #[test]
+    fn test_file_storage_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("idle-game-storage-{}", std::process::id()));
+        let mut storage = FileStorage::open(&dir).unwrap();
+        let game = played_game();
+        game.save_to_slot(&mut storage, "a/b:c").unwrap();
+        game.save_to(&mut storage).unwrap();
+
+        let reopened = FileStorage::open(&dir).unwrap();
+        let mut restored = TestGameState::new();
+        restored.load_slot(&reopened, "a/b:c").unwrap();
+        assert_eq!(restored.resource_amount("stone"), BigNum::new(3.5, 720));
+        assert_eq!(slots::list(&reopened).unwrap().len(), 2);
+        assert!(reopened.read("missing").unwrap().is_none());
+
+        storage.remove(slots::CURRENT_SAVE_KEY).unwrap();
+        storage.remove(slots::CURRENT_SAVE_KEY).unwrap();
+        assert!(restored.load_from(&storage).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_production_counts_towards_lifetime_statistics() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.buy_building(1));
+        game.clock.advance_secs(10.0);
+        game.game_loop();
+
+        assert_eq!(game.statistics.total_coins_earned, 10.0);
+        assert_eq!(game.statistics.total_wood_earned, 0.0);
+    }
+
+    #[test]
+    fn test_prestige_requires_enough_lifetime_coins() {
+        let mut game = TestGameState::new();
+        game.statistics.total_coins_earned = BigNum::from_f64(9_999.0);
+        game.drain_events();
+
+        assert_eq!(game.pending_prestige_points(), 0.0);
+        assert!(!game.prestige());
+        assert_eq!(
+            game.drain_events(),
+            vec![GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Prestige,
+                reason: FailureReason::InsufficientResources {
+                    resource: "coins".to_string(),
+                    required: 10_000.0.into(),
+                    available: 9_999.0.into(),
+                },
+            }]
+        );
+        assert_eq!(game.prestige.prestige_count, 0);
+    }
+
+    #[test]
+    fn test_prestige_resets_run_and_keeps_progress() {
+        let mut game = played_game();
+        game.statistics.total_coins_earned = BigNum::from_f64(45_000.0);
+        let statistics = game.statistics.clone();
+        let achievements = game.achievements.clone();
+        game.drain_events();
+
+        assert!(game.prestige());
+        assert!(matches!(
+            game.drain_events().first(),
+            Some(GameEvent::Prestiged { points_gained, prestige_count: 1, .. }) if *points_gained == 2.0
+        ));
+        assert_eq!(game.prestige.points, 2.0);
+        assert_eq!(game.get_coins(), 0.0);
+        assert!(game.buildings.iter().all(|b| b.count == 0));
+        assert!(game.upgrades.iter().all(|u| u.owned == 0));
+        assert!(game.workers.iter().all(|w| w.assigned_building.is_none()));
+        assert_eq!(
+            game.statistics.total_coins_earned,
+            statistics.total_coins_earned
+        );
+        assert_eq!(game.statistics.total_clicks, statistics.total_clicks);
+        assert_eq!(game.achievements.len(), achievements.len());
+        assert!(game.achievements[0].unlocked);
+
+        // Two points earned: +4% to clicks and buildings
+        assert!((game.prestige_summary().multiplier - 1.04).abs() < 1e-12);
+        assert!((game.state.coins_per_click - 1.04).abs() < 1e-12);
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.buy_building(1));
+        assert!((game.get_coins_per_second() - 1.04).abs() < 1e-12);
+
+        // Points already claimed are not granted again
+        assert_eq!(game.pending_prestige_points(), 0.0);
+        game.statistics.total_coins_earned = BigNum::from_f64(90_000.0);
+        assert_eq!(game.pending_prestige_points(), 1.0);
+        assert_eq!(game.prestige_summary().coins_for_next_point, 90_000.0);
+        assert!(game.prestige());
+        assert_eq!(game.prestige.points, 3.0);
+        assert_eq!(game.prestige.prestige_count, 2);
+
+        game.reset_game();
+        assert_eq!(game.prestige.points, 0.0);
+        assert_eq!(game.state.coins_per_click, 1.0);
+    }
+
+    #[test]
+    fn test_prestige_survives_save_and_migrates_in() {
+        let mut game = TestGameState::new();
+        game.statistics.total_coins_earned = BigNum::from_f64(10_000.0);
+        assert!(game.prestige());
+
+        let mut restored = TestGameState::new();
+        restored
+            .load_json(&serde_json::to_string(&game.save_game()).unwrap())
+            .unwrap();
+        assert_eq!(restored.prestige, game.prestige);
+        assert!((restored.state.coins_per_click - 1.02).abs() < 1e-12);
+
+        let compact = game.export_code_as(SaveFormat::Compact);
+        let mut restored = TestGameState::new();
+        restored.import_code(&compact, LoadMode::Strict).unwrap();
+        assert_eq!(restored.prestige, game.prestige);
+
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        json["save_version"] = serde_json::json!(2);
+        json.as_object_mut().unwrap().remove("prestige");
+        let report = restored.load_json(&json.to_string()).unwrap();
+        assert_eq!(report.steps[0], "added prestige progress");
+        assert_eq!(restored.prestige, Default::default());
+    }
+
+    #[test]
+    fn test_prestige_upgrades_spend_points_and_respect_max_level() {
+        let mut game = TestGameState::new();
+        assert_eq!(game.prestige_upgrades.len(), 6);
+        game.drain_events();
+
+        assert!(!game.buy_prestige_upgrade(0));
+        assert!(matches!(
+            game.drain_events().first(),
+            Some(GameEvent::PurchaseFailed {
+                target: PurchaseTarget::PrestigeUpgrade { index: 0 },
+                reason: FailureReason::InsufficientResources { resource, .. },
+            }) if resource == "prestige_points"
+        ));
+
+        game.prestige.points = BigNum::from_f64(20.0);
+        assert!(game.buy_prestige_upgrade(3));
+        assert_eq!(game.prestige.points, 15.0);
+        assert!(matches!(
+            game.drain_events().first(),
+            Some(GameEvent::PrestigeUpgradeBought {
+                index: 3,
+                owned: 1,
+                ..
+            })
+        ));
+        assert!(game.modifiers().keep_autoclickers);
+
+        assert!(!game.buy_prestige_upgrade(3));
+        assert!(matches!(
+            game.drain_events().first(),
+            Some(GameEvent::PurchaseFailed {
+                reason: FailureReason::MaxLevel,
+                ..
+            })
+        ));
+        assert_eq!(game.prestige.points, 15.0);
+
+        // Bulk discount compounds its cost and lowers building prices
+        let price = game.building_price(0);
+        assert!(game.buy_prestige_upgrade(1));
+        assert_eq!(game.prestige_upgrades[1].cost, 4.0);
+        assert!(game.building_price(0) < price);
+        assert!(!game.buy_prestige_upgrade(99));
+    }
+
+    #[test]
+    fn test_prestige_upgrades_apply_after_prestige() {
+        let levels = |upgrades: &[crate::entities::Upgrade]| {
+            upgrades
+                .iter()
+                .map(|u| (u.id.clone(), u.owned, u.cost))
+                .collect::<Vec<_>>()
+        };
+        let mut game = played_game();
+        game.statistics.total_coins_earned = BigNum::from_f64(1_000_000.0);
+        game.prestige.points = BigNum::from_f64(6.0);
+        assert!(game.buy_prestige_upgrade(0));
+        assert!(game.buy_prestige_upgrade(3));
+        game.state.resources.set_amount("coins", 10_000.0);
+        assert!(game.buy_upgrade(1));
+        assert!(game.buy_upgrade(1));
+
+        assert!(game.prestige());
+        assert_eq!(game.get_coins(), 100.0);
+        assert_eq!(game.upgrades[1].id, "autoclicker_lv1");
+        assert_eq!(game.upgrades[1].owned, 2);
+        assert!(game.upgrades[0].owned == 0);
+        assert_eq!(game.prestige_upgrades[0].owned, 1);
+        assert_eq!(game.prestige_upgrades[3].owned, 1);
+        assert_eq!(game.prestige.points, 10.0);
+
+        let compact = game.export_code_as(SaveFormat::Compact);
+        let mut restored = TestGameState::new();
+        restored.import_code(&compact, LoadMode::Strict).unwrap();
+        assert_eq!(
+            levels(&restored.prestige_upgrades),
+            levels(&game.prestige_upgrades)
+        );
+        let mut restored = TestGameState::new();
+        restored
+            .load_json(&serde_json::to_string(&game.save_game()).unwrap())
+            .unwrap();
+        assert_eq!(
+            levels(&restored.prestige_upgrades),
+            levels(&game.prestige_upgrades)
+        );
+        assert!(restored.modifiers().keep_autoclickers);
+
+        game.reset_game();
+        assert!(game.prestige_upgrades.iter().all(|u| u.owned == 0));
+        assert!(!game.modifiers().keep_autoclickers);
+    }
+
+    #[test]
+    fn test_worker_slots_limit_assignments() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        // Every worker the content ships fits in the base slots
+        for index in 0..game.workers.len() {
+            assert!(game.assign_worker(index, "coin_mine"));
+        }
+        let mut extra = game.workers[0].clone();
+        extra.id = "extra".to_string();
+        extra.assigned_building = None;
+        game.workers.push(extra);
+        let index = game.workers.len() - 1;
+
+        game.drain_events();
+        assert!(!game.assign_worker(index, "coin_mine"));
+        assert!(game.workers[index].assigned_building.is_none());
+        assert_eq!(
+            game.drain_events(),
+            vec![GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Worker { index },
+                reason: FailureReason::NoWorkerSlots { slots: 5 },
+            }]
+        );
+        // Moving a worker who already has a slot is fine
+        assert!(game.assign_worker(0, "woodcutter"));
+
+        game.prestige.points = BigNum::from_f64(3.0);
+        assert!(game.buy_prestige_upgrade(4));
+        assert_eq!(game.worker_slots(), 6);
+        assert!(game.assign_worker(index, "coin_mine"));
+    }
+
+    #[test]
+    fn test_unassign_worker_frees_slot() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        assert!(game.assign_worker(0, "coin_mine"));
+        game.buildings[0].count = 1;
+        game.update_production();
+        let boosted = game.get_coins_per_second();
+
+        assert!(game.unassign_worker(0));
+        assert!(game.workers[0].assigned_building.is_none());
+        assert!(game.get_coins_per_second() < boosted);
+
+        // An empty building id unassigns too
+        assert!(game.assign_worker(1, "woodcutter"));
+        assert!(game.assign_worker(1, ""));
+        assert!(game.workers[1].assigned_building.is_none());
+
+        assert!(!game.unassign_worker(99));
+    }
+
+    #[test]
+    fn test_content_achievement_conditions_need_no_code() {
+        let json = r#"{"achievements": [{"id": "mine_owner", "name": "Mine Owner",
+            "description": "", "category": "buildings",
+            "condition": {"type": "all", "conditions": [
+                {"type": "stat", "stat": "building:coin_mine", "value": 2},
+                {"type": "any", "conditions": [
+                    {"type": "stat", "stat": "resource:coins", "comparator": "<", "value": 5},
+                    {"type": "stat", "stat": "upgrade:better_click", "value": 1}
+                ]}
+            ]}}]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        let index = game
+            .achievements
+            .iter()
+            .position(|a| a.id == "mine_owner")
+            .unwrap();
+        assert_eq!(game.achievements[index].requirement, 2.0);
+
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.buy_building(0));
+        assert!(game.buy_building(0));
+        assert!(!game.achievements[index].unlocked);
+        assert_eq!(game.achievements[index].progress, 1.0);
+
+        game.state.resources.set_amount("coins", 1.0);
+        game.game_loop();
+        assert!(game.achievements[index].unlocked);
+        assert!(game
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, GameEvent::AchievementUnlocked { id, .. } if id == "mine_owner")));
+    }
+
+    #[test]
+    fn test_achievement_unlocks_cascade_and_conditions_survive_loading() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 200.0);
+        assert!(game.buy_building(0));
+        // first_building and first_coins_100 make two, which first_unlock counts
+        assert!(game
+            .achievements
+            .iter()
+            .any(|a| a.id == "first_unlock" && a.unlocked));
+        assert_eq!(
+            game.statistics.achievements_unlocked_count as usize,
+            game.achievements.iter().filter(|a| a.unlocked).count()
+        );
+
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        for achievement in json["achievements"].as_array_mut().unwrap() {
+            achievement.as_object_mut().unwrap().remove("condition");
+        }
+        let mut restored = TestGameState::new();
+        restored.load_json(&json.to_string()).unwrap();
+        let click_novice = restored
+            .achievements
+            .iter()
+            .find(|a| a.id == "click_novice_10")
+            .unwrap();
+        assert_eq!(
+            click_novice.condition,
+            Condition::stat(StatKey::TotalClicks, 10.0)
+        );
+    }
+
+    #[test]
+    fn test_content_rejects_bad_condition_stats() {
+        let achievement = |stat: &str| {
+            format!(
+                r#"{{"achievements": [{{"id": "a", "name": "A", "description": "",
+                    "category": "x", "condition": {{"type": "stat", "stat": "{}", "value": 1}}}}]}}"#,
+                stat
+            )
+        };
+        assert!(matches!(
+            GameContent::embedded_with_override(&achievement("karma")),
+            Err(ContentError::Parse(message)) if message.contains("unknown stat karma")
+        ));
+        assert_eq!(
+            GameContent::embedded_with_override(&achievement("earned:mithril")),
+            Err(ContentError::UnknownResource {
+                kind: "achievement",
+                id: "a".to_string(),
+                resource: "mithril".to_string(),
+            })
+        );
+        assert_eq!(
+            GameContent::embedded_with_override(&achievement("upgrade:time_machine")),
+            Err(ContentError::UnknownUpgrade {
+                kind: "achievement",
+                id: "a".to_string(),
+                upgrade: "time_machine".to_string(),
+            })
+        );
+        assert_eq!(
+            "building:coin_mine".parse::<StatKey>(),
+            Ok(StatKey::Building("coin_mine".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_achievement_rewards_and_bonuses() {
+        let json = r#"{
+            "recipes": [{"id": "coins_to_wood", "name": "x",
+                "inputs": [{"resource": "coins", "amount": 1}],
+                "outputs": [{"resource": "wood", "amount": 1}],
+                "unlocked": false}],
+            "achievements": [{"id": "first_click", "name": "First Click", "description": "",
+                "category": "clicks",
+                "condition": {"type": "stat", "stat": "total_clicks", "value": 1},
+                "effects": [{"type": "click_bonus", "amount": 2}],
+                "rewards": [
+                    {"type": "grant", "resource": "stone", "amount": 50},
+                    {"type": "unlock_recipe", "recipe": "coins_to_wood"},
+                    {"type": "unlock_feature", "feature": "workers_tab"}
+                ]}]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        assert!(!game.crafting_recipes[0].unlocked);
+
+        game.click_action();
+        assert_eq!(game.get_stone(), 50.0);
+        assert_eq!(game.get_coins(), 1.0);
+        assert_eq!(game.get_coins_per_click(), 3.0);
+        assert!(game.crafting_recipes[0].unlocked);
+        assert!(game
+            .unlocked_features
+            .iter()
+            .any(|f| f.id == "workers_tab" && f.unlocked));
+        assert!(game
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, GameEvent::FeatureUnlocked { id, .. } if id == "workers_tab")));
+
+        // Grants are one-time; bonuses stay through prestige and reloads
+        game.click_action();
+        assert_eq!(game.get_stone(), 50.0);
+        game.statistics.total_coins_earned = BigNum::from_f64(10_000.0);
+        assert!(game.prestige());
+        assert_eq!(game.get_coins_per_click(), 3.0 * 1.02);
+        let save = serde_json::to_string(&game.save_game()).unwrap();
+        let mut restored = TestGameState::with_content(game.content().clone());
+        restored.load_json(&save).unwrap();
+        assert_eq!(restored.get_coins_per_click(), 3.0 * 1.02);
+        assert_eq!(restored.achievements.last().unwrap().rewards.len(), 3);
+    }
+
+    #[test]
+    fn test_content_rejects_unknown_reward_targets() {
+        let json = r#"{"achievements": [{"id": "a", "name": "A", "description": "",
+            "category": "x", "condition": {"type": "stat", "stat": "clicks", "value": 1},
+            "rewards": [{"type": "unlock_recipe", "recipe": "gold_to_lead"}]}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownRecipe {
+                kind: "achievement",
+                id: "a".to_string(),
+                recipe: "gold_to_lead".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_locked_content_fails_with_locked_reason() {
+        let mut game = TestGameState::new();
+        game.state.resources.set_amount("coins", 1000.0);
+        game.drain_events();
+
+        assert_eq!(game.buildings[2].id, "coin_corporation");
+        assert!(!game.buy_building(2));
+        assert!(!game.craft_resource("coins_to_wood"));
+        assert!(!game.assign_worker(0, "coin_mine"));
+        assert_eq!(
+            game.drain_events(),
+            vec![
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Building { index: 2 },
+                    reason: FailureReason::Locked {
+                        feature: Some("advanced_buildings".to_string()),
+                    },
+                },
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Recipe {
+                        id: "coins_to_wood".to_string(),
+                    },
+                    reason: FailureReason::Locked {
+                        feature: Some("unlock_crafting".to_string()),
+                    },
+                },
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Worker { index: 0 },
+                    reason: FailureReason::Locked {
+                        feature: Some("workers_tab".to_string()),
+                    },
+                },
+            ]
+        );
+        assert_eq!(game.get_coins(), 1000.0);
+
+        // Ungated buildings are unaffected
+        assert!(game.buy_building(0));
+        assert!(game.unlock_feature("unlock_crafting"));
+        assert!(game.craft_resource("coins_to_wood"));
+        game.finish_crafts();
+        game.state.total_clicks = 50;
+        assert!(game.unlock_feature("workers_tab"));
+        assert!(game.assign_worker(0, "coin_mine"));
+
+        game.crafting_recipes[1].unlocked = false;
+        assert!(!game.craft_resource("wood_to_coins"));
+        assert!(matches!(
+            game.drain_events().last(),
+            Some(GameEvent::PurchaseFailed {
+                reason: FailureReason::Locked { feature: None },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unlocks_persist_and_content_checks_requirements() {
+        let mut game = TestGameState::new();
+        game.statistics.buildings_purchased = 20;
+        assert!(game.unlock_feature("advanced_buildings"));
+        let save = serde_json::to_string(&game.save_game()).unwrap();
+        let mut restored = TestGameState::new();
+        restored.load_json(&save).unwrap();
+        assert!(restored.is_feature_unlocked("advanced_buildings"));
+        restored.state.resources.set_amount("coins", 1000.0);
+        assert!(restored.buy_building(2));
+
+        let json = r#"{"buildings": [{"id": "vault", "name": "Vault", "base_cost": 1.0,
+            "requires": "time_travel"}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownFeature {
+                kind: "building",
+                id: "vault".to_string(),
+                feature: "time_travel".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unlocks_are_evaluated_automatically() {
+        let mut game = TestGameState::new();
+        for _ in 0..10 {
+            game.click_action();
+        }
+        assert!(game.is_feature_unlocked("statistics_panel"));
+        assert!(game.drain_events().iter().any(
+            |e| matches!(e, GameEvent::FeatureUnlocked { id, .. } if id == "statistics_panel")
+        ));
+        let panel = game
+            .unlocked_features
+            .iter()
+            .find(|f| f.id == "achievements_panel")
+            .unwrap();
+        assert_eq!(panel.progress, 10.0);
+        assert!(!panel.unlocked);
+
+        for _ in 0..15 {
+            game.click_action();
+        }
+        assert!(game.is_feature_unlocked("achievements_panel"));
+
+        // Coins from production count on the next tick
+        game.state.resources.set_amount("coins", 150.0);
+        game.game_loop();
+        assert!(game.is_feature_unlocked("unlock_crafting"));
+    }
+
+    #[test]
+    fn test_unlock_prerequisite_chains_and_stat_types() {
+        let json = r#"{"unlocks": [
+            {"id": "gate", "name": "Gate", "feature_type": "area",
+             "condition": {"type": "stat", "stat": "play_time_seconds", "value": 5}},
+            {"id": "keep", "name": "Keep", "feature_type": "area",
+             "prerequisites": ["gate"],
+             "condition": {"type": "all", "conditions": [
+                {"type": "stat", "stat": "resources_crafted", "value": 1},
+                {"type": "stat", "stat": "achievements_unlocked", "value": 1}
+             ]}},
+            {"id": "tower", "name": "Tower", "feature_type": "area",
+             "prerequisites": ["keep"],
+             "condition": {"type": "stat", "stat": "workers_assigned", "value": 1}}
+        ]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.unlock_all_features();
+        for id in ["gate", "keep", "tower"] {
+            let feature = game
+                .unlocked_features
+                .iter_mut()
+                .find(|f| f.id == id)
+                .unwrap();
+            feature.unlocked = false;
+        }
+
+        // Crafting unlocks first_craft; both parts of keep hold, but not gate
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.craft_resource("coins_to_wood"));
+        assert!(game.assign_worker(0, "coin_mine"));
+        assert!(!game.check_unlock("keep"));
+        assert!(!game.is_feature_unlocked("keep"));
+
+        // Once gate opens the whole chain follows in the same tick
+        game.clock.advance_secs(6.0);
+        game.game_loop();
+        assert!(game.is_feature_unlocked("gate"));
+        assert!(game.is_feature_unlocked("keep"));
+        assert!(game.is_feature_unlocked("tower"));
+        let unlocked: Vec<String> = game
+            .drain_events()
+            .into_iter()
+            .filter_map(|e| match e {
+                GameEvent::FeatureUnlocked { id, .. } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unlocked, vec!["gate", "keep", "tower"]);
+    }
+
+    #[test]
+    fn test_unlock_conditions_load_into_old_saves() {
+        let game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        for feature in json["unlocked_features"].as_array_mut().unwrap() {
+            let feature = feature.as_object_mut().unwrap();
+            for field in ["condition", "prerequisites", "progress", "requirement"] {
+                feature.remove(field);
+            }
+            feature.insert("requirement_type".to_string(), "total_clicks".into());
+            feature.insert("requirement_value".to_string(), 10.0.into());
+        }
+        let mut restored = TestGameState::new();
+        restored.load_json(&json.to_string()).unwrap();
+        let workers_tab = &restored.unlocked_features[0];
+        assert_eq!(
+            workers_tab.condition,
+            Condition::stat(StatKey::Clicks, 50.0)
+        );
+        assert_eq!(workers_tab.requirement, 50.0);
+
+        let json = r#"{"unlocks": [{"id": "x", "name": "X", "feature_type": "area",
+            "prerequisites": ["nowhere"],
+            "condition": {"type": "stat", "stat": "clicks", "value": 1}}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownFeature {
+                kind: "unlock",
+                id: "x".to_string(),
+                feature: "nowhere".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_recipes_with_several_inputs_and_outputs() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("wood", 100.0);
+        game.state.resources.set_amount("stone", 1.0);
+        game.state.resources.set_amount("coins", 1000.0);
+        game.drain_events();
+
+        assert!(!game.craft_resource("craft_tools"));
+        game.buildings
+            .iter_mut()
+            .find(|b| b.id == "lumber_mill")
+            .unwrap()
+            .count = 1;
+        assert!(!game.craft_resource("craft_tools"));
+        assert_eq!(
+            game.drain_events(),
+            vec![
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Recipe {
+                        id: "craft_tools".to_string()
+                    },
+                    reason: FailureReason::MissingBuilding {
+                        building: "lumber_mill".to_string()
+                    },
+                },
+                GameEvent::PurchaseFailed {
+                    target: PurchaseTarget::Recipe {
+                        id: "craft_tools".to_string()
+                    },
+                    reason: FailureReason::InsufficientResources {
+                        resource: "stone".to_string(),
+                        required: 2.0.into(),
+                        available: 1.0.into(),
+                    },
+                },
+            ]
+        );
+        // A missing input spends none of the others
+        assert_eq!(game.get_wood(), 100.0);
+        assert_eq!(game.get_coins(), 1000.0);
+
+        game.state.resources.set_amount("stone", 2.0);
+        assert!(game.craft_resource("craft_tools"));
+        assert_eq!(game.get_wood(), 90.0);
+        assert_eq!(game.get_stone(), 0.0);
+        assert_eq!(game.get_coins(), 950.0);
+        game.finish_crafts();
+        let tools = game.get_resource("tools");
+        assert!(tools == 1.0 || tools == 2.0);
+
+        assert!(game.craft_resource("sell_tools"));
+        assert_eq!(game.get_coins(), 1450.0);
+    }
+
+    #[test]
+    fn test_bonus_outputs_roll_per_craft() {
+        let json = r#"{"recipes": [{
+            "id": "lucky", "name": "Lucky",
+            "inputs": [{"resource": "coins", "amount": 1}],
+            "outputs": [{"resource": "wood", "amount": 1}],
+            "bonus_outputs": [
+                {"resource": "stone", "amount": 1, "chance": 0.25},
+                {"resource": "wood", "amount": 5, "chance": 0}
+            ]
+        }]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.seed_rng(7);
+        game.state.resources.set_amount("coins", 400.0);
+        for _ in 0..400 {
+            assert!(game.craft_resource("lucky"));
+        }
+        assert_eq!(game.get_wood(), 400.0);
+        let stone = game.get_stone();
+        assert!(stone > 60.0 && stone < 140.0, "{} bonus stone", stone);
+
+        let bonus_crafts = game
+            .drain_events()
+            .iter()
+            .filter(|e| matches!(e, GameEvent::Crafted { bonus, .. } if !bonus.is_empty()))
+            .count();
+        assert_eq!(bonus_crafts as f64, stone);
+
+        // The same seed rolls the same results
+        let mut replay =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        replay.seed_rng(7);
+        replay.state.resources.set_amount("coins", 400.0);
+        for _ in 0..400 {
+            replay.craft_resource("lucky");
+        }
+        assert_eq!(replay.get_stone(), stone);
+    }
+
+    #[test]
+    fn test_single_ingredient_recipes_migrate_to_lists() {
+        let game = TestGameState::new();
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        json["save_version"] = serde_json::json!(4);
+        let recipes = json["crafting_recipes"].as_array_mut().unwrap();
+        recipes.push(serde_json::json!({
+            "id": "retired", "name": "Retired", "unlocked": true,
+            "inputs": [], "outputs": []
+        }));
+        for recipe in recipes.iter_mut() {
+            let recipe = recipe.as_object_mut().unwrap();
+            recipe.remove("inputs");
+            recipe.remove("outputs");
+            recipe.insert("input_resource".to_string(), "coins".into());
+            recipe.insert("input_amount".to_string(), 3.0.into());
+            recipe.insert("output_resource".to_string(), "stone".into());
+            recipe.insert("output_amount".to_string(), 4.0.into());
+        }
+
+        let mut restored = TestGameState::new();
+        let report = restored.load_json(&json.to_string()).unwrap();
+        assert_eq!(
+            report.steps[0],
+            "turned recipe inputs and outputs into lists"
+        );
+        // Content recipes take their ingredients from the definitions
+        let tools = restored
+            .crafting_recipes
+            .iter()
+            .find(|r| r.id == "craft_tools")
+            .unwrap();
+        assert_eq!(tools.inputs.len(), 3);
+        assert_eq!(tools.requires_building.as_deref(), Some("lumber_mill"));
+        let retired = restored
+            .crafting_recipes
+            .iter()
+            .find(|r| r.id == "retired")
+            .unwrap();
+        assert_eq!(retired.inputs, vec![RecipeItem::new("coins", 3.0)]);
+        assert_eq!(retired.outputs, vec![RecipeItem::new("stone", 4.0)]);
+
+        let json = r#"{"recipes": [{"id": "x", "name": "X",
+            "inputs": [], "outputs": [], "requires_building": "castle"}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownBuilding {
+                kind: "recipe",
+                id: "x".to_string(),
+                building: "castle".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_crafts_queue_and_complete_over_time() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 300.0);
+        for _ in 0..3 {
+            assert!(game.craft_resource("coins_to_wood"));
+        }
+        assert_eq!(game.get_coins(), 0.0);
+        assert_eq!(game.get_wood(), 0.0);
+        assert_eq!(game.crafting_queue.len(), 3);
+        assert!(game.drain_events().contains(&GameEvent::CraftQueued {
+            recipe_id: "coins_to_wood".to_string(),
+            count: 1,
+            duration: 5.0,
+        }));
+
+        // One slot: jobs run one after another
+        game.clock.advance_secs(4.0);
+        game.game_loop();
+        assert_eq!(game.get_wood(), 0.0);
+        assert_eq!(game.crafting_queue[0].progress, 4.0);
+        assert_eq!(game.crafting_queue[1].progress, 0.0);
+
+        // Time left over from a finished job goes to the next one
+        game.clock.advance_secs(3.0);
+        game.game_loop();
+        assert_eq!(game.get_wood(), 10.0);
+        assert_eq!(game.crafting_queue.len(), 2);
+        assert_eq!(game.crafting_queue[0].progress, 2.0);
+        assert_eq!(game.statistics.total_resources_crafted, 1);
+        let crafted = game
+            .drain_events()
+            .iter()
+            .filter(|e| matches!(e, GameEvent::Crafted { .. }))
+            .count();
+        assert_eq!(crafted, 1);
+
+        game.finish_crafts();
+        assert_eq!(game.get_wood(), 30.0);
+        assert!(game.crafting_queue.is_empty());
+    }
+
+    #[test]
+    fn test_crafting_slots_and_queue_limit() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.prestige.points = 3.0.into();
+        let bench = game
+            .prestige_upgrades
+            .iter()
+            .position(|u| u.id == "crafting_bench")
+            .unwrap();
+        assert_eq!(game.crafting_slots(), 1);
+        assert!(game.buy_prestige_upgrade(bench));
+        assert_eq!(game.crafting_slots(), 2);
+
+        game.state.resources.set_amount("coins", 2000.0);
+        for _ in 0..10 {
+            assert!(game.craft_resource("coins_to_stone"));
+        }
+        game.drain_events();
+        assert!(!game.craft_resource("coins_to_stone"));
+        assert_eq!(
+            game.drain_events(),
+            vec![GameEvent::PurchaseFailed {
+                target: PurchaseTarget::Recipe {
+                    id: "coins_to_stone".to_string()
+                },
+                reason: FailureReason::QueueFull,
+            }]
+        );
+        assert_eq!(game.get_coins(), 1000.0);
+
+        // Two slots: the first two jobs progress together
+        game.clock.advance_secs(5.0);
+        game.game_loop();
+        assert_eq!(game.get_stone(), 2.0);
+        assert_eq!(game.crafting_queue.len(), 8);
+
+        // Recipes that take no time skip the queue even when it is full
+        assert!(game.craft_resource("coins_to_stone"));
+        game.state.resources.set_amount("tools", 1.0);
+        assert!(game.craft_resource("sell_tools"));
+        assert_eq!(game.get_resource("tools"), 0.0);
+    }
+
+    #[test]
+    fn test_cancel_craft_refunds_inputs() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 200.0);
+        assert!(game.craft_resource("coins_to_wood"));
+        assert!(game.craft_resource("coins_to_stone"));
+        game.clock.advance_secs(2.0);
+        game.game_loop();
+        game.drain_events();
+
+        assert!(game.cancel_craft(0));
+        assert!(!game.cancel_craft(5));
+        assert_eq!(game.get_coins(), 100.0);
+        assert_eq!(game.crafting_queue.len(), 1);
+        assert_eq!(game.crafting_queue[0].recipe_id, "coins_to_stone");
+        assert!(game.drain_events().contains(&GameEvent::CraftCancelled {
+            recipe_id: "coins_to_wood".to_string(),
+        }));
+
+        game.finish_crafts();
+        assert_eq!(game.get_stone(), 1.0);
+        assert_eq!(game.get_wood(), 0.0);
+    }
+
+    #[test]
+    fn test_offline_progress_completes_crafts() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 300.0);
+        for _ in 0..3 {
+            assert!(game.craft_resource("coins_to_wood"));
+        }
+
+        let report = game.apply_offline_progress(12.0);
+        assert_eq!(report.crafts_completed, vec!["coins_to_wood"; 2]);
+        assert_eq!(game.get_wood(), 20.0);
+        assert_eq!(game.crafting_queue[0].progress, 2.0);
+
+        // Queued crafts belong to the run, so prestige drops them
+        game.statistics.total_coins_earned = 1e6.into();
+        assert!(game.prestige());
+        assert!(game.crafting_queue.is_empty());
+    }
+
+    #[test]
+    fn test_crafting_queue_persists_and_is_validated() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 200.0);
+        assert!(game.craft_resource("coins_to_wood"));
+        assert!(game.craft_resource("coins_to_stone"));
+        game.clock.advance_secs(1.0);
+        game.game_loop();
+
+        let code = game.export_code_as(SaveFormat::Compact);
+        let mut restored = TestGameState::new();
+        restored.import_code(&code, LoadMode::Strict).unwrap();
+        assert_eq!(restored.crafting_queue, game.crafting_queue);
+
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        json["crafting_queue"][0]["progress"] = serde_json::json!(-3.0);
+        let mut repaired = TestGameState::new();
+        let report = repaired.load_json(&json.to_string()).unwrap();
+        assert_eq!(
+            report.repaired,
+            vec![SaveProblem::InvalidValue {
+                field: "crafting_queue.0.progress".to_string(),
+                value: -3.0,
+            }]
+        );
+        assert_eq!(repaired.crafting_queue[0].progress, 0.0);
+
+        // Saves from before the queue load with nothing in progress
+        json["save_version"] = serde_json::json!(5);
+        json.as_object_mut().unwrap().remove("crafting_queue");
+        let mut old = TestGameState::new();
+        let report = old.load_json(&json.to_string()).unwrap();
+        assert_eq!(report.steps, vec!["added the crafting queue"]);
+        assert!(old.crafting_queue.is_empty());
+    }
+
+    #[test]
+    fn test_craft_resource_times_batches_into_one_job() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("coins", 250.0);
+        game.drain_events();
+
+        assert_eq!(game.craft_resource_times("coins_to_wood", 0), 0);
+        assert!(game.drain_events().is_empty());
+
+        // Only two of the five are affordable
+        assert_eq!(game.craft_resource_times("coins_to_wood", 5), 2);
+        assert_eq!(game.get_coins(), 50.0);
+        assert_eq!(game.crafting_queue.len(), 1);
+        assert_eq!(game.crafting_queue[0].count, 2);
+        assert_eq!(game.crafting_queue[0].duration, 10.0);
+        assert!(game.drain_events().contains(&GameEvent::CraftQueued {
+            recipe_id: "coins_to_wood".to_string(),
+            count: 2,
+            duration: 10.0,
+        }));
+
+        assert_eq!(game.craft_resource_times("coins_to_wood", 5), 0);
+        assert!(matches!(
+            game.drain_events().last(),
+            Some(GameEvent::PurchaseFailed {
+                reason: FailureReason::InsufficientResources { .. },
+                ..
+            })
+        ));
+
+        game.finish_crafts();
+        assert_eq!(game.get_wood(), 20.0);
+        assert_eq!(game.statistics.total_resources_crafted, 2);
+        let crafted: Vec<u32> = game
+            .drain_events()
+            .into_iter()
+            .filter_map(|e| match e {
+                GameEvent::Crafted { count, .. } => Some(count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(crafted, vec![2]);
+
+        // Cancelling a batch refunds all of it
+        game.state.resources.set_amount("coins", 1000.0);
+        assert_eq!(game.craft_max("coins_to_stone"), 10);
+        assert_eq!(game.get_coins(), 0.0);
+        assert!(game.cancel_craft(0));
+        assert_eq!(game.get_coins(), 1000.0);
+    }
+
+    #[test]
+    fn test_craft_max_applies_instant_batches_at_once() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        game.state.resources.set_amount("tools", 150.5);
+        game.drain_events();
+
+        assert_eq!(game.craft_max("sell_tools"), 150);
+        assert_eq!(game.get_coins(), 75_000.0);
+        assert_eq!(game.get_resource("tools"), 0.5);
+        assert_eq!(game.statistics.total_resources_crafted, 150);
+        let events = game.drain_events();
+        let crafted = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::Crafted { count: 150, .. }))
+            .count();
+        assert_eq!(crafted, 1);
+        // Milestones see the whole batch
+        assert!(game
+            .achievements
+            .iter()
+            .any(|a| a.id == "craft_master_100" && a.unlocked));
+
+        let json = r#"{"recipes": [{
+            "id": "lucky", "name": "Lucky",
+            "inputs": [{"resource": "coins", "amount": 2}],
+            "outputs": [{"resource": "wood", "amount": 1}],
+            "bonus_outputs": [{"resource": "stone", "amount": 3, "chance": 1}]
+        }]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.state.resources.set_amount("coins", 9.0);
+        game.drain_events();
+        assert_eq!(game.craft_max("lucky"), 4);
+        assert_eq!(game.get_stone(), 12.0);
+        assert!(game.drain_events().contains(&GameEvent::Crafted {
+            recipe_id: "lucky".to_string(),
+            count: 4,
+            bonus: vec![RecipeItem::new("stone", 12.0)],
+        }));
+    }
+
+    #[test]
+    fn test_converter_buildings_craft_and_stall() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        let sawmill = game
+            .buildings
+            .iter()
+            .position(|b| b.id == "sawmill")
+            .unwrap();
+        game.buildings[sawmill].count = 1;
+        game.state.resources.set_amount("coins", 250.0);
+        game.drain_events();
+
+        // One craft of coins_to_wood every five seconds
+        game.clock.advance_secs(5.0);
+        game.game_loop();
+        assert_eq!(game.get_coins(), 150.0);
+        assert_eq!(game.get_wood(), 10.0);
+
+        // Two crafts are due but only one is affordable
+        game.clock.advance_secs(10.0);
+        game.game_loop();
+        assert_eq!(game.get_coins(), 50.0);
+        assert_eq!(game.get_wood(), 20.0);
+        let converter = game.buildings[sawmill].converter.clone().unwrap();
+        assert_eq!(converter.stalled_on.as_deref(), Some("coins"));
+        assert_eq!(converter.progress, 0.0);
+        assert!(game.drain_events().contains(&GameEvent::ConverterStalled {
+            index: sawmill,
+            name: "Sawmill".to_string(),
+            resource: "coins".to_string(),
+        }));
+
+        game.state.resources.set_amount("coins", 1000.0);
+        game.clock.advance_secs(5.0);
+        game.game_loop();
+        assert_eq!(game.get_wood(), 30.0);
+        assert_eq!(
+            game.buildings[sawmill]
+                .converter
+                .as_ref()
+                .unwrap()
+                .stalled_on,
+            None
+        );
+        assert!(game.drain_events().contains(&GameEvent::ConverterResumed {
+            index: sawmill,
+            name: "Sawmill".to_string(),
+        }));
+        // Manual crafting is tracked separately
+        assert_eq!(game.statistics.total_resources_crafted, 0);
+    }
+
+    #[test]
+    fn test_converters_run_offline_and_persist() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        let sawmill = game
+            .buildings
+            .iter()
+            .position(|b| b.id == "sawmill")
+            .unwrap();
+        game.state.resources.set_amount("coins", 2_000.0);
+        assert!(game.buy_building(sawmill));
+        assert!(game.buy_building(sawmill));
+        game.state.resources.set_amount("coins", 10_000.0);
+
+        let report = game.apply_offline_progress(1000.0);
+        let wood = game.get_wood();
+        assert!(wood > 0.0);
+        assert_eq!(game.get_coins() + wood * 10.0, 10_000.0);
+        assert_eq!(report.resources_gained["wood"], wood);
+
+        game.clock.advance_secs(2.0);
+        game.game_loop();
+        let progress = game.buildings[sawmill].converter.as_ref().unwrap().progress;
+        assert!(progress > 0.0 && progress < 1.0);
+
+        let json = serde_json::to_string(&game.save_game()).unwrap();
+        let mut restored = TestGameState::new();
+        restored.load_json(&json).unwrap();
+        assert_eq!(
+            restored.buildings[sawmill].converter,
+            game.buildings[sawmill].converter
+        );
+
+        let mut tampered = serde_json::to_value(game.save_game()).unwrap();
+        tampered["buildings"][sawmill]["converter"]["progress"] = serde_json::json!(1e12);
+        let mut strict = TestGameState::new();
+        match strict.load_json_with_mode(&tampered.to_string(), LoadMode::Strict) {
+            Err(SaveError::Invalid { problems }) => assert_eq!(
+                problems,
+                vec![SaveProblem::InvalidValue {
+                    field: "buildings.sawmill.converter.progress".to_string(),
+                    value: 1e12,
+                }]
+            ),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        let mut repaired = TestGameState::new();
+        repaired.load_json(&tampered.to_string()).unwrap();
+        let converter = repaired.buildings[sawmill].converter.as_ref().unwrap();
+        assert_eq!(converter.progress, 0.0);
+
+        let json = r#"{"buildings": [{"id": "press", "name": "Press", "base_cost": 1,
+            "converter": {"recipe": "nothing", "rate": 1}}]}"#;
+        assert_eq!(
+            GameContent::embedded_with_override(json),
+            Err(ContentError::UnknownRecipe {
+                kind: "building",
+                id: "press".to_string(),
+                recipe: "nothing".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_offline_consumers_stop_when_input_runs_out() {
+        let json = r#"{"buildings": [{"id": "smelter", "name": "Smelter", "base_cost": 10.0,
+            "inputs": [{"resource": "stone", "rate": 1.0}],
+            "outputs": [{"resource": "coins", "rate": 10.0}]}]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        let smelter = game
+            .buildings
+            .iter()
+            .position(|b| b.id == "smelter")
+            .unwrap();
+        game.state.resources.set_amount("coins", 10.0);
+        assert!(game.buy_building(smelter));
+        game.state.resources.set_amount("stone", 10.0);
+        game.update_production();
+
+        // Ten stone pay for ten seconds of the hour away, not the whole hour
+        game.offline_config.efficiency = 1.0;
+        game.clock.advance_secs(3600.0);
+        game.game_loop();
+
+        let report = game.take_offline_report().unwrap();
+        assert!((game.get_coins() - 100.0).abs() < 1e-9);
+        assert!(game.get_stone().abs() < 1e-9);
+        assert!((report.resources_gained["coins"] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_crafted_resources_count_as_earned() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        let lumber_mill = game
+            .buildings
+            .iter()
+            .position(|b| b.id == "lumber_mill")
+            .unwrap();
+        game.state.resources.set_amount("coins", 1_000.0);
+        assert!(game.buy_building(lumber_mill));
+        game.state.resources.set_amount("coins", 100.0);
+        game.state.resources.set_amount("wood", 10.0);
+        game.state.resources.set_amount("stone", 2.0);
+
+        assert!(game.craft_resource("craft_tools"));
+        game.finish_crafts();
+        let tools = game.get_resource("tools");
+        assert!(tools >= 1.0);
+        assert_eq!(game.stat(&StatKey::Earned("tools".to_string())), tools);
+
+        // A refund gives back what was spent, it is not a gain
+        game.state.resources.set_amount("coins", 100.0);
+        assert!(game.craft_resource("coins_to_wood"));
+        let coins_earned = game.statistics.earned("coins");
+        assert!(game.cancel_craft(0));
+        assert_eq!(game.statistics.earned("coins"), coins_earned);
+
+        let code = game.export_code_as(SaveFormat::Compact);
+        let mut restored = TestGameState::new();
+        restored.import_code(&code, LoadMode::Strict).unwrap();
+        assert_eq!(restored.statistics.earned("tools"), tools);
+    }
+
+    #[test]
+    fn test_validation_caps_levels_and_worker_assignments() {
+        let mut game = TestGameState::new();
+        game.unlock_all_features();
+        for index in 0..game.workers.len() {
+            assert!(game.assign_worker(index, "coin_mine"));
+        }
+        let workstation = game
+            .prestige_upgrades
+            .iter()
+            .position(|u| u.id == "extra_workstation")
+            .unwrap();
+        // 1000 levels with a cost to match, granting 1005 slots if believed
+        let mut json = serde_json::to_value(game.save_game()).unwrap();
+        let upgrade = &mut json["prestige_upgrades"][workstation];
+        upgrade["owned"] = serde_json::json!(1000);
+        let cost = BigNum::from_f64(3.0) * BigNum::from_f64(3.0).powi(1000);
+        upgrade["cost"] = serde_json::to_value(cost).unwrap();
+
+        let mut strict = TestGameState::new();
+        match strict.load_json_with_mode(&json.to_string(), LoadMode::Strict) {
+            Err(SaveError::Invalid { problems }) => {
+                assert!(problems.contains(&SaveProblem::AboveMaxLevel {
+                    kind: "prestige_upgrade".to_string(),
+                    id: "extra_workstation".to_string(),
+                    owned: 1000,
+                    max_level: 2,
+                }))
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+
+        let mut repaired = TestGameState::new();
+        repaired.load_json(&json.to_string()).unwrap();
+        let upgrade = &repaired.prestige_upgrades[workstation];
+        assert_eq!(upgrade.owned, 2);
+        assert_eq!(upgrade.cost, BigNum::from_f64(27.0));
+        assert_eq!(repaired.worker_slots(), 7);
+
+        // Without the workstation every default worker still fits
+        json["prestige_upgrades"][workstation]["owned"] = serde_json::json!(0);
+        json["prestige_upgrades"][workstation]["cost"] = serde_json::json!(3.0);
+        let mut extra = json["workers"][0].clone();
+        extra["id"] = serde_json::json!("extra");
+        json["workers"].as_array_mut().unwrap().push(extra);
+        assert_eq!(
+            validation::validate(
+                &SavedGame::from_json(&json.to_string()).unwrap(),
+                game.content()
+            ),
+            vec![SaveProblem::ExcessAssignment {
+                worker: "extra".to_string(),
+                building: "coin_mine".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_converters_do_not_run_locked_recipes() {
+        let json = r#"{
+            "recipes": [{"id": "press_stone", "name": "Press", "requires": "advanced_buildings",
+                "inputs": [{"resource": "wood", "amount": 1}],
+                "outputs": [{"resource": "stone", "amount": 1}], "duration": 1}],
+            "buildings": [{"id": "press", "name": "Press", "base_cost": 1,
+                "converter": {"recipe": "press_stone", "rate": 1}}]
+        }"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        let press = game.buildings.iter().position(|b| b.id == "press").unwrap();
+        game.buildings[press].count = 1;
+        game.state.resources.set_amount("wood", 10.0);
+        game.drain_events();
+
+        game.clock.advance_secs(3.0);
+        game.game_loop();
+        assert_eq!(game.get_wood(), 10.0);
+        assert_eq!(game.get_stone(), 0.0);
+        assert!(game.buildings[press].converter.as_ref().unwrap().locked);
+        assert!(game.drain_events().contains(&GameEvent::ConverterLocked {
+            index: press,
+            name: "Press".to_string(),
+            feature: Some("advanced_buildings".to_string()),
+        }));
+
+        game.unlock_all_features();
+        game.clock.advance_secs(3.0);
+        game.game_loop();
+        assert_eq!(game.get_wood(), 7.0);
+        assert_eq!(game.get_stone(), 3.0);
+        assert!(!game.buildings[press].converter.as_ref().unwrap().locked);
+        assert!(game.drain_events().contains(&GameEvent::ConverterResumed {
+            index: press,
+            name: "Press".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_prestige_checks_milestones_at_once() {
+        let json = r#"{"achievements": [{"id": "reborn", "name": "Reborn",
+            "description": "", "category": "prestige",
+            "condition": {"type": "stat", "stat": "prestige_count", "value": 1}}]}"#;
+        let mut game =
+            TestGameState::with_content(GameContent::embedded_with_override(json).unwrap());
+        game.statistics.total_coins_earned = BigNum::from_f64(10_000.0);
+
+        assert!(game.prestige());
+        let reborn = game.achievements.iter().find(|a| a.id == "reborn").unwrap();
+        assert!(reborn.unlocked);
+    }
 }
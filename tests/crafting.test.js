@@ -385,8 +385,9 @@ test.describe('Crafting System', () => {
 
         if (Array.isArray(recipes) && recipes.length > 0) {
             for (const recipe of recipes) {
-                const inputAmount = recipe.input_amount || 0;
-                const outputAmount = recipe.output_amount || 0;
+                const total = items => (items || []).reduce((sum, item) => sum + item.amount, 0);
+                const inputAmount = total(recipe.inputs);
+                const outputAmount = total(recipe.outputs);
                 
                 const ratio = inputAmount / outputAmount;
                 console.log(`Recipe: ${recipe.name || recipe.id} - ${inputAmount} -> ${outputAmount} (ratio: ${ratio})`);
@@ -297,7 +297,7 @@ test.describe('Unlock System', () => {
         });
 
         if (Array.isArray(unlocks) && unlocks.length > 0) {
-            const thresholds = unlocks.map(u => u.requirement_value || 0).filter(v => v > 0);
+            const thresholds = unlocks.map(u => u.requirement || 0).filter(v => v > 0);
             
             console.log('Unlock thresholds:', thresholds);
             